@@ -1,14 +1,64 @@
 // src/app.rs
+pub mod actions;
+pub mod clipboard;
+pub mod color;
+pub mod config;
 pub mod cursor;
+pub mod diff;
+pub mod diff_view;
+pub mod document;
 pub mod editor;
+pub mod editorconfig;
+pub mod explorer;
 pub mod features;
+pub mod finder;
+pub mod fuzzy;
+pub mod git;
+pub mod history;
+pub mod hunks;
+pub mod input;
+pub mod keymap;
+pub mod keys;
+pub mod language;
+pub mod mouse;
 pub mod msg;
+pub mod palette;
+pub mod search;
+pub mod settings;
+pub mod syntax;
+pub mod tabs;
+pub mod text_buffer;
+pub mod theme;
+pub mod window;
+use clipboard::Clipboard;
+use color::ColorDepth;
+use diff::DiffBase;
+use diff_view::DiffView;
+use document::Document;
 use editor::Editor;
+use editorconfig::{Charset, EditorConfig};
+use explorer::Explorer;
+use finder::Finder;
+use git::GitRepo;
+use history::History;
+use input::InputLine;
+use keymap::{KeybindingsView, Keymap};
+use keys::KeyChord;
+use language::LanguageRegistry;
+use mouse::MouseState;
+use palette::CommandPalette;
+use search::SearchPrompt;
+use settings::{Settings, SettingsFile};
 use std::env;
 use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant}; // DurationとInstantをインポート
+use syntax::Syntax;
+use tabs::CloseConfirm;
+use text_buffer::TextBuffer;
+use theme::Theme;
+use window::Windows;
 
 // msg!とemsg!マクロをインポート
 use crate::{emsg, msg};
@@ -24,44 +74,85 @@ pub enum MessageType {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LineStatus {
     Unchanged,
-    Modified, // 変更された行
-    Added,    // 新しく追加された行
-              // Deletedは現在のバッファに行が存在しないため、このVecでは直接表現しません。
-              // 左ブロックや右ブロックで、original_bufferと比較して空白または特殊記号で表現することを検討します。
+    Modified,     // 変更された行
+    Added,        // 新しく追加された行
+    DeletedAbove, // 変更のない行だが、この行の直前で行が削除されている
+    DeletedBelow, // 変更のない最終行だが、この行の後ろで行が削除されている
 }
 
 /// アプリケーション全体の状態を管理します。
 pub struct App {
-    pub editor: Editor,
-    pub target_path: Option<PathBuf>, // 編集対象の元のファイルのパス
-    pub temp_path: Option<PathBuf>,   // 編集中の内容を保存する一時ファイルのパス
-    pub clipboard: Option<String>,    // アプリケーション内のクリップボードデータ
+    pub documents: Vec<Document>, // 開いているファイルの一覧（タブの並び順）。常に1つ以上ある
+    pub active_document: usize, // 編集中のファイル（フォーカスのあるペインのファイル）のインデックス
+    pub windows: Windows,       // エディタ領域の分割（ペイン）の状態
+    pub clipboard: Clipboard,   // コピーと貼り付けに使うクリップボード
     pub messages: Vec<(MessageType, String, Instant)>, // UIに表示するメッセージのキュー (種類, 内容, タイムスタンプ)
-    pub original_buffer: String, // ファイル読み込み時のオリジナルコンテンツ（差分計算用）
-    pub word_wrap_enabled: bool, // 折り返し表示モードのON/OFF
-    pub line_statuses: Vec<LineStatus>, // 各行の差分状態
+    pub word_wrap_enabled: bool,                       // 折り返し表示モードのON/OFF
+    pub hunk_preview_visible: bool, // カーソル行の変更箇所の比較元の内容をポップアップ表示するか
+    pub diff_view: Option<DiffView>, // 保存済みの内容との差分表示（表示中のみSome）
+    pub search: Option<SearchPrompt>, // 検索バーの状態（開いている場合のみSome）
+    pub open_file_prompt: Option<InputLine>, // 「ファイルを開く」のパスの入力欄（開いている場合のみSome）
+    pub languages: LanguageRegistry,         // 利用できる言語の一覧
+    pub explorer: Explorer,                  // サイドバーのファイルツリー
+    pub finder: Option<Finder>,              // ファイルのあいまい検索（開いている場合のみSome）
+    pub palette: Option<CommandPalette>,     // コマンドパレット（開いている場合のみSome）
+    pub keymap: Keymap,                      // キー割り当て
+    pub pending_keys: Vec<KeyChord>,         // 続けて押すキーの並びのうち、入力済みのキー
+    pub keybindings_view: Option<KeybindingsView>, // キー割り当ての一覧（表示中のみSome）
+    pub settings: Settings, // 言語を指定しない設定（全体の設定にプロジェクトの設定を重ねたもの）
+    pub settings_files: Vec<SettingsFile>, // 読み込んだ設定ファイル（優先度の低い順）
+    pub theme: Theme,       // 画面の色（テーマ）
+    pub color_depth: ColorDepth, // 端末で表示できる色の数
+    pub mouse: MouseState,  // マウス操作の状態（ダブルクリックの判定やドラッグ中の操作）
+    pub close_confirm: Option<CloseConfirm>, // 保存していない変更を破棄する前の確認（確認中のみSome）
+    pub quit_requested: bool,                // 終了が要求されたか
 }
 
 impl Default for App {
     fn default() -> Self {
         Self {
-            editor: Editor::new(String::new()),
-            target_path: None,
-            temp_path: None,
-            clipboard: None,
+            documents: vec![Document::default()], // 空のバッファを1つ開いた状態
+            active_document: 0,
+            windows: Windows::default(),
+            clipboard: Clipboard::default(),
             messages: Vec::new(),
-            original_buffer: String::new(), // 初期化
-            word_wrap_enabled: true,        // デフォルトで折り返し表示を有効
-            line_statuses: Vec::new(),      // 初期化
+            word_wrap_enabled: true, // デフォルトで折り返し表示を有効
+            hunk_preview_visible: false,
+            diff_view: None,
+            search: None,
+            open_file_prompt: None,
+            languages: LanguageRegistry::default(),
+            explorer: Explorer::default(),
+            finder: None,
+            palette: None,
+            keymap: Keymap::default(),
+            pending_keys: Vec::new(),
+            keybindings_view: None,
+            settings: Settings::default(),
+            settings_files: Vec::new(),
+            theme: Theme::default(),
+            color_depth: ColorDepth::TrueColor,
+            mouse: MouseState::default(),
+            close_confirm: None,
+            quit_requested: false,
         }
     }
 }
 
-/// Appがスコープを抜ける際に一時ファイルを削除するためのDrop実装
+/// Appがスコープを抜ける際に各ファイルのUndo履歴を保存し、一時ファイルを削除するためのDrop実装
 impl Drop for App {
     fn drop(&mut self) {
-        if let Some(path) = &self.temp_path {
-            if path.exists() {
+        for document in &self.documents {
+            // 次回同じファイルを開いたときに編集を元に戻せるよう、Undo履歴を残しておく
+            // 一時ファイルは削除するため、次回は元のファイルの内容から始まる履歴にする
+            if let Some(path) = &document.history_path
+                && let Err(e) = document.write_history_for_disk(path)
+            {
+                eprintln!("Undo履歴 {:?} の保存中にエラーが発生しました: {}", path, e);
+            }
+            if let Some(path) = &document.temp_path
+                && path.exists()
+            {
                 if let Err(e) = fs::remove_file(path) {
                     eprintln!(
                         "一時ファイル {:?} の削除中にエラーが発生しました: {}",
@@ -76,155 +167,244 @@ impl Drop for App {
 }
 
 impl App {
-    /// アプリケーションを初期化します。コマンドライン引数からファイルパスを読み込みます。
+    /// アプリケーションを初期化します。コマンドライン引数で指定された全てのファイルを開きます。
     pub fn init() -> Self {
         let mut app = Self::default();
+        app.load_settings(); // 全体の設定とプロジェクトの設定を反映する
+        app.load_keymap(); // 設定ファイルのキー割り当てを反映する
 
-        let args: Vec<String> = env::args().collect();
-        let file_path_str_opt = args.get(1);
-
-        if let Some(file_path_str) = file_path_str_opt {
-            let original_path = PathBuf::from(file_path_str);
-            app.target_path = Some(original_path.clone());
-
-            let temp_filename = format!(
-                ".{}.inedit",
-                original_path
-                    .file_name()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("untitled")
-            );
-            let temp_file = PathBuf::from(".");
-            let temp_path = original_path
-                .parent()
-                .unwrap_or(temp_file.as_path())
-                .join(temp_filename);
-            app.temp_path = Some(temp_path.clone());
-
-            msg!(app, "元のファイルパス: {:?}", original_path);
-            msg!(app, "一時ファイルパス: {:?}", temp_path);
+        let args: Vec<String> = env::args().skip(1).collect();
+        if args.is_empty() {
             msg!(
                 app,
-                "ファイルの行数: {}",
-                app.editor.buffer.lines().collect::<Vec<&str>>().len()
+                "ファイルパスが指定されていません。空のバッファ（プレーンテキストモード）で開始します。一時ファイルは作成されません。"
             );
+            app.detect_language(); // ファイル名と1行目の内容から言語を判定
+            app.init_diff_base(); // 差分の比較元を決めて、初期化時に差分状態を計算
+            return app;
+        }
+        for file_path_str in &args {
+            app.open_file(Path::new(file_path_str));
+        }
+        app.switch_document(0); // 最初に指定されたファイルを表示する
+        app
+    }
 
-            // まず一時ファイルからの読み込みを試みる
-            if temp_path.exists() {
-                match app.editor.load_from_file(&temp_path) {
-                    Ok(_) => {
-                        msg!(
-                            app,
-                            "一時ファイル {:?} から正常に読み込みました。",
-                            temp_path
-                        );
-                        app.original_buffer = app.editor.buffer.clone(); // original_bufferも設定
-                        app.calculate_diff_status();
-                        return app;
-                    }
-                    Err(e) => {
-                        emsg!(
-                            app,
-                            "一時ファイル {:?} の読み込み中にエラーが発生しました: {}。元のファイルに戻ります。",
-                            temp_path,
-                            e
-                        );
-                    }
+    /// 編集中のファイルの状態を返します。
+    pub fn document(&self) -> &Document {
+        &self.documents[self.active_document]
+    }
+
+    /// 編集中のファイルの状態を返します。
+    pub fn document_mut(&mut self) -> &mut Document {
+        &mut self.documents[self.active_document]
+    }
+
+    /// 編集中のファイルのエディタを返します。
+    pub fn editor(&self) -> &Editor {
+        &self.document().editor
+    }
+
+    /// 編集中のファイルのエディタを返します。
+    pub fn editor_mut(&mut self) -> &mut Editor {
+        &mut self.document_mut().editor
+    }
+
+    /// 指定されたファイルを新しいバッファとして読み込み、編集中のファイルにします。
+    /// 編集内容を保存する一時ファイルがあればそこから、なければ元のファイルから読み込みます。
+    fn load_document(&mut self, original_path: PathBuf) {
+        let temp_path = companion_path(&original_path, "inedit");
+        let history_path = companion_path(&original_path, "inedit-undo");
+
+        self.documents.push(Document {
+            target_path: Some(original_path.clone()),
+            temp_path: Some(temp_path.clone()),
+            history_path: Some(history_path),
+            ..Document::default()
+        });
+        self.active_document = self.documents.len() - 1;
+        self.windows.focused_pane_mut().document = self.active_document;
+
+        msg!(self, "元のファイルパス: {:?}", original_path);
+        msg!(self, "一時ファイルパス: {:?}", temp_path);
+
+        // 元のファイルの文字コードを判別する（latin1 は .editorconfig で指定されている場合のみ）
+        // 判別できないファイルは、保存で内容を壊さないよう保存できないようにする
+        let hint = EditorConfig::for_path(&original_path).0.charset;
+        let original = match fs::read(&original_path) {
+            Ok(bytes) => match editorconfig::decode(&bytes, hint) {
+                Ok((text, charset)) => Some((text, Some(charset))),
+                Err(e) => {
+                    emsg!(
+                        self,
+                        "{:?} の文字コードを判別できないため、このファイルは保存できません: {}",
+                        original_path,
+                        e
+                    );
+                    Some((String::new(), None))
+                }
+            },
+            Err(_) => None, // 新しいファイル（または読み込めないファイル。後で読み込むときに報告する）
+        };
+        if let Some((content, encoding)) = &original {
+            let document = self.document_mut();
+            document.encoding = *encoding;
+            document.disk_hash = encoding.map(|_| TextBuffer::new(content).content_hash());
+        }
+
+        // まず一時ファイルからの読み込みを試みる（一時ファイルは常にUTF-8）
+        if temp_path.exists() {
+            match self.editor_mut().load_from_file(&temp_path, None) {
+                Ok(_) => {
+                    msg!(
+                        self,
+                        "一時ファイル {:?} から正常に読み込みました。",
+                        temp_path
+                    );
+                    let document = self.document_mut();
+                    document.snapshot_buffer = document.editor.buffer.to_string(); // 差分の比較元も設定
+                    // 元のファイルと内容が異なれば、保存していない変更を復元したことになる
+                    let saved = original.as_ref().is_some_and(|(content, encoding)| {
+                        encoding.is_some() && *content == document.snapshot_buffer
+                    });
+                    document.saved_revision = saved.then_some(document.editor.revision);
+                    self.restore_history();
+                    self.finish_loading();
+                    return;
+                }
+                Err(e) => {
+                    emsg!(
+                        self,
+                        "一時ファイル {:?} の読み込み中にエラーが発生しました: {}。元のファイルに戻ります。",
+                        temp_path,
+                        e
+                    );
                 }
             }
+        }
 
-            // 一時ファイルが存在しないか読み込みに失敗した場合、元のファイルを試す
-            if original_path.exists() {
-                match app.editor.load_from_file(&original_path) {
-                    Ok(_) => {
-                        msg!(
-                            app,
-                            "元のファイル {:?} を正常に読み込みました。",
-                            original_path
-                        );
-                        app.original_buffer = app.editor.buffer.clone(); // original_bufferも設定
-                        // 元のファイルを読み込んだら、その内容をすぐに一時ファイルに書き込む
-                        if let Err(e) = app.editor.save_to_file(&temp_path) {
-                            emsg!(
-                                app,
-                                "警告: 初期コンテンツを一時ファイル {:?} に書き込めませんでした: {}",
-                                temp_path,
-                                e
-                            );
-                        } else {
-                            msg!(
-                                app,
-                                "初期コンテンツを一時ファイル {:?} に書き込みました。",
-                                temp_path
-                            );
-                        }
-                    }
-                    Err(e) => {
+        // 一時ファイルが存在しないか読み込みに失敗した場合、元のファイルを試す
+        if original_path.exists() {
+            match self.editor_mut().load_from_file(&original_path, hint) {
+                Ok(_) => {
+                    msg!(
+                        self,
+                        "元のファイル {:?} を正常に読み込みました。",
+                        original_path
+                    );
+                    let document = self.document_mut();
+                    document.snapshot_buffer = document.editor.buffer.to_string(); // 差分の比較元も設定
+                    document.saved_revision = Some(document.editor.revision);
+                    self.restore_history();
+                    // 元のファイルを読み込んだら、その内容をすぐに一時ファイルに書き込む
+                    if let Err(e) = self.editor_mut().save_to_file(
+                        &temp_path,
+                        &EditorConfig::default(),
+                        Charset::Utf8,
+                    ) {
                         emsg!(
-                            app,
-                            "元のファイル {:?} の読み込み中にエラーが発生しました: {}。空のバッファで開始します。",
-                            original_path,
+                            self,
+                            "警告: 初期コンテンツを一時ファイル {:?} に書き込めませんでした: {}",
+                            temp_path,
                             e
                         );
-                        if let Err(e) = fs::write(&temp_path, "") {
-                            emsg!(
-                                app,
-                                "警告: 空の一時ファイル {:?} を作成できませんでした: {}",
-                                temp_path,
-                                e
-                            );
-                        }
+                    } else {
+                        msg!(
+                            self,
+                            "初期コンテンツを一時ファイル {:?} に書き込みました。",
+                            temp_path
+                        );
                     }
                 }
-            } else {
-                msg!(
-                    app,
-                    "元のファイルが存在しません: {:?}。新しいファイルバッファと一時ファイルを作成します。",
-                    file_path_str
-                );
-                if let Err(e) = fs::write(&temp_path, "") {
+                Err(e) => {
                     emsg!(
-                        app,
-                        "警告: 空の一時ファイル {:?} を作成できませんでした: {}",
-                        temp_path,
+                        self,
+                        "元のファイル {:?} の読み込み中にエラーが発生しました: {}。空のバッファで開始します。",
+                        original_path,
                         e
                     );
+                    if let Err(e) = fs::write(&temp_path, "") {
+                        emsg!(
+                            self,
+                            "警告: 空の一時ファイル {:?} を作成できませんでした: {}",
+                            temp_path,
+                            e
+                        );
+                    }
                 }
             }
         } else {
             msg!(
-                app,
-                "ファイルパスが指定されていません。空のバッファ（プレーンテキストモード）で開始します。一時ファイルは作成されません。"
+                self,
+                "元のファイルが存在しません: {:?}。新しいファイルバッファと一時ファイルを作成します。",
+                original_path
             );
+            if let Err(e) = fs::write(&temp_path, "") {
+                emsg!(
+                    self,
+                    "警告: 空の一時ファイル {:?} を作成できませんでした: {}",
+                    temp_path,
+                    e
+                );
+            }
         }
-        app.calculate_diff_status(); // 初期化時に差分状態を計算
-        app
+        self.finish_loading();
+    }
+
+    /// 読み込んだファイルの言語と差分の比較元を決めます。
+    fn finish_loading(&mut self) {
+        self.detect_language(); // ファイル名と1行目の内容から言語を判定
+        self.load_editorconfig(self.active_document); // .editorconfig のインデントなどの指定を反映する
+        self.init_diff_base(); // 差分の比較元を決めて、初期化時に差分状態を計算
+        let document = self.document_mut();
+        document.recovery_revision = document.editor.revision; // 一時ファイルは読み込んだ内容と一致している
     }
 
     /// 現在のファイルを保存します。元のファイルパスが設定されている必要があります。
     pub fn save_current_file(&mut self) -> io::Result<()> {
-        if let Some(original_path) = &self.target_path {
-            self.editor.save_to_file(original_path)?;
+        if let Some(original_path) = self.document().target_path.clone() {
+            let config = self.document().editorconfig.clone();
+            let Some(encoding) = self.document().encoding else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "文字コードを判別できなかったファイルは、内容を壊さないよう保存しません",
+                ));
+            };
+            self.editor_mut()
+                .save_to_file(&original_path, &config, encoding)?;
             msg!(self, "ファイルは {:?} に保存されました。", original_path);
-            self.original_buffer = self.editor.buffer.clone(); // 保存後、オリジナルバッファを更新
-            self.calculate_diff_status(); // 差分状態を再計算
+            let document = self.document_mut();
+            document.snapshot_buffer = document.editor.buffer.to_string(); // 保存後、スナップショットを更新
+            document.saved_revision = Some(document.editor.revision);
+            document.recovery_revision = document.editor.revision; // 保存した内容を一時ファイルに書き直す必要はない
+            // 改行コードを変換して書き込んだ場合もあるため、書き込んだ内容を読み直してハッシュを求める
+            document.disk_hash = fs::read(&original_path)
+                .ok()
+                .and_then(|bytes| {
+                    editorconfig::decode(&bytes, Some(config.charset.unwrap_or(encoding))).ok()
+                })
+                .map(|(content, _)| TextBuffer::new(&content).content_hash());
+            document.editor.history.seal(); // 保存した内容にUndoでちょうど戻れるようにする
+            self.reload_diff_base(); // 差分の比較元を読み直し、差分状態を再計算
+            self.refresh_diff_view(); // 差分表示中なら、保存した内容との差分に更新
+            self.save_history(self.active_document); // 保存した内容に対応するUndo履歴を残す
 
-            if let Some(temp_path) = &self.temp_path {
-                if temp_path.exists() {
-                    if let Err(e) = fs::remove_file(temp_path) {
-                        emsg!(
-                            self,
-                            "警告: 一時ファイル {:?} を削除できませんでした: {}",
-                            temp_path,
-                            e
-                        );
-                    } else {
-                        msg!(
-                            self,
-                            "一時ファイル {:?} は正常な保存後に削除されました。",
-                            temp_path
-                        );
-                    }
+            if let Some(temp_path) = self.document().temp_path.clone()
+                && temp_path.exists()
+            {
+                if let Err(e) = fs::remove_file(&temp_path) {
+                    emsg!(
+                        self,
+                        "警告: 一時ファイル {:?} を削除できませんでした: {}",
+                        temp_path,
+                        e
+                    );
+                } else {
+                    msg!(
+                        self,
+                        "一時ファイル {:?} は正常な保存後に削除されました。",
+                        temp_path
+                    );
                 }
             }
             Ok(())
@@ -239,43 +419,250 @@ impl App {
         }
     }
 
+    /// 開いている各ファイルについて、編集中の内容とUndo履歴を一時ファイルに書き込みます。
+    /// 前回の書き込みから内容が変わっていない場合や、一定時間が経過していない場合は何もしません。
+    /// クラッシュした場合でも、次回起動時に一時ファイルから内容と履歴を復元できます。
+    pub fn update_recovery_files(&mut self) {
+        const RECOVERY_INTERVAL_SECS: u64 = 2; // 一時ファイルを書き込む最小間隔（秒）
+        for index in 0..self.documents.len() {
+            let document = &mut self.documents[index];
+            if document.editor.revision == document.recovery_revision
+                || document.last_recovery_write.elapsed()
+                    < Duration::from_secs(RECOVERY_INTERVAL_SECS)
+            {
+                continue;
+            }
+            let Some(temp_path) = document.temp_path.clone() else {
+                continue;
+            };
+
+            document.last_recovery_write = Instant::now();
+            document.recovery_revision = document.editor.revision;
+            if let Err(e) =
+                document
+                    .editor
+                    .save_to_file(&temp_path, &EditorConfig::default(), Charset::Utf8)
+            {
+                emsg!(
+                    self,
+                    "警告: 一時ファイル {:?} に書き込めませんでした: {}",
+                    temp_path,
+                    e
+                );
+                continue;
+            }
+            self.save_history(index);
+        }
+    }
+
+    /// 編集対象のファイル名と1行目の内容から言語を判定し、
+    /// その言語に文法があれば構文ハイライトを有効にします。
+    pub fn detect_language(&mut self) {
+        let first_line = self.editor().buffer.line(0).to_string();
+        let language = self
+            .languages
+            .detect(self.document().target_path.as_deref(), &first_line)
+            .cloned();
+        self.editor_mut().syntax = None;
+        if let Some(language) = &language
+            && let Some(grammar) = language.grammar
+        {
+            match Syntax::new(&grammar(), language.highlights_query) {
+                Ok(syntax) => self.editor_mut().syntax = Some(syntax),
+                Err(e) => emsg!(
+                    self,
+                    "{} の構文ハイライトを初期化できませんでした: {}",
+                    language.name,
+                    e
+                ),
+            }
+        }
+        self.editor_mut().language = language;
+    }
+
+    /// 保存されているUndo履歴を読み込み、エディタに復元します。
+    /// 履歴を保存した時点から内容が変わっている場合、その履歴は破棄されます。
+    fn restore_history(&mut self) {
+        let Some(path) = self.document().history_path.clone() else {
+            return;
+        };
+        if !path.exists() {
+            return;
+        }
+        match History::load_from_file(&path, self.editor().buffer.content_hash()) {
+            Ok(Some(history)) => {
+                self.editor_mut().history = history;
+                self.editor_mut().history.seal(); // 読み込んだ内容にUndoでちょうど戻れるようにする
+                msg!(self, "前回のセッションのUndo履歴を復元しました。");
+            }
+            Ok(None) => {
+                // ファイルの内容が履歴と一致しないため、古い履歴は使えない
+                if let Err(e) = fs::remove_file(&path) {
+                    emsg!(
+                        self,
+                        "警告: 古いUndo履歴 {:?} を削除できませんでした: {}",
+                        path,
+                        e
+                    );
+                } else {
+                    msg!(
+                        self,
+                        "ファイルが変更されているため、保存されていたUndo履歴を破棄しました。"
+                    );
+                }
+            }
+            Err(e) => {
+                emsg!(
+                    self,
+                    "Undo履歴 {:?} の読み込み中にエラーが発生しました: {}",
+                    path,
+                    e
+                );
+            }
+        }
+    }
+
+    /// 指定されたファイルのUndo履歴を履歴ファイルに保存します。
+    fn save_history(&mut self, index: usize) {
+        let document = &self.documents[index];
+        let Some(path) = &document.history_path else {
+            return;
+        };
+        if let Err(e) = document.write_history(path) {
+            emsg!(
+                self,
+                "警告: Undo履歴 {:?} を保存できませんでした: {}",
+                path,
+                e
+            );
+        }
+    }
+
     /// メッセージキューに新しいメッセージを追加します。
     pub fn add_message(&mut self, message_type: MessageType, msg: String) {
         self.messages.push((message_type, msg, Instant::now()));
     }
 
-    /// 現在のバッファとオリジナルバッファを比較し、各行の差分状態を計算します。
-    /// （簡易的な行ごとの比較で、行の挿入・削除によるズレは考慮しません。）
-    pub fn calculate_diff_status(&mut self) {
-        self.line_statuses.clear();
-        let original_lines: Vec<&str> = self.original_buffer.lines().collect();
-        let current_lines: Vec<&str> = self.editor.buffer.lines().collect();
-
-        for (i, current_line) in current_lines.iter().enumerate() {
-            if let Some(original_line) = original_lines.get(i) {
-                if current_line == original_line {
-                    self.line_statuses.push(LineStatus::Unchanged);
-                } else {
-                    self.line_statuses.push(LineStatus::Modified);
+    /// 編集対象のファイルがgitリポジトリで管理されていればHEADを、そうでなければ
+    /// 読み込み時のスナップショットを差分の比較元にします。
+    fn init_diff_base(&mut self) {
+        let document = self.document_mut();
+        document.git_repo = document.target_path.as_deref().and_then(GitRepo::discover);
+        document.diff_base = match document.read_git_file(DiffBase::Head) {
+            Ok(Some(_)) => DiffBase::Head,
+            _ => DiffBase::Snapshot,
+        };
+        self.reload_diff_base();
+    }
+
+    /// 差分の比較元を次の候補（スナップショット → HEAD → インデックス）に切り替えます。
+    /// gitリポジトリの外にあるファイルでは、スナップショットのみが選べます。
+    pub fn cycle_diff_base(&mut self) {
+        if self.document().git_repo.is_none() {
+            msg!(
+                self,
+                "gitリポジトリ内のファイルではないため、比較元を切り替えられません。"
+            );
+            return;
+        }
+        let document = self.document_mut();
+        document.diff_base = match document.diff_base {
+            DiffBase::Snapshot => DiffBase::Head,
+            DiffBase::Head => DiffBase::Index,
+            DiffBase::Index => DiffBase::Snapshot,
+        };
+        self.reload_diff_base();
+        msg!(self, "差分の比較元: {}", self.document().diff_base.label());
+    }
+
+    /// 現在の比較元の内容をoriginal_bufferに読み込み、差分を計算し直します。
+    /// gitから読み込めなかった場合はエラーを表示し、スナップショットとの比較に戻ります。
+    pub fn reload_diff_base(&mut self) {
+        let document = self.document();
+        let original_buffer = match document.diff_base {
+            DiffBase::Snapshot => document.snapshot_buffer.clone(),
+            base => match document.read_git_file(base) {
+                // 比較元にファイルがない（まだ追加されていない）場合は、全ての行を追加として扱う
+                Ok(content) => String::from_utf8_lossy(&content.unwrap_or_default()).into_owned(),
+                Err(e) => {
+                    emsg!(self, "{} の内容を読み込めませんでした: {}", base.label(), e);
+                    self.document_mut().diff_base = DiffBase::Snapshot;
+                    self.document().snapshot_buffer.clone()
                 }
-            } else {
-                // original_linesよりもcurrent_linesが長い場合、これは追加された行
-                self.line_statuses.push(LineStatus::Added);
-            }
+            },
+        };
+        let document = self.document_mut();
+        document.original_buffer = original_buffer;
+        document.calculate_diff_status();
+    }
+
+    /// 開いている各ファイルについて、編集されていれば差分を計算し直します。メインループから毎回呼び出します。
+    pub fn update_diff_status(&mut self) {
+        for document in &mut self.documents {
+            document.update_diff_status();
         }
-        // ここでは、original_linesに存在しcurrent_linesに存在しない行（削除された行）は
-        // line_statusesの長さには影響しません。UI側でoriginal_bufferの行数と比較して、
-        // 削除された行のギャップを表現することを検討できますが、今回は簡易的なアプローチです。
     }
 
     /// 現在表示されているメッセージの数を返します。
     /// これにより、UIはメッセージの数に応じて表示エリアのサイズを調整できます。
     pub fn get_visible_message_count(&self) -> u16 {
-        const MESSAGE_LIFETIME_SECS: u64 = 3; // メッセージの表示期間（秒）
+        let lifetime = Duration::from_secs(self.settings.message_lifetime_secs); // メッセージの表示期間
         let now = Instant::now();
         self.messages
             .iter()
-            .filter(|(_, _, timestamp)| now.duration_since(*timestamp) < Duration::from_secs(MESSAGE_LIFETIME_SECS))
+            .filter(|(_, _, timestamp)| now.duration_since(*timestamp) < lifetime)
             .count() as u16
     }
 }
+
+/// 編集対象のファイルと同じディレクトリに置く、隠しファイル（`.<ファイル名>.<拡張子>`）のパスを返します。
+/// 一時ファイルやUndo履歴のファイルに使用します。
+fn companion_path(original_path: &Path, extension: &str) -> PathBuf {
+    let filename = format!(
+        ".{}.{}",
+        original_path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("untitled"),
+        extension
+    );
+    original_path
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(filename)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 保存後に編集して終了しても、次に開いたときに保存した内容からUndoできることを確かめます。
+    #[test]
+    fn undo_history_survives_unsaved_edits_after_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        fs::write(&path, "hello\n").unwrap();
+
+        let mut app = App::default();
+        app.open_file(&path);
+        app.editor_mut().set_cursor_position(usize::MAX, 0, false);
+        for c in " world".chars() {
+            app.editor_mut().insert_char(c);
+        }
+        app.save_current_file().unwrap();
+        // 保存した直後の入力は、保存前の入力に連結されない
+        app.editor_mut().insert_char('!');
+        drop(app);
+        assert!(!companion_path(&path, "inedit").exists());
+
+        let mut app = App::default();
+        app.open_file(&path);
+        assert_eq!(app.editor().buffer.to_string(), "hello world\n");
+        assert!(app.editor_mut().undo());
+        assert_eq!(app.editor().buffer.to_string(), "hello\n");
+        // 保存していなかった変更は、やり直しで取り戻せる
+        assert!(app.editor_mut().redo());
+        assert!(app.editor_mut().redo());
+        assert_eq!(app.editor().buffer.to_string(), "hello world!\n");
+    }
+}
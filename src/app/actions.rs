@@ -0,0 +1,577 @@
+// src/app/actions.rs
+
+use super::App;
+use super::tabs::CloseConfirm;
+use super::window::{FocusDirection, SplitDirection};
+use crate::{emsg, msg};
+
+/// 名前で呼び出せるエディタの操作（コマンド）です。
+/// キー入力（キー割り当て）やコマンドパレットから、この一覧を通じて実行します。
+pub struct Action {
+    pub name: &'static str,                    // 操作の名前（例: `file.save`）
+    pub description: &'static str,             // コマンドパレットに表示する説明
+    pub default_keys: &'static [&'static str], // 既定のキー（`Ctrl+S`、`Ctrl+K Ctrl+S`などの表記）
+    pub run: fn(&mut App),
+}
+
+/// 名前で操作を探します。
+pub fn find_action(name: &str) -> Option<&'static Action> {
+    ACTIONS.iter().find(|action| action.name == name)
+}
+
+impl App {
+    /// アプリケーションの終了を要求します。（イベント処理の後に終了します）
+    /// 保存していない変更のあるファイルがある場合は、終了する前に確認します。
+    pub fn request_quit(&mut self) {
+        let modified = self.documents.iter().filter(|d| d.is_modified()).count();
+        if modified > 0 {
+            msg!(
+                self,
+                "保存されていないファイルが{}個あります。y: 保存せずに終了 / s: 保存して終了 / Esc: キャンセル",
+                modified
+            );
+            self.close_confirm = Some(CloseConfirm::Quit);
+            return;
+        }
+        self.quit();
+    }
+
+    /// 保存していない変更があっても確認せずに、アプリケーションの終了を要求します。
+    pub fn quit(&mut self) {
+        msg!(self, "アプリケーションを終了します。");
+        self.quit_requested = true;
+    }
+
+    /// 名前を指定して操作を実行します。
+    pub fn run_action(&mut self, name: &str) {
+        match find_action(name) {
+            Some(action) => (action.run)(self),
+            None => emsg!(self, "不明なコマンド \"{}\" です。", name),
+        }
+    }
+
+    /// カーソル前の単語を補完します。候補に共通する部分までを入力し、候補が複数あれば一覧を表示します。
+    pub fn complete_word(&mut self) {
+        let line = self
+            .editor()
+            .buffer
+            .line(self.editor().cursor.y)
+            .to_string();
+        let prefix: String = {
+            let before: Vec<char> = line.chars().take(self.editor().cursor.x).collect();
+            let start = before
+                .iter()
+                .rposition(|c| !(c.is_alphanumeric() || *c == '_'))
+                .map_or(0, |i| i + 1);
+            before[start..].iter().collect()
+        };
+        // 入力中の単語そのものはバッファ内の単語として候補に含まれるため除く
+        let suggestions: Vec<String> = self
+            .editor()
+            .get_completion_suggestions()
+            .into_iter()
+            .filter(|suggestion| *suggestion != prefix)
+            .collect();
+        let Some(first) = suggestions.first() else {
+            msg!(self, "補完の候補がありません。");
+            return;
+        };
+        let common: String = suggestions.iter().skip(1).fold(first.clone(), |common, s| {
+            common
+                .chars()
+                .zip(s.chars())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect()
+        });
+        let rest: String = common.chars().skip(prefix.chars().count()).collect();
+        if !rest.is_empty() {
+            self.editor_mut().paste_text(&rest);
+        }
+        if suggestions.len() > 1 {
+            const MAX_LISTED: usize = 5; // メッセージに並べる候補の数
+            let listed = suggestions[..suggestions.len().min(MAX_LISTED)].join(", ");
+            let more = if suggestions.len() > MAX_LISTED {
+                ", …"
+            } else {
+                ""
+            };
+            msg!(self, "候補: {}{}", listed, more);
+        }
+    }
+
+    /// カーソル位置の括弧に対応する括弧に移動します。
+    pub fn jump_to_matching_bracket(&mut self) {
+        match self.editor().find_matching_paren() {
+            Some((y, x)) => self.editor_mut().set_cursor_position(x, y, false),
+            None => msg!(self, "対応する括弧が見つかりません。"),
+        }
+    }
+}
+
+/// 登録されている全ての操作です。
+pub static ACTIONS: &[Action] = &[
+    // アプリケーション・ファイル
+    Action {
+        name: "app.quit",
+        description: "終了する",
+        default_keys: &["Ctrl+Q"],
+        run: App::request_quit,
+    },
+    Action {
+        name: "app.escape",
+        description: "プレビューを閉じる・選択を解除する",
+        default_keys: &["Esc"],
+        run: |app| {
+            if app.hunk_preview_visible {
+                app.hunk_preview_visible = false;
+            } else {
+                app.editor_mut().cursor.clear_selection();
+            }
+        },
+    },
+    Action {
+        name: "app.command_palette",
+        description: "コマンドパレットを開く",
+        default_keys: &["Ctrl+Shift+P", "F1"],
+        run: App::open_palette,
+    },
+    Action {
+        name: "keymap.show",
+        description: "キー割り当ての一覧を表示する",
+        default_keys: &["Ctrl+K Ctrl+S"],
+        run: App::open_keybindings_view,
+    },
+    Action {
+        name: "keymap.open_config",
+        description: "キー割り当ての設定ファイルを開く",
+        default_keys: &[],
+        run: App::open_keymap_config,
+    },
+    Action {
+        name: "keymap.reload",
+        description: "キー割り当ての設定ファイルを読み直す",
+        default_keys: &[],
+        run: |app| {
+            app.load_keymap();
+            msg!(app, "キー割り当てを読み直しました。");
+        },
+    },
+    Action {
+        name: "settings.open",
+        description: "設定ファイルを開く",
+        default_keys: &["Ctrl+,"],
+        run: App::open_settings,
+    },
+    Action {
+        name: "settings.open_project",
+        description: "プロジェクトの設定ファイルを開く",
+        default_keys: &[],
+        run: App::open_project_settings,
+    },
+    Action {
+        name: "settings.reload",
+        description: "設定ファイルを読み直す",
+        default_keys: &[],
+        run: |app| {
+            app.load_settings();
+            msg!(app, "設定を読み直しました。");
+        },
+    },
+    Action {
+        name: "theme.next",
+        description: "次のテーマに切り替える",
+        default_keys: &["Ctrl+K Ctrl+T"],
+        run: |app| app.next_theme(),
+    },
+    Action {
+        name: "theme.reload",
+        description: "テーマを読み直す",
+        default_keys: &[],
+        run: |app| app.reload_theme(),
+    },
+    Action {
+        name: "file.save",
+        description: "ファイルを保存する",
+        default_keys: &["Ctrl+S"],
+        run: |app| match app.save_current_file() {
+            Ok(_) => msg!(app, "ファイルが正常に保存されました。"),
+            Err(e) => emsg!(app, "ファイルの保存に失敗しました: {}", e),
+        },
+    },
+    Action {
+        name: "file.open",
+        description: "パスを入力してファイルを開く",
+        default_keys: &["Ctrl+O"],
+        run: App::open_file_prompt,
+    },
+    Action {
+        name: "file.find",
+        description: "ファイルをあいまい検索して開く",
+        default_keys: &["Ctrl+P"],
+        run: App::open_finder,
+    },
+    Action {
+        name: "file.close",
+        description: "タブを閉じる（最後のタブなら終了する）",
+        default_keys: &["Ctrl+W"],
+        run: |app| {
+            if app.close_current_document() {
+                app.request_quit();
+            }
+        },
+    },
+    Action {
+        name: "view.toggle_explorer",
+        description: "ファイルツリーを表示する・閉じる",
+        default_keys: &["Ctrl+B"],
+        run: App::toggle_explorer,
+    },
+    Action {
+        name: "view.toggle_word_wrap",
+        description: "折り返し表示を切り替える",
+        default_keys: &["Alt+Z"],
+        run: |app| {
+            app.word_wrap_enabled = !app.word_wrap_enabled;
+            if app.word_wrap_enabled {
+                msg!(app, "折り返し表示モード: ON");
+            } else {
+                msg!(app, "折り返し表示モード: OFF");
+            }
+        },
+    },
+    // タブ
+    Action {
+        name: "tab.next",
+        description: "次のタブに切り替える",
+        default_keys: &["Ctrl+PageDown"],
+        run: App::next_document,
+    },
+    Action {
+        name: "tab.previous",
+        description: "前のタブに切り替える",
+        default_keys: &["Ctrl+PageUp"],
+        run: App::previous_document,
+    },
+    Action {
+        name: "tab.select_1",
+        description: "1番目のタブに切り替える",
+        default_keys: &["Alt+1"],
+        run: |app| app.switch_document(0),
+    },
+    Action {
+        name: "tab.select_2",
+        description: "2番目のタブに切り替える",
+        default_keys: &["Alt+2"],
+        run: |app| app.switch_document(1),
+    },
+    Action {
+        name: "tab.select_3",
+        description: "3番目のタブに切り替える",
+        default_keys: &["Alt+3"],
+        run: |app| app.switch_document(2),
+    },
+    Action {
+        name: "tab.select_4",
+        description: "4番目のタブに切り替える",
+        default_keys: &["Alt+4"],
+        run: |app| app.switch_document(3),
+    },
+    Action {
+        name: "tab.select_5",
+        description: "5番目のタブに切り替える",
+        default_keys: &["Alt+5"],
+        run: |app| app.switch_document(4),
+    },
+    Action {
+        name: "tab.select_6",
+        description: "6番目のタブに切り替える",
+        default_keys: &["Alt+6"],
+        run: |app| app.switch_document(5),
+    },
+    Action {
+        name: "tab.select_7",
+        description: "7番目のタブに切り替える",
+        default_keys: &["Alt+7"],
+        run: |app| app.switch_document(6),
+    },
+    Action {
+        name: "tab.select_8",
+        description: "8番目のタブに切り替える",
+        default_keys: &["Alt+8"],
+        run: |app| app.switch_document(7),
+    },
+    Action {
+        name: "tab.select_9",
+        description: "9番目のタブに切り替える",
+        default_keys: &["Alt+9"],
+        run: |app| app.switch_document(8),
+    },
+    // 編集
+    Action {
+        name: "edit.copy",
+        description: "選択範囲をコピーする",
+        default_keys: &["Ctrl+C"],
+        run: |app| {
+            if let Some(text) = app.editor().copy_selection() {
+                match app.clipboard.copy(&text) {
+                    Ok(()) => msg!(app, "選択範囲をクリップボードにコピーしました。"),
+                    Err(e) => emsg!(
+                        app,
+                        "クリップボード ({}) にコピーできませんでした: {}。エディタ内にのみコピーしました。",
+                        app.clipboard.backend_name(),
+                        e
+                    ),
+                }
+            } else {
+                msg!(app, "コピーする選択範囲がありません。");
+            }
+        },
+    },
+    Action {
+        name: "edit.cut",
+        description: "選択範囲を切り取る",
+        default_keys: &["Ctrl+X"],
+        run: |app| {
+            if let Some(cut_text) = app.editor_mut().cut_selection() {
+                match app.clipboard.copy(&cut_text) {
+                    Ok(()) => msg!(app, "選択範囲をクリップボードに切り取りました。"),
+                    Err(e) => emsg!(
+                        app,
+                        "クリップボード ({}) にコピーできませんでした: {}。エディタ内にのみ切り取りました。",
+                        app.clipboard.backend_name(),
+                        e
+                    ),
+                }
+            } else {
+                msg!(app, "切り取る選択範囲がありません。");
+            }
+        },
+    },
+    Action {
+        name: "edit.paste",
+        description: "クリップボードの内容を貼り付ける",
+        default_keys: &["Ctrl+V"],
+        run: |app| {
+            let text = app.clipboard.paste().unwrap_or_else(|e| {
+                emsg!(
+                    app,
+                    "クリップボード ({}) を読み取れませんでした: {}。エディタ内でコピーした内容を使います。",
+                    app.clipboard.backend_name(),
+                    e
+                );
+                app.clipboard.last_copied.clone()
+            });
+            if let Some(text_to_paste) = text.filter(|text| !text.is_empty()) {
+                app.editor_mut().paste_text(&text_to_paste);
+                msg!(app, "クリップボードの内容をペーストしました。");
+            } else {
+                msg!(app, "クリップボードが空です。");
+            }
+        },
+    },
+    Action {
+        name: "edit.select_all",
+        description: "全て選択する",
+        default_keys: &["Ctrl+A"],
+        run: |app| {
+            app.editor_mut().select_all();
+            msg!(app, "全選択しました。");
+        },
+    },
+    Action {
+        name: "edit.undo",
+        description: "元に戻す",
+        default_keys: &["Ctrl+Z"],
+        run: |app| {
+            if app.editor_mut().undo() {
+                msg!(app, "元に戻しました。");
+            } else {
+                msg!(app, "元に戻す操作がありません。");
+            }
+        },
+    },
+    Action {
+        name: "edit.redo",
+        description: "やり直す",
+        default_keys: &["Ctrl+Y"],
+        run: |app| {
+            if app.editor_mut().redo() {
+                msg!(app, "やり直しました。");
+            } else {
+                msg!(app, "やり直す操作がありません。");
+            }
+        },
+    },
+    Action {
+        name: "edit.toggle_comment",
+        description: "行のコメントを切り替える",
+        // 多くの端末は Ctrl+/ を Ctrl+7 として送る
+        default_keys: &["Ctrl+/", "Ctrl+7"],
+        run: |app| {
+            if !app.editor_mut().toggle_comment() {
+                msg!(app, "この言語のコメントの記号がありません。");
+            }
+        },
+    },
+    Action {
+        name: "edit.complete",
+        description: "カーソル前の単語を補完する",
+        default_keys: &["Ctrl+Space"],
+        run: App::complete_word,
+    },
+    // カーソル移動
+    Action {
+        name: "cursor.document_start",
+        description: "ファイルの先頭に移動する",
+        default_keys: &["Ctrl+Home"],
+        run: |app| app.editor_mut().move_cursor_to_document_start(false),
+    },
+    Action {
+        name: "cursor.document_end",
+        description: "ファイルの末尾に移動する",
+        default_keys: &["Ctrl+End"],
+        run: |app| app.editor_mut().move_cursor_to_document_end(false),
+    },
+    Action {
+        name: "cursor.select_to_document_start",
+        description: "ファイルの先頭まで選択する",
+        default_keys: &["Ctrl+Shift+Home"],
+        run: |app| app.editor_mut().move_cursor_to_document_start(true),
+    },
+    Action {
+        name: "cursor.select_to_document_end",
+        description: "ファイルの末尾まで選択する",
+        default_keys: &["Ctrl+Shift+End"],
+        run: |app| app.editor_mut().move_cursor_to_document_end(true),
+    },
+    Action {
+        name: "cursor.matching_bracket",
+        description: "対応する括弧に移動する",
+        default_keys: &["Alt+M"],
+        run: App::jump_to_matching_bracket,
+    },
+    // 検索
+    Action {
+        name: "search.find",
+        description: "検索する",
+        default_keys: &["Ctrl+F"],
+        run: App::open_search,
+    },
+    Action {
+        name: "search.replace",
+        description: "置換する",
+        default_keys: &["Ctrl+H"],
+        run: App::open_replace,
+    },
+    Action {
+        name: "search.next",
+        description: "次の検索結果に移動する",
+        default_keys: &["F3"],
+        run: App::search_next,
+    },
+    Action {
+        name: "search.previous",
+        description: "前の検索結果に移動する",
+        default_keys: &["Shift+F3"],
+        run: App::search_previous,
+    },
+    // 差分
+    Action {
+        name: "diff.next_hunk",
+        description: "次の変更箇所に移動する",
+        default_keys: &["Alt+]"],
+        run: App::next_hunk,
+    },
+    Action {
+        name: "diff.previous_hunk",
+        description: "前の変更箇所に移動する",
+        default_keys: &["Alt+["],
+        run: App::previous_hunk,
+    },
+    Action {
+        name: "diff.preview_hunk",
+        description: "変更箇所の変更前の内容を表示する",
+        default_keys: &["Alt+P"],
+        run: App::toggle_hunk_preview,
+    },
+    Action {
+        name: "diff.revert_hunk",
+        description: "変更箇所を変更前の内容に戻す",
+        default_keys: &["Alt+U"],
+        run: App::revert_hunk,
+    },
+    Action {
+        name: "diff.toggle_view",
+        description: "保存済みの内容との差分を表示する",
+        default_keys: &["Alt+V"],
+        run: App::toggle_diff_view,
+    },
+    Action {
+        name: "diff.export",
+        description: "保存済みの内容からの変更を unified diff 形式で書き出す",
+        default_keys: &["Alt+E"],
+        run: App::export_unified_diff,
+    },
+    Action {
+        name: "diff.cycle_base",
+        description: "差分の比較元を切り替える（スナップショット / HEAD / インデックス）",
+        default_keys: &["Alt+D"],
+        run: App::cycle_diff_base,
+    },
+    // ペイン
+    Action {
+        name: "pane.split_horizontal",
+        description: "ペインを上下に分割する",
+        default_keys: &["Alt+-"],
+        run: |app| app.split_pane(SplitDirection::Horizontal),
+    },
+    Action {
+        name: "pane.split_vertical",
+        description: "ペインを左右に分割する",
+        default_keys: &["Alt+\\"],
+        run: |app| app.split_pane(SplitDirection::Vertical),
+    },
+    Action {
+        name: "pane.close",
+        description: "ペインを閉じる",
+        default_keys: &["Alt+W"],
+        run: App::close_pane,
+    },
+    Action {
+        name: "pane.grow",
+        description: "ペインを大きくする",
+        default_keys: &["Alt+."],
+        run: |app| app.resize_pane(5),
+    },
+    Action {
+        name: "pane.shrink",
+        description: "ペインを小さくする",
+        default_keys: &["Alt+,"],
+        run: |app| app.resize_pane(-5),
+    },
+    Action {
+        name: "pane.focus_left",
+        description: "左のペインに移動する",
+        default_keys: &["Alt+Left"],
+        run: |app| app.focus_pane(FocusDirection::Left),
+    },
+    Action {
+        name: "pane.focus_right",
+        description: "右のペインに移動する",
+        default_keys: &["Alt+Right"],
+        run: |app| app.focus_pane(FocusDirection::Right),
+    },
+    Action {
+        name: "pane.focus_up",
+        description: "上のペインに移動する",
+        default_keys: &["Alt+Up"],
+        run: |app| app.focus_pane(FocusDirection::Up),
+    },
+    Action {
+        name: "pane.focus_down",
+        description: "下のペインに移動する",
+        default_keys: &["Alt+Down"],
+        run: |app| app.focus_pane(FocusDirection::Down),
+    },
+];
@@ -0,0 +1,313 @@
+// src/app/clipboard.rs
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::env;
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+
+/// 設定ファイルの`clipboard`に指定できる名前（`auto`は環境から選ぶ）
+pub const CLIPBOARD_PROVIDERS: &[&str] = &[
+    "auto", "internal", "osc52", "wl-copy", "xclip", "xsel", "pbcopy",
+];
+
+/// クリップボードの読み書きの方法です。
+pub trait ClipboardBackend {
+    /// 方法の名前（設定ファイルでの名前）を返します。
+    fn name(&self) -> &'static str;
+
+    /// テキストをクリップボードに書き込みます。
+    fn copy(&mut self, text: &str) -> Result<(), String>;
+
+    /// クリップボードの内容を読み取ります。読み取りに対応していない方法では`None`を返します。
+    fn paste(&mut self) -> Result<Option<String>, String>;
+}
+
+/// アプリケーション内だけで使うクリップボード（レジスタ）です。
+/// 端末の外とはやり取りしないため、他の方法が使えない環境やテストで使います。
+#[derive(Debug, Default, Clone)]
+pub struct Register {
+    pub text: Option<String>,
+}
+
+impl ClipboardBackend for Register {
+    fn name(&self) -> &'static str {
+        "internal"
+    }
+
+    fn copy(&mut self, text: &str) -> Result<(), String> {
+        self.text = Some(text.to_string());
+        Ok(())
+    }
+
+    fn paste(&mut self) -> Result<Option<String>, String> {
+        Ok(self.text.clone())
+    }
+}
+
+/// 外部コマンド（xclip・xsel・wl-copy・pbcopy）でシステムのクリップボードを読み書きします。
+#[derive(Debug, Clone)]
+pub struct CommandBackend {
+    name: &'static str,
+    copy_command: &'static [&'static str], // 標準入力の内容をクリップボードに書き込むコマンド
+    paste_command: &'static [&'static str], // クリップボードの内容を標準出力に書き出すコマンド
+}
+
+impl CommandBackend {
+    /// 名前に対応するコマンドの組を返します。
+    pub fn new(name: &str) -> Option<Self> {
+        let (name, copy_command, paste_command): (_, &[&str], &[&str]) = match name {
+            "wl-copy" => ("wl-copy", &["wl-copy"], &["wl-paste", "--no-newline"]),
+            "xclip" => (
+                "xclip",
+                &["xclip", "-selection", "clipboard", "-in"],
+                &["xclip", "-selection", "clipboard", "-out"],
+            ),
+            "xsel" => (
+                "xsel",
+                &["xsel", "--clipboard", "--input"],
+                &["xsel", "--clipboard", "--output"],
+            ),
+            "pbcopy" => ("pbcopy", &["pbcopy"], &["pbpaste"]),
+            _ => return None,
+        };
+        Some(Self {
+            name,
+            copy_command,
+            paste_command,
+        })
+    }
+}
+
+impl ClipboardBackend for CommandBackend {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn copy(&mut self, text: &str) -> Result<(), String> {
+        let (program, args) = self.copy_command.split_first().unwrap_or((&"", &[]));
+        // xclipやwl-copyはクリップボードを保持するために子プロセスを残すため、出力は受け取らない
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("{} を実行できません: {}", program, e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .map_err(|e| format!("{} への書き込みに失敗しました: {}", program, e))?;
+        }
+        let status = child.wait().map_err(|e| e.to_string())?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(format!("{} が失敗しました ({})", program, status))
+        }
+    }
+
+    fn paste(&mut self) -> Result<Option<String>, String> {
+        let (program, args) = self.paste_command.split_first().unwrap_or((&"", &[]));
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("{} を実行できません: {}", program, e))?;
+        let mut text = String::new();
+        if let Some(mut stdout) = child.stdout.take() {
+            stdout
+                .read_to_string(&mut text)
+                .map_err(|e| format!("{} の出力を読み取れません: {}", program, e))?;
+        }
+        let status = child.wait().map_err(|e| e.to_string())?;
+        if status.success() {
+            Ok(Some(text))
+        } else {
+            Err(format!("{} が失敗しました ({})", program, status))
+        }
+    }
+}
+
+/// OSC 52 のエスケープシーケンスで、端末にクリップボードへの書き込みを依頼します。
+/// SSH越しでも手元の端末のクリップボードに書き込めます。読み取りに対応した端末は少ないため、読み取りはしません。
+pub struct Osc52<W: Write> {
+    out: W, // エスケープシーケンスの書き込み先（通常は標準出力）
+}
+
+impl<W: Write> Osc52<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+}
+
+impl<W: Write> ClipboardBackend for Osc52<W> {
+    fn name(&self) -> &'static str {
+        "osc52"
+    }
+
+    fn copy(&mut self, text: &str) -> Result<(), String> {
+        write!(self.out, "\x1b]52;c;{}\x07", STANDARD.encode(text))
+            .and_then(|_| self.out.flush())
+            .map_err(|e| format!("端末への書き込みに失敗しました: {}", e))
+    }
+
+    fn paste(&mut self) -> Result<Option<String>, String> {
+        Ok(None)
+    }
+}
+
+/// コピーと貼り付けに使うクリップボードです。
+/// 最後にコピーした内容を覚えておき、読み取りに対応していない方法や読み取りに失敗した場合に使います。
+pub struct Clipboard {
+    backend: Box<dyn ClipboardBackend>,
+    pub last_copied: Option<String>, // 最後にコピー（または切り取り）した内容
+}
+
+impl Default for Clipboard {
+    fn default() -> Self {
+        Self::new(Box::new(Register::default()))
+    }
+}
+
+impl Clipboard {
+    pub fn new(backend: Box<dyn ClipboardBackend>) -> Self {
+        Self {
+            backend,
+            last_copied: None,
+        }
+    }
+
+    /// 設定ファイルの`clipboard`の値からクリップボードの読み書きの方法を作成します。
+    /// `auto`の場合は、環境変数と使えるコマンドから選びます。
+    pub fn backend_for(name: &str) -> Box<dyn ClipboardBackend> {
+        let name = if name == "auto" {
+            detect_from(
+                |name| env::var_os(name).is_some_and(|v| !v.is_empty()),
+                has_command,
+            )
+        } else {
+            name
+        };
+        match name {
+            "osc52" => Box::new(Osc52::new(io::stdout())),
+            _ => match CommandBackend::new(name) {
+                Some(backend) => Box::new(backend),
+                None => Box::new(Register::default()),
+            },
+        }
+    }
+
+    /// 読み書きの方法を切り替えます。最後にコピーした内容は残します。
+    pub fn set_backend(&mut self, backend: Box<dyn ClipboardBackend>) {
+        self.backend = backend;
+    }
+
+    /// 使っている読み書きの方法の名前を返します。
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    /// テキストをクリップボードにコピーします。
+    pub fn copy(&mut self, text: &str) -> Result<(), String> {
+        self.last_copied = Some(text.to_string());
+        self.backend.copy(text)
+    }
+
+    /// クリップボードの内容を返します。読み取りに対応していない方法では、最後にコピーした内容を返します。
+    pub fn paste(&mut self) -> Result<Option<String>, String> {
+        match self.backend.paste()? {
+            Some(text) => Ok(Some(text)),
+            None => Ok(self.last_copied.clone()),
+        }
+    }
+}
+
+/// 環境変数の有無（`has_var`）と使えるコマンド（`has_command`）から、クリップボードの読み書きの方法を選びます。
+/// SSH越しでは手元の端末に届く OSC 52 を、そうでなければ表示サーバーに合ったコマンドを使い、
+/// どれも使えない場合はアプリケーション内のクリップボードを使います。
+pub fn detect_from(
+    has_var: impl Fn(&str) -> bool,
+    has_command: impl Fn(&str) -> bool,
+) -> &'static str {
+    if has_var("SSH_TTY") || has_var("SSH_CONNECTION") {
+        return "osc52";
+    }
+    if has_var("WAYLAND_DISPLAY") && has_command("wl-copy") && has_command("wl-paste") {
+        return "wl-copy";
+    }
+    if has_var("DISPLAY") {
+        if has_command("xclip") {
+            return "xclip";
+        }
+        if has_command("xsel") {
+            return "xsel";
+        }
+    }
+    if has_command("pbcopy") && has_command("pbpaste") {
+        return "pbcopy";
+    }
+    "internal"
+}
+
+/// 実行できるコマンドが`PATH`にあるかを返します。
+fn has_command(name: &str) -> bool {
+    env::var_os("PATH")
+        .is_some_and(|paths| env::split_paths(&paths).any(|dir| dir.join(name).is_file()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_copies_and_pastes() {
+        let mut clipboard = Clipboard::new(Box::new(Register::default()));
+        assert_eq!(clipboard.paste(), Ok(None));
+        clipboard.copy("hello\nworld").unwrap();
+        assert_eq!(clipboard.paste(), Ok(Some("hello\nworld".to_string())));
+        assert_eq!(clipboard.backend_name(), "internal");
+    }
+
+    #[test]
+    fn falls_back_to_last_copied_when_backend_cannot_paste() {
+        let mut clipboard = Clipboard::new(Box::new(Osc52::new(Vec::new())));
+        assert_eq!(clipboard.paste(), Ok(None));
+        clipboard.copy("copied").unwrap();
+        assert_eq!(clipboard.paste(), Ok(Some("copied".to_string())));
+    }
+
+    #[test]
+    fn osc52_writes_base64_escape_sequence() {
+        let mut out = Vec::new();
+        Osc52::new(&mut out).copy("hello").unwrap();
+        assert_eq!(out, b"\x1b]52;c;aGVsbG8=\x07");
+
+        let mut out = Vec::new();
+        Osc52::new(&mut out).copy("日本").unwrap();
+        assert_eq!(out, b"\x1b]52;c;5pel5pys\x07");
+    }
+
+    #[test]
+    fn detect_prefers_ssh_then_wayland_then_x11_then_pbcopy() {
+        let detect = |vars: &[&str], commands: &[&str]| {
+            detect_from(|name| vars.contains(&name), |name| commands.contains(&name))
+        };
+        let all_commands = ["wl-copy", "wl-paste", "xclip", "xsel", "pbcopy", "pbpaste"];
+        let all_vars = ["SSH_TTY", "WAYLAND_DISPLAY", "DISPLAY"];
+
+        assert_eq!(detect(&all_vars, &all_commands), "osc52");
+        assert_eq!(detect(&["SSH_CONNECTION"], &[]), "osc52");
+        assert_eq!(detect(&all_vars[1..], &all_commands), "wl-copy");
+        // wl-paste がなければ Wayland のコマンドは使わない
+        assert_eq!(detect(&all_vars[1..], &["wl-copy", "xclip"]), "xclip");
+        assert_eq!(detect(&["DISPLAY"], &all_commands), "xclip");
+        assert_eq!(detect(&["DISPLAY"], &["xsel", "pbcopy", "pbpaste"]), "xsel");
+        // 表示サーバーがなければ xclip などは使わない
+        assert_eq!(detect(&[], &all_commands), "pbcopy");
+        assert_eq!(detect(&["DISPLAY"], &[]), "internal");
+        assert_eq!(detect(&[], &[]), "internal");
+    }
+}
@@ -0,0 +1,221 @@
+// src/app/color.rs
+
+use ratatui::buffer::Buffer;
+use ratatui::style::Color;
+use std::env;
+use std::str::FromStr;
+
+/// 端末で表示できる色の数です。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    TrueColor,  // 24ビットの色（`Color::Rgb`をそのまま使う）
+    Ansi256,    // 256色のパレット
+    Ansi16,     // 基本の16色
+    Monochrome, // 色を使わない（太字・反転・下線で区別する）
+}
+
+impl FromStr for ColorDepth {
+    type Err = String;
+
+    /// 設定ファイルでの表記（`truecolor`、`256`、`16`、`monochrome`）を解釈します。
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "truecolor" | "24bit" => Ok(ColorDepth::TrueColor),
+            "256" => Ok(ColorDepth::Ansi256),
+            "16" => Ok(ColorDepth::Ansi16),
+            "monochrome" => Ok(ColorDepth::Monochrome),
+            _ => Err(
+                "auto / truecolor / 256 / 16 / monochrome のいずれかを指定してください".to_string(),
+            ),
+        }
+    }
+}
+
+/// 基本の16色と、その代表的なRGBの値（xtermの既定の色）
+const ANSI_16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// 256色のパレットの6x6x6の色の立方体の各成分の値
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorDepth {
+    /// 環境変数から、端末で表示できる色の数を判定します。
+    pub fn detect() -> Self {
+        let var = |name| env::var(name).ok().filter(|value| !value.is_empty());
+        Self::detect_from(
+            var("NO_COLOR").is_some(),
+            var("COLORTERM").as_deref(),
+            var("TERM").as_deref(),
+        )
+    }
+
+    /// `NO_COLOR`の有無、`COLORTERM`と`TERM`の値から、表示できる色の数を判定します。
+    /// 判定できない場合は、多くの端末で使える16色とします。
+    pub fn detect_from(no_color: bool, colorterm: Option<&str>, term: Option<&str>) -> Self {
+        if no_color {
+            return ColorDepth::Monochrome;
+        }
+        if matches!(colorterm, Some("truecolor" | "24bit")) {
+            return ColorDepth::TrueColor;
+        }
+        match term {
+            Some("dumb") => ColorDepth::Monochrome,
+            Some(term) if term.ends_with("-direct") => ColorDepth::TrueColor,
+            Some(term) if term.contains("256color") => ColorDepth::Ansi256,
+            _ => ColorDepth::Ansi16,
+        }
+    }
+
+    /// 色をこの端末で表示できる最も近い色に変換します。
+    pub fn map(self, color: Color) -> Color {
+        match (self, color) {
+            (_, Color::Reset) | (ColorDepth::TrueColor, _) => color,
+            (ColorDepth::Monochrome, _) => Color::Reset,
+            (ColorDepth::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(nearest_256((r, g, b))),
+            (ColorDepth::Ansi16, Color::Rgb(r, g, b)) => nearest_16((r, g, b)),
+            (ColorDepth::Ansi16, Color::Indexed(index)) => nearest_16(indexed_rgb(index)),
+            _ => color, // 基本の16色はどの端末でも表示できる
+        }
+    }
+
+    /// 描画した画面の全ての色を、この端末で表示できる色に変換します。
+    pub fn degrade(self, buffer: &mut Buffer) {
+        if self == ColorDepth::TrueColor {
+            return;
+        }
+        for cell in &mut buffer.content {
+            cell.fg = self.map(cell.fg);
+            cell.bg = self.map(cell.bg);
+        }
+    }
+}
+
+/// 2つの色の距離（の2乗）を返します。
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+/// 基本の16色のうち、最も近い色を返します。
+fn nearest_16(rgb: (u8, u8, u8)) -> Color {
+    ANSI_16
+        .iter()
+        .min_by_key(|(_, ansi)| distance(rgb, *ansi))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+/// 256色のパレットのうち、最も近い色（色の立方体か灰色の階調）の番号を返します。
+/// 0〜15番は端末ごとに色が異なるため使いません。
+fn nearest_256(rgb: (u8, u8, u8)) -> u8 {
+    let level = |value: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - value as i32).abs())
+            .unwrap_or(0)
+    };
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube_index = (16 + 36 * r + 6 * g + b) as u8;
+
+    let average = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let gray_step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray_index = 232 + gray_step;
+
+    if distance(rgb, indexed_rgb(gray_index)) < distance(rgb, indexed_rgb(cube_index)) {
+        gray_index
+    } else {
+        cube_index
+    }
+}
+
+/// 256色のパレットの番号に対応するRGBの値を返します。
+fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI_16[index as usize].1,
+        16..=231 => {
+            let i = (index - 16) as usize;
+            (
+                CUBE_LEVELS[i / 36],
+                CUBE_LEVELS[i / 6 % 6],
+                CUBE_LEVELS[i % 6],
+            )
+        }
+        _ => {
+            let value = 8 + 10 * (index - 232);
+            (value, value, value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_color_depth_from_environment() {
+        use ColorDepth::*;
+        let cases = [
+            // (NO_COLOR, COLORTERM, TERM, 期待する色の数)
+            (true, Some("truecolor"), Some("xterm-256color"), Monochrome),
+            (false, Some("truecolor"), None, TrueColor),
+            (false, Some("24bit"), Some("xterm"), TrueColor),
+            (false, None, Some("xterm-256color"), Ansi256),
+            (false, None, Some("screen-256color"), Ansi256),
+            (false, None, Some("xterm-direct"), TrueColor),
+            (false, Some("yes"), Some("xterm"), Ansi16),
+            (false, None, Some("dumb"), Monochrome),
+            (false, None, None, Ansi16),
+        ];
+        for (no_color, colorterm, term, expected) in cases {
+            assert_eq!(
+                ColorDepth::detect_from(no_color, colorterm, term),
+                expected,
+                "NO_COLOR={} COLORTERM={:?} TERM={:?}",
+                no_color,
+                colorterm,
+                term
+            );
+        }
+    }
+
+    #[test]
+    fn maps_rgb_to_nearest_256_color() {
+        assert_eq!(nearest_256((0, 0, 0)), 16);
+        assert_eq!(nearest_256((255, 0, 0)), 196);
+        assert_eq!(nearest_256((255, 255, 255)), 231);
+        assert_eq!(nearest_256((95, 135, 175)), 67);
+        // 灰色は、色の立方体より近ければ灰色の階調を使う
+        assert_eq!(nearest_256((128, 128, 128)), 244);
+        assert_eq!(nearest_256((18, 18, 18)), 233);
+        for index in 16..=255 {
+            assert_eq!(nearest_256(indexed_rgb(index)), index);
+        }
+    }
+
+    #[test]
+    fn maps_colors_for_each_depth() {
+        let red = Color::Rgb(250, 10, 10);
+        assert_eq!(ColorDepth::TrueColor.map(red), red);
+        assert_eq!(ColorDepth::Ansi256.map(red), Color::Indexed(196));
+        assert_eq!(ColorDepth::Ansi16.map(red), Color::LightRed);
+        assert_eq!(ColorDepth::Ansi16.map(Color::Rgb(200, 0, 0)), Color::Red);
+        assert_eq!(ColorDepth::Ansi16.map(Color::Indexed(244)), Color::DarkGray);
+        assert_eq!(ColorDepth::Ansi256.map(Color::Blue), Color::Blue);
+        assert_eq!(ColorDepth::Monochrome.map(red), Color::Reset);
+        assert_eq!(ColorDepth::Ansi16.map(Color::Reset), Color::Reset);
+    }
+}
@@ -0,0 +1,14 @@
+// src/app/config.rs
+
+use std::env;
+use std::path::PathBuf;
+
+/// 設定ファイルを置くディレクトリを返します。
+/// `$XDG_CONFIG_HOME/inedit`、`XDG_CONFIG_HOME`が設定されていなければ`~/.config/inedit`です。
+pub fn config_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute()) // XDG Base Directory の仕様では相対パスは無視する
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("inedit"))
+}
@@ -1,18 +1,20 @@
 // src/app/cursor.rs
 
+use serde::{Deserialize, Serialize};
+
 /// カーソルの論理的な位置と選択範囲を管理します。
 /// この構造体は、テキストバッファの内容やその境界に関する知識を持ちません。
 /// 実際のカーソル位置の調整（バッファ境界内へのクランプ）はEditor側で行うべきです。
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Cursor {
-    pub x: u16,                              // 列位置 (0-indexed, 文字単位)
-    pub y: u16,                              // 行位置 (0-indexed)
-    pub selection_start: Option<(u16, u16)>, // 選択範囲の開始位置 (x, y)
+    pub x: usize,                                // 列位置 (0-indexed, 文字単位)
+    pub y: usize,                                // 行位置 (0-indexed)
+    pub selection_start: Option<(usize, usize)>, // 選択範囲の開始位置 (x, y)
 }
 
 impl Cursor {
     /// 新しいカーソルを作成します。
-    pub fn new(x: u16, y: u16) -> Self {
+    pub fn new(x: usize, y: usize) -> Self {
         Self {
             x,
             y,
@@ -27,7 +29,7 @@ impl Cursor {
     /// `extend_selection`が`true`の場合、現在のカーソル位置を基準に選択範囲を拡張します。
     /// まだ選択が開始されていない場合は、この呼び出しの前のカーソル位置が選択開始点となります。
     /// `false`の場合、既存の選択範囲はクリアされます。
-    pub fn update_position(&mut self, new_x: u16, new_y: u16, extend_selection: bool) {
+    pub fn update_position(&mut self, new_x: usize, new_y: usize, extend_selection: bool) {
         if !extend_selection {
             // 選択を拡張しない場合、現在の選択をクリア
             self.clear_selection();
@@ -40,7 +42,7 @@ impl Cursor {
     }
 
     /// 現在のカーソル位置を取得します。
-    pub fn get_current_pos(&self) -> (u16, u16) {
+    pub fn get_current_pos(&self) -> (usize, usize) {
         (self.x, self.y)
     }
 
@@ -60,7 +62,7 @@ impl Cursor {
     ///
     /// 注意: ここで返される座標は論理的なものであり、バイトオフセットではありません。
     /// バイトオフセットへの変換はEditor側で行う必要があります。
-    pub fn get_normalized_selection_coords(&self) -> Option<((u16, u16), (u16, u16))> {
+    pub fn get_normalized_selection_coords(&self) -> Option<((usize, usize), (usize, usize))> {
         if let Some(start_pos) = self.selection_start {
             // selection_startは(x, y)形式なので、(y, x)に変換
             let selection_start_y_x = (start_pos.1, start_pos.0);
@@ -85,44 +87,44 @@ impl Cursor {
 // 実際の最終的なカーソル位置（バッファ境界に合わせたもの）は、Editor側で決定されます。
 impl Cursor {
     /// カーソルを1行下に移動したと仮定したY座標を返します。
-    pub fn get_potential_next_line_y(&self) -> u16 {
+    pub fn get_potential_next_line_y(&self) -> usize {
         self.y.saturating_add(1)
     }
 
     /// カーソルを1行上に移動したと仮定したY座標を返します。
-    pub fn get_potential_previous_line_y(&self) -> u16 {
+    pub fn get_potential_previous_line_y(&self) -> usize {
         self.y.saturating_sub(1)
     }
 
     /// カーソルを1文字右に移動したと仮定したX座標を返します。
-    pub fn get_potential_next_char_x(&self) -> u16 {
+    pub fn get_potential_next_char_x(&self) -> usize {
         self.x.saturating_add(1)
     }
 
     /// カーソルを1文字左に移動したと仮定したX座標を返します。
-    pub fn get_potential_previous_char_x(&self) -> u16 {
+    pub fn get_potential_previous_char_x(&self) -> usize {
         self.x.saturating_sub(1)
     }
 
     /// カーソルを現在の行の先頭に移動したと仮定したX座標を返します。
-    pub fn get_potential_start_of_line_x(&self) -> u16 {
+    pub fn get_potential_start_of_line_x(&self) -> usize {
         0
     }
 
     /// カーソルを現在の行の末尾に移動したと仮定したX座標を返します。
     /// これはEditorが実際の行の長さを考慮して調整する「マーカー」として使用されるべきです。
-    pub fn get_potential_end_of_line_x(&self) -> u16 {
-        u16::MAX // Editorがこの値を「行末」として解釈することを期待
+    pub fn get_potential_end_of_line_x(&self) -> usize {
+        usize::MAX // Editorがこの値を「行末」として解釈することを期待
     }
 
     /// カーソルをドキュメントの先頭に移動したと仮定したY座標とX座標を返します。
-    pub fn get_potential_document_start_pos(&self) -> (u16, u16) {
+    pub fn get_potential_document_start_pos(&self) -> (usize, usize) {
         (0, 0) // (y, x) 形式
     }
 
     /// カーソルをドキュメントの末尾に移動したと仮定したY座標とX座標を返します。
     /// これもEditorが実際のドキュメントの行数と最後の行の長さを考慮して調整する「マーカー」として使用されるべきです。
-    pub fn get_potential_document_end_pos(&self) -> (u16, u16) {
-        (u16::MAX, u16::MAX) // (y, x) 形式。Editorがこの値を「ドキュメント末尾」として解釈することを期待
+    pub fn get_potential_document_end_pos(&self) -> (usize, usize) {
+        (usize::MAX, usize::MAX) // (y, x) 形式。Editorがこの値を「ドキュメント末尾」として解釈することを期待
     }
 }
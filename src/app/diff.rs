@@ -0,0 +1,340 @@
+// src/app/diff.rs
+
+use super::LineStatus;
+use super::text_buffer::TextBuffer;
+use similar::{Algorithm, DiffOp, TextDiff, capture_diff_deadline};
+use std::time::{Duration, Instant};
+
+/// 差分の計算にかける時間の上限です。これを超えると、多少粗い差分で打ち切ります。
+const DIFF_DEADLINE: Duration = Duration::from_millis(50);
+
+/// 差分の比較元です。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffBase {
+    Snapshot, // ファイルを開いた時点（保存後は保存した時点）の内容
+    Head,     // gitリポジトリのHEADのコミットにある内容
+    Index,    // gitリポジトリのインデックス（ステージングエリア）にある内容
+}
+
+impl DiffBase {
+    /// 画面に表示する名前を返します。
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Snapshot => "スナップショット",
+            Self::Head => "HEAD",
+            Self::Index => "インデックス",
+        }
+    }
+}
+
+/// 変更のあった連続した行のまとまり（ハンク）です。行番号は0-indexedです。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hunk {
+    pub old_start: usize, // 比較元での開始行
+    pub old_len: usize,   // 比較元での行数（0なら行の追加のみ）
+    pub new_start: usize, // 現在のバッファでの開始行
+    pub new_len: usize,   // 現在のバッファでの行数（0なら行の削除のみ）
+}
+
+impl Hunk {
+    /// 現在のバッファの指定された行がこのハンクに含まれるかどうかを返します。
+    /// 削除のみのハンクは、削除位置の直後の行に含まれるものとして扱います。
+    pub fn contains_line(&self, y: usize) -> bool {
+        if self.new_len == 0 {
+            y == self.new_start
+        } else {
+            (self.new_start..self.new_start + self.new_len).contains(&y)
+        }
+    }
+}
+
+/// 左右に並べた差分表示の1行分です。行番号は0-indexedで、対応する行がない側はNoneです。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffRow {
+    pub old: Option<usize>, // 比較元の行
+    pub new: Option<usize>, // 現在のバッファの行
+    pub changed: bool,      // ハンクに含まれる行かどうか
+}
+
+/// 比較元のテキストと現在のバッファを行単位で比較し、ハンクの一覧を返します。
+/// 行の比較には patience diff を使い、挿入した行の後ろの行が全て変更扱いになることはありません。
+/// 先頭と末尾の共通部分は差分アルゴリズムにかける前に取り除くため、
+/// 編集箇所が局所的であれば、ファイル全体の大きさによらず高速に計算できます。
+pub fn diff_lines(original: &str, buffer: &TextBuffer) -> Vec<Hunk> {
+    diff_lines_until(original, buffer, Instant::now() + DIFF_DEADLINE)
+}
+
+/// `diff_lines`の本体です。`deadline`を過ぎると、それまでに求めた範囲で差分を打ち切ります。
+fn diff_lines_until(original: &str, buffer: &TextBuffer, deadline: Instant) -> Vec<Hunk> {
+    // TextBufferと同じ行の数え方（LFで分割し、末尾の改行の後ろも1行とする）で比較する
+    let old_lines: Vec<&str> = original.split('\n').collect();
+    let new_len = buffer.len_lines();
+
+    // 先頭の共通部分
+    let prefix = (0..old_lines.len().min(new_len))
+        .take_while(|&i| buffer.line(i) == old_lines[i])
+        .count();
+    // 末尾の共通部分（先頭の共通部分と重ならない範囲）
+    let suffix = (0..(old_lines.len() - prefix).min(new_len - prefix))
+        .take_while(|&i| buffer.line(new_len - 1 - i) == old_lines[old_lines.len() - 1 - i])
+        .count();
+
+    let old_middle = &old_lines[prefix..old_lines.len() - suffix];
+    let new_middle: Vec<String> = (prefix..new_len - suffix)
+        .map(|y| buffer.line(y).to_string())
+        .collect();
+    let new_middle: Vec<&str> = new_middle.iter().map(String::as_str).collect();
+
+    let ops = capture_diff_deadline(
+        Algorithm::Patience,
+        old_middle,
+        0..old_middle.len(),
+        &new_middle,
+        0..new_middle.len(),
+        Some(deadline),
+    );
+
+    // 隣り合う変更（削除・挿入・置換）を1つのハンクにまとめる
+    let mut hunks: Vec<Hunk> = Vec::new();
+    for op in ops {
+        if let DiffOp::Equal { .. } = op {
+            continue;
+        }
+        let (_, old_range, new_range) = op.as_tag_tuple();
+        let old_start = prefix + old_range.start;
+        let new_start = prefix + new_range.start;
+        match hunks.last_mut() {
+            Some(last)
+                if last.old_start + last.old_len == old_start
+                    && last.new_start + last.new_len == new_start =>
+            {
+                last.old_len += old_range.len();
+                last.new_len += new_range.len();
+            }
+            _ => hunks.push(Hunk {
+                old_start,
+                old_len: old_range.len(),
+                new_start,
+                new_len: new_range.len(),
+            }),
+        }
+    }
+    hunks
+}
+
+/// ハンクの一覧から、現在のバッファの各行の差分状態を求めます。
+/// ハンク内の行は、比較元の行数までを変更、それを超える分を追加として扱います。
+/// 比較元の方が行数の多いハンクでは、余った分を直後の行の上（末尾なら最終行の下）で削除されたものとします。
+pub fn line_statuses(hunks: &[Hunk], line_count: usize) -> Vec<LineStatus> {
+    let mut statuses = vec![LineStatus::Unchanged; line_count];
+    for hunk in hunks {
+        for i in 0..hunk.new_len {
+            statuses[hunk.new_start + i] = if i < hunk.old_len {
+                LineStatus::Modified
+            } else {
+                LineStatus::Added
+            };
+        }
+        if hunk.old_len > hunk.new_len {
+            let y = hunk.new_start + hunk.new_len;
+            if y < line_count {
+                if statuses[y] == LineStatus::Unchanged {
+                    statuses[y] = LineStatus::DeletedAbove;
+                }
+            } else if let Some(last) = statuses.last_mut()
+                && *last == LineStatus::Unchanged
+            {
+                *last = LineStatus::DeletedBelow;
+            }
+        }
+    }
+    statuses
+}
+
+/// ハンクの一覧から、比較元と現在のバッファの行を左右に並べた表示行の一覧を作ります。
+/// ハンク内では両側の行を先頭から順に対応させ、行数の少ない側は空行で埋めます。
+pub fn align_rows(hunks: &[Hunk], old_line_count: usize, new_line_count: usize) -> Vec<DiffRow> {
+    let mut rows = Vec::with_capacity(old_line_count.max(new_line_count));
+    let (mut old_y, mut new_y) = (0, 0);
+    let unchanged =
+        |rows: &mut Vec<DiffRow>, old_y: &mut usize, new_y: &mut usize, until: usize| {
+            while *new_y < until {
+                rows.push(DiffRow {
+                    old: Some(*old_y),
+                    new: Some(*new_y),
+                    changed: false,
+                });
+                *old_y += 1;
+                *new_y += 1;
+            }
+        };
+    for hunk in hunks {
+        unchanged(&mut rows, &mut old_y, &mut new_y, hunk.new_start);
+        for i in 0..hunk.old_len.max(hunk.new_len) {
+            rows.push(DiffRow {
+                old: (i < hunk.old_len).then_some(hunk.old_start + i),
+                new: (i < hunk.new_len).then_some(hunk.new_start + i),
+                changed: true,
+            });
+        }
+        old_y = hunk.old_start + hunk.old_len;
+        new_y = hunk.new_start + hunk.new_len;
+    }
+    unchanged(&mut rows, &mut old_y, &mut new_y, new_line_count);
+    rows
+}
+
+/// 比較元のテキストと現在の内容の差分を、unified diff 形式の文字列で返します。
+/// `old_name`と`new_name`はヘッダ（`---`と`+++`の行）に書くファイル名です。差分がなければ空文字列を返します。
+pub fn unified_diff(original: &str, current: &str, old_name: &str, new_name: &str) -> String {
+    const CONTEXT_LINES: usize = 3; // 変更箇所の前後に含める行数
+    if original == current {
+        return String::new();
+    }
+    TextDiff::configure()
+        .algorithm(Algorithm::Patience)
+        .deadline(Instant::now() + DIFF_DEADLINE)
+        .diff_lines(original, current)
+        .unified_diff()
+        .context_radius(CONTEXT_LINES)
+        .header(old_name, new_name)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use LineStatus::*;
+
+    fn statuses(original: &str, current: &str) -> Vec<LineStatus> {
+        let buffer = TextBuffer::new(current);
+        line_statuses(&diff_lines(original, &buffer), buffer.len_lines())
+    }
+
+    /// 挿入・削除・変更ごとに、各行の差分状態を確かめます。
+    #[test]
+    fn line_statuses_for_edits() {
+        let cases: &[(&str, &str, &[LineStatus])] = &[
+            ("a\nb\nc\n", "a\nb\nc\n", &[Unchanged; 4]),
+            // 挿入した行の後ろの行は変更扱いにならない
+            (
+                "a\nb\nc\n",
+                "a\nx\nb\nc\n",
+                &[Unchanged, Added, Unchanged, Unchanged, Unchanged],
+            ),
+            ("a\nb\n", "a\nB\n", &[Unchanged, Modified, Unchanged]),
+            (
+                "a\nb\nc",
+                "a\nB\nB2\nc",
+                &[Unchanged, Modified, Added, Unchanged],
+            ),
+            // 削除した位置は、直後の行の上に示す
+            ("a\nb\nc\n", "a\nc\n", &[Unchanged, DeletedAbove, Unchanged]),
+            ("a\nb\nc\n", "b\nc\n", &[DeletedAbove, Unchanged, Unchanged]),
+            // 末尾の行を削除した場合は、最終行の下に示す
+            ("a\nb\nc", "a\nb", &[Unchanged, DeletedBelow]),
+            // 置き換えで行数が減った場合も、ハンクの直後の行の上に削除を示す
+            (
+                "a\nb\nc\nd",
+                "a\nC\nd",
+                &[Unchanged, Modified, DeletedAbove],
+            ),
+            ("", "a\nb", &[Modified, Added]),
+        ];
+        for (original, current, expected) in cases {
+            assert_eq!(
+                statuses(original, current),
+                *expected,
+                "{:?} -> {:?}",
+                original,
+                current
+            );
+        }
+    }
+
+    /// 隣り合う変更が1つのハンクにまとまり、離れた変更は別のハンクになることを確かめます。
+    #[test]
+    fn diff_lines_groups_adjacent_changes() {
+        let buffer = TextBuffer::new("a\nB\nC\nd\ne\nF\n");
+        let hunks = diff_lines("a\nb\nc\nd\ne\nf\ng\n", &buffer);
+        assert_eq!(
+            hunks,
+            [
+                Hunk {
+                    old_start: 1,
+                    old_len: 2,
+                    new_start: 1,
+                    new_len: 2,
+                },
+                Hunk {
+                    old_start: 5,
+                    old_len: 2,
+                    new_start: 5,
+                    new_len: 1,
+                },
+            ]
+        );
+        assert!(hunks[1].contains_line(5));
+        assert!(!hunks[1].contains_line(6));
+    }
+
+    /// 時間の上限を過ぎても、先頭と末尾の共通部分を除いた範囲の粗い差分が得られることを確かめます。
+    #[test]
+    fn diff_lines_falls_back_after_deadline() {
+        let original = "a\nb\nc\nd\ne\n";
+        let buffer = TextBuffer::new("a\nX\nc\nY\ne\n");
+        let hunks = diff_lines_until(original, &buffer, Instant::now());
+        assert!(!hunks.is_empty());
+        let statuses = line_statuses(&hunks, buffer.len_lines());
+        assert_eq!(statuses[0], Unchanged);
+        assert_ne!(statuses[1], Unchanged);
+        assert_ne!(statuses[3], Unchanged);
+        assert_eq!(statuses[4..], [Unchanged, Unchanged]);
+        // 全ての行が、いずれかのハンクか共通部分として比較元の行に対応している
+        let old_lines = original.split('\n').count();
+        let rows = align_rows(&hunks, old_lines, buffer.len_lines());
+        assert_eq!(rows.iter().filter_map(|row| row.old).count(), old_lines);
+        assert_eq!(
+            rows.iter().filter_map(|row| row.new).count(),
+            buffer.len_lines()
+        );
+    }
+
+    fn row(old: Option<usize>, new: Option<usize>, changed: bool) -> DiffRow {
+        DiffRow { old, new, changed }
+    }
+
+    /// 行数の異なるハンクでは、少ない側を空行で埋めて左右を並べることを確かめます。
+    #[test]
+    fn align_rows_pads_uneven_hunks() {
+        // 1行を3行に置き換え、後ろの2行を削除した
+        let original = "a\nb\nc\nd\ne\nf";
+        let buffer = TextBuffer::new("a\nB1\nB2\nB3\nc\nd");
+        let hunks = diff_lines(original, &buffer);
+        let rows = align_rows(&hunks, 6, buffer.len_lines());
+        assert_eq!(
+            rows,
+            [
+                row(Some(0), Some(0), false),
+                row(Some(1), Some(1), true),
+                row(None, Some(2), true),
+                row(None, Some(3), true),
+                row(Some(2), Some(4), false),
+                row(Some(3), Some(5), false),
+                row(Some(4), None, true),
+                row(Some(5), None, true),
+            ]
+        );
+    }
+
+    /// unified diff のヘッダにファイル名が入り、差分がなければ空文字列になることを確かめます。
+    #[test]
+    fn unified_diff_has_headers() {
+        let diff = unified_diff("a\nb\n", "a\nc\n", "a/file.txt", "b/file.txt");
+        assert_eq!(
+            diff,
+            "--- a/file.txt\n+++ b/file.txt\n@@ -1,2 +1,2 @@\n a\n-b\n+c\n"
+        );
+        assert_eq!(unified_diff("a\n", "a\n", "a/x", "b/x"), "");
+    }
+}
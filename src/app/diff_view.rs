@@ -0,0 +1,188 @@
+// src/app/diff_view.rs
+
+use super::App;
+use super::diff::{self, DiffRow, Hunk};
+use crate::{emsg, msg};
+use std::fs;
+use std::path::PathBuf;
+
+/// 保存済みの内容と現在のバッファを左右に並べて表示する差分表示モードの状態です。
+/// 左右の行は揃えて並べてあり、1つのスクロール位置で両側を同時にスクロールします。
+#[derive(Debug, Clone)]
+pub struct DiffView {
+    pub hunks: Vec<Hunk>,   // 保存済みの内容との差分のハンク
+    pub rows: Vec<DiffRow>, // 左右に並べた表示行
+    pub scroll_y: usize,    // 表示の先頭の表示行
+    pub scroll_x: u16,      // 横方向のスクロール量（左右共通）
+    pub page_height: usize, // 最後に描画した際の表示行数（ページ単位のスクロールに使用）
+}
+
+impl App {
+    /// 差分表示モードを開閉します。開く際は、カーソル行が見える位置から表示します。
+    pub fn toggle_diff_view(&mut self) {
+        if self.diff_view.take().is_some() {
+            return;
+        }
+        let mut view = DiffView {
+            hunks: Vec::new(),
+            rows: Vec::new(),
+            scroll_y: 0,
+            scroll_x: 0,
+            page_height: 0,
+        };
+        self.compute_diff_view(&mut view);
+        let cursor_y = self.editor().cursor.y;
+        view.scroll_y = view
+            .rows
+            .iter()
+            .position(|row| row.new == Some(cursor_y))
+            .unwrap_or(0)
+            .saturating_sub(3); // カーソル行の上に数行の余裕を持たせる
+        if view.hunks.is_empty() {
+            msg!(self, "保存済みの内容から変更はありません。");
+        }
+        self.diff_view = Some(view);
+    }
+
+    /// 差分表示モードの内容を計算し直します。保存した後などに呼び出します。
+    pub fn refresh_diff_view(&mut self) {
+        if let Some(mut view) = self.diff_view.take() {
+            self.compute_diff_view(&mut view);
+            self.diff_view = Some(view);
+        }
+    }
+
+    /// 差分表示を指定した行数だけスクロールします（負の値で上へ）。
+    pub fn scroll_diff_view(&mut self, delta: isize) {
+        if let Some(view) = &mut self.diff_view {
+            view.scroll_y = view.scroll_y.saturating_add_signed(delta);
+            view.clamp_scroll();
+        }
+    }
+
+    /// 差分表示を1ページ分スクロールします。
+    pub fn scroll_diff_view_page(&mut self, down: bool) {
+        let page =
+            self.diff_view
+                .as_ref()
+                .map_or(1, |view| view.page_height.saturating_sub(1).max(1)) as isize;
+        self.scroll_diff_view(if down { page } else { -page });
+    }
+
+    /// 差分表示を横方向にスクロールします（負の値で左へ）。
+    pub fn scroll_diff_view_horizontal(&mut self, delta: i16) {
+        if let Some(view) = &mut self.diff_view {
+            view.scroll_x = view.scroll_x.saturating_add_signed(delta);
+        }
+    }
+
+    /// 差分表示を先頭または末尾までスクロールします。
+    pub fn scroll_diff_view_to_edge(&mut self, end: bool) {
+        if let Some(view) = &mut self.diff_view {
+            view.scroll_y = if end { usize::MAX } else { 0 };
+            view.clamp_scroll();
+        }
+    }
+
+    /// 差分表示で、表示の先頭より後ろ（`forward`がfalseなら前）にある変更箇所までスクロールします。
+    pub fn diff_view_jump_hunk(&mut self, forward: bool) {
+        let Some(view) = &mut self.diff_view else {
+            return;
+        };
+        // 各ハンクの先頭の表示行（ハンクの直前の変更のない行の次）
+        let hunk_rows = view
+            .rows
+            .iter()
+            .enumerate()
+            .filter(|(i, row)| row.changed && (*i == 0 || !view.rows[i - 1].changed))
+            .map(|(i, _)| i);
+        let current = view.scroll_y + 3; // 開く際と同じく、数行の余裕を持たせた位置を基準にする
+        let target = if forward {
+            hunk_rows.clone().find(|&i| i > current)
+        } else {
+            hunk_rows.clone().rev().find(|&i| i < current)
+        };
+        match target {
+            Some(row) => {
+                view.scroll_y = row.saturating_sub(3);
+                view.clamp_scroll();
+            }
+            None => msg!(self, "これ以上の変更箇所はありません。"),
+        }
+    }
+
+    /// 描画領域の高さに合わせて、差分表示のスクロール位置を調整します。描画の前に呼び出します。
+    pub fn adjust_diff_view_scroll(&mut self, height: u16) {
+        if let Some(view) = &mut self.diff_view {
+            view.page_height = height as usize;
+            view.clamp_scroll();
+        }
+    }
+
+    /// 保存済みの内容から現在のバッファへの変更を、unified diff 形式でファイルに書き出します。
+    /// 書き出し先は、編集対象のファイル名に`.diff`を付けたパスです。
+    pub fn export_unified_diff(&mut self) {
+        let Some(target_path) = &self.document().target_path else {
+            emsg!(
+                self,
+                "ファイルに保存されていないため、差分を書き出せません。"
+            );
+            return;
+        };
+        // gitリポジトリ内であれば`git apply`でそのまま適用できるよう、リポジトリからの相対パスを使う
+        let name = match self
+            .document()
+            .git_repo
+            .as_ref()
+            .and_then(|repo| repo.relative_path(target_path))
+        {
+            Some(relative_path) => relative_path,
+            None => target_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        };
+        let patch = diff::unified_diff(
+            &self.document().snapshot_buffer,
+            &self.editor().buffer.to_string(),
+            &format!("a/{}", name),
+            &format!("b/{}", name),
+        );
+        if patch.is_empty() {
+            msg!(self, "保存済みの内容から変更はありません。");
+            return;
+        }
+
+        let mut export_path = target_path.clone().into_os_string();
+        export_path.push(".diff");
+        let export_path = PathBuf::from(export_path);
+        match fs::write(&export_path, patch) {
+            Ok(()) => msg!(self, "差分を {:?} に書き出しました。", export_path),
+            Err(e) => emsg!(
+                self,
+                "差分を {:?} に書き出せませんでした: {}",
+                export_path,
+                e
+            ),
+        }
+    }
+
+    /// 保存済みの内容と現在のバッファの差分から、表示行を計算します。
+    fn compute_diff_view(&self, view: &mut DiffView) {
+        view.hunks = diff::diff_lines(&self.document().snapshot_buffer, &self.editor().buffer);
+        view.rows = diff::align_rows(
+            &view.hunks,
+            self.document().snapshot_buffer.split('\n').count(),
+            self.editor().buffer.len_lines(),
+        );
+        view.clamp_scroll();
+    }
+}
+
+impl DiffView {
+    /// スクロール位置が表示行の範囲を超えないようにします。
+    fn clamp_scroll(&mut self) {
+        let max_scroll = self.rows.len().saturating_sub(self.page_height.max(1));
+        self.scroll_y = self.scroll_y.min(max_scroll);
+    }
+}
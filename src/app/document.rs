@@ -0,0 +1,184 @@
+// src/app/document.rs
+
+use super::LineStatus;
+use super::diff::{self, DiffBase, Hunk};
+use super::editor::Editor;
+use super::editorconfig::{Charset, EditorConfig};
+use super::git::GitRepo;
+use super::history::History;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// 開いているファイル（バッファ）1つ分の状態です。
+/// カーソルやスクロール位置、Undo履歴、差分の状態、一時ファイルはバッファごとに持ちます。
+pub struct Document {
+    pub editor: Editor,
+    pub target_path: Option<PathBuf>, // 編集対象の元のファイルのパス
+    pub temp_path: Option<PathBuf>,   // 編集中の内容を保存する一時ファイルのパス
+    pub history_path: Option<PathBuf>, // Undo履歴を保存するファイルのパス
+    pub original_buffer: String,      // 差分の比較元の内容（diff_baseに応じて読み込む）
+    pub snapshot_buffer: String,      // ファイル読み込み時（保存後は保存時）の内容
+    pub diff_base: DiffBase,          // 差分の比較元
+    pub git_repo: Option<GitRepo>,    // 編集対象のファイルを含むgitリポジトリ
+    pub line_statuses: Vec<LineStatus>, // 各行の差分状態
+    pub diff_hunks: Vec<Hunk>,        // original_bufferとの差分のハンク（行順）
+    pub saved_revision: Option<u64>, // 保存済みの内容と一致するリビジョン（未保存の変更を復元した場合はNone）
+    pub(super) disk_hash: Option<u64>, // 最後に読み込んだか保存した元のファイルの内容のハッシュ（ファイルがなければNone）
+    pub(super) diff_revision: Option<u64>, // 差分を最後に計算した時点のバッファのリビジョン
+    pub(super) last_diff_update: Instant, // 差分を最後に計算した時刻
+    pub(super) recovery_revision: u64, // 一時ファイルに最後に書き込んだ時点のバッファのリビジョン
+    pub(super) last_recovery_write: Instant, // 一時ファイルに最後に書き込んだ時刻
+    pub editorconfig: EditorConfig,    // このファイルに適用される .editorconfig の指定
+    pub encoding: Option<Charset>, // 読み込んだファイルの文字コード（判別できずに読み込めなかった場合はNone）
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Self {
+            editor: Editor::new(String::new()),
+            target_path: None,
+            temp_path: None,
+            history_path: None,
+            original_buffer: String::new(),
+            snapshot_buffer: String::new(),
+            diff_base: DiffBase::Snapshot,
+            git_repo: None,
+            line_statuses: Vec::new(),
+            diff_hunks: Vec::new(),
+            saved_revision: Some(0),
+            disk_hash: None,
+            diff_revision: None,
+            last_diff_update: Instant::now(),
+            recovery_revision: 0,
+            last_recovery_write: Instant::now(),
+            editorconfig: EditorConfig::default(),
+            encoding: Some(Charset::Utf8),
+        }
+    }
+}
+
+impl Document {
+    /// タブなどに表示する名前（ファイル名、なければ「Untitled」）を返します。
+    pub fn name(&self) -> String {
+        self.target_path
+            .as_deref()
+            .and_then(Path::file_name)
+            .map_or_else(
+                || "Untitled".to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            )
+    }
+
+    /// 保存していない変更があるかどうかを返します。
+    pub fn is_modified(&self) -> bool {
+        self.saved_revision != Some(self.editor.revision)
+    }
+
+    /// original_bufferと現在のバッファの差分を計算し、各行の差分状態を更新します。
+    /// original_bufferを変更した場合など、すぐに差分を反映したいときに呼び出します。
+    pub fn calculate_diff_status(&mut self) {
+        self.diff_hunks = diff::diff_lines(&self.original_buffer, &self.editor.buffer);
+        self.line_statuses = diff::line_statuses(&self.diff_hunks, self.editor.buffer.len_lines());
+        self.diff_revision = Some(self.editor.revision);
+        self.last_diff_update = Instant::now();
+    }
+
+    /// バッファが編集されていれば差分を計算し直します。
+    /// キー入力のたびに計算しないよう、前回の計算から一定時間が経つまでは計算を遅らせます。
+    pub fn update_diff_status(&mut self) {
+        const DIFF_UPDATE_INTERVAL: Duration = Duration::from_millis(150);
+        if self.diff_revision == Some(self.editor.revision)
+            || self.last_diff_update.elapsed() < DIFF_UPDATE_INTERVAL
+        {
+            return;
+        }
+        self.calculate_diff_status();
+    }
+
+    /// 差分の計算がバッファの編集に追いついていなければ、すぐに計算し直します。
+    /// ハンクに対する操作は、最新の差分に基づいて行う必要があるためです。
+    pub fn refresh_diff_if_stale(&mut self) {
+        if self.diff_revision != Some(self.editor.revision) {
+            self.calculate_diff_status();
+        }
+    }
+
+    /// gitリポジトリのHEADまたはインデックスから、編集対象のファイルの内容を読み込みます。
+    pub fn read_git_file(&self, base: DiffBase) -> io::Result<Option<Vec<u8>>> {
+        let (Some(repo), Some(path)) = (&self.git_repo, &self.target_path) else {
+            return Ok(None);
+        };
+        let Some(relative_path) = repo.relative_path(path) else {
+            return Ok(None);
+        };
+        match base {
+            DiffBase::Snapshot => Ok(None),
+            DiffBase::Head => repo.read_head_file(&relative_path),
+            DiffBase::Index => repo.read_index_file(&relative_path),
+        }
+    }
+
+    /// Undo履歴を現在のバッファ内容のハッシュとともに書き込みます。
+    /// 履歴が空で、以前の履歴ファイルも存在しない場合は何も書き込みません。
+    pub fn write_history(&self, path: &Path) -> io::Result<()> {
+        if self.editor.history.is_empty() && !path.exists() {
+            return Ok(());
+        }
+        self.editor
+            .history
+            .save_to_file(path, self.editor.buffer.content_hash())
+    }
+
+    /// Undo履歴を、元のファイルの内容を起点とする形に直して書き込みます。
+    /// 一時ファイルを削除して閉じた後は元のファイルから読み込まれるため、閉じるときはこちらを使います。
+    /// 履歴をたどっても元のファイルの内容に戻れない場合は、使えない履歴ファイルを削除します。
+    pub fn write_history_for_disk(&self, path: &Path) -> io::Result<()> {
+        match self.history_from_disk() {
+            Some(history) if history.is_empty() && !path.exists() => Ok(()),
+            Some(history) => history.save_to_file(path, self.disk_hash.unwrap_or_default()),
+            None if path.exists() => fs::remove_file(path),
+            None => Ok(()),
+        }
+    }
+
+    /// 元のファイルの内容（最後に読み込んだか保存した内容）から見たUndo履歴を返します。
+    /// 保存していない変更は元に戻し、やり直しの履歴に移します。
+    /// 保存した後に元に戻していた場合は、元のファイルの内容までやり直します。
+    fn history_from_disk(&self) -> Option<History> {
+        let disk_hash = self.disk_hash?;
+        if self.editor.buffer.content_hash() == disk_hash {
+            return Some(self.editor.history.clone());
+        }
+
+        let mut history = self.editor.history.clone();
+        let mut buffer = self.editor.buffer.clone();
+        while let Some(transaction) = history.undo() {
+            for edit in transaction.edits.iter().rev() {
+                buffer.replace(
+                    edit.offset..edit.offset + edit.inserted.len(),
+                    &edit.deleted,
+                );
+            }
+            if buffer.content_hash() == disk_hash {
+                return Some(history);
+            }
+        }
+
+        let mut history = self.editor.history.clone();
+        let mut buffer = self.editor.buffer.clone();
+        while let Some(transaction) = history.redo() {
+            for edit in &transaction.edits {
+                buffer.replace(
+                    edit.offset..edit.offset + edit.deleted.len(),
+                    &edit.inserted,
+                );
+            }
+            if buffer.content_hash() == disk_hash {
+                return Some(history);
+            }
+        }
+        None
+    }
+}
@@ -1,84 +1,420 @@
 // src/app/editor.rs
 
 use super::cursor::Cursor;
+use super::editorconfig::{self, Charset, EditorConfig};
+use super::history::{Edit, EditKind, History};
+use super::language::LanguageConfig;
+use super::search::{SearchMatch, SearchOptions};
+use super::syntax::{self, Syntax};
+use super::text_buffer::TextBuffer;
 use ratatui::layout::Rect;
+use regex::Regex;
+use std::collections::VecDeque;
 use std::fs;
 use std::io;
-use std::path::Path; // Rect を使用するためにインポート
+use std::path::Path;
+use std::sync::Arc;
+use tree_sitter::InputEdit;
+use unicode_width::UnicodeWidthChar;
+
+/// タブの表示幅の既定値
+const DEFAULT_TAB_WIDTH: usize = 4;
+
+/// 記録しておくバッファの変更の最大数
+const MAX_RECENT_SPLICES: usize = 1000;
+
+/// バッファの1回の変更の位置（バイトオフセット）です。
+/// 同じファイルを表示している他のペインの表示位置を、変更に合わせてずらすために使います。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Splice {
+    revision: u64,  // 変更後のリビジョン
+    start: usize,   // 変更開始位置
+    old_end: usize, // 変更前の変更範囲の終了位置
+    new_end: usize, // 変更後の変更範囲の終了位置
+}
+
+impl Splice {
+    /// 変更前のバイトオフセットを、変更後の位置に移します。
+    /// 変更範囲より後ろの位置はずらし、変更範囲の中の位置は変更開始位置に移します。
+    fn map(&self, offset: usize) -> usize {
+        if offset <= self.start {
+            offset
+        } else if offset >= self.old_end {
+            offset - self.old_end + self.new_end
+        } else {
+            self.start
+        }
+    }
+}
+
+/// 画面上の1行です。折り返し表示では、バッファの1行が複数の画面上の行になります。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScreenRow {
+    pub line: usize,  // バッファの行
+    pub start: usize, // この画面上の行に表示する最初の文字の位置（行頭からの文字数）
+    pub end: usize,   // この画面上の行に表示する最後の文字の次の位置
+}
+
+/// 1行を`width`桁で折り返した場合の、各画面上の行の先頭の文字の位置を返します。（最初は常に0）
+/// 単語（空白以外の文字の並び）の途中では折り返さず、`width`より長い単語だけを途中で折り返します。
+pub fn wrap_line(chars: &[char], tab_width: usize, width: usize) -> Vec<usize> {
+    let width = width.max(1);
+    let mut starts = vec![0];
+    let mut column = 0; // 行頭からの表示上の桁位置（タブの幅の計算に使う）
+    let mut row_width = 0; // 今の画面上の行の幅
+    let mut i = 0;
+    while i < chars.len() {
+        // 空白は1文字ずつ、それ以外は単語ごとに配置する
+        let word_end = if chars[i].is_whitespace() {
+            i + 1
+        } else {
+            (i..chars.len())
+                .find(|&j| chars[j].is_whitespace())
+                .unwrap_or(chars.len())
+        };
+        let mut word_width = 0;
+        for &c in &chars[i..word_end] {
+            word_width += char_display_width(c, column + word_width, tab_width);
+        }
+        if row_width > 0 && row_width + word_width > width {
+            starts.push(i);
+            row_width = 0;
+        }
+        if word_width <= width {
+            row_width += word_width;
+            column += word_width;
+        } else {
+            // 1行に収まらない単語は、文字単位で折り返す
+            for (j, &c) in chars.iter().enumerate().take(word_end).skip(i) {
+                let char_width = char_display_width(c, column, tab_width);
+                if row_width > 0 && row_width + char_width > width {
+                    starts.push(j);
+                    row_width = 0;
+                }
+                row_width += char_width;
+                column += char_width;
+            }
+        }
+        i = word_end;
+    }
+    starts
+}
+
+/// 表示上の桁位置`column`にある文字`c`の表示幅を返します。
+/// タブは次のタブ位置まで、全角文字は2桁、改行などの制御文字は0桁として数えます。
+pub fn char_display_width(c: char, column: usize, tab_width: usize) -> usize {
+    match c {
+        '\t' => {
+            let tab_width = tab_width.max(1);
+            tab_width - column % tab_width
+        }
+        c if c.is_control() => 0,
+        c => c.width().unwrap_or(0),
+    }
+}
 
 /// テキストバッファとカーソルを管理し、編集操作を提供します。
 #[derive(Default)]
 pub struct Editor {
-    pub buffer: String,
+    pub buffer: TextBuffer,
     pub cursor: Cursor,
     pub search_query: String,
-    pub search_matches: Vec<(u16, u16)>, // 検索結果の(y, x)位置 (文字単位)
+    pub search_matches: Vec<SearchMatch>, // 検索結果の位置（出現順にソート済み）
     pub current_search_idx: Option<usize>, // 現在の検索結果のインデックス
-    pub scroll_offset_y: u16,            // 垂直方向のスクロールオフセット (行単位)
-    pub scroll_offset_x: u16,            // 水平方向のスクロールオフセット (文字単位)
+    pub search_options: SearchOptions,    // 正規表現モードなどの検索オプション
+    search_regex: Option<Regex>,          // 最後の検索で使用した正規表現（置換時に使用）
+    pub search_scope: Option<(usize, usize)>, // 選択範囲内検索の対象範囲（バイトオフセット）
+    search_text: Option<(u64, Arc<str>)>, // 検索用に連結したバッファの内容と、その時点のリビジョン
+    pub scroll_offset_y: usize,           // 垂直方向のスクロールオフセット (行単位)
+    pub scroll_offset_x: usize,           // 水平方向のスクロールオフセット (表示上の桁単位)
+    pub history: History,                 // Undo/Redoの履歴
+    pub revision: u64,                    // バッファが変更されるたびに増加するリビジョン番号
+    recent_splices: VecDeque<Splice>,     // 最近のバッファの変更（古い順）
+    pub language: Option<LanguageConfig>, // 編集中のファイルの言語（判定できた場合のみSome）
+    pub syntax: Option<Syntax>, // 構文ハイライト用の構文木（文法がある言語の場合のみSome）
+    pub tab_width: usize,       // タブ文字の表示幅
 }
 
 impl Editor {
     /// 新しいエディタを作成します。
     pub fn new(initial_text: String) -> Self {
         Self {
-            buffer: initial_text,
+            buffer: TextBuffer::new(&initial_text),
             cursor: Cursor::new(0, 0),
             search_query: String::new(),
             search_matches: Vec::new(),
             current_search_idx: None,
+            search_options: SearchOptions::default(),
+            search_regex: None,
+            search_scope: None,
+            search_text: None,
             scroll_offset_y: 0, // 初期スクロールオフセット
             scroll_offset_x: 0, // 初期スクロールオフセット
+            history: History::default(),
+            revision: 0,
+            recent_splices: VecDeque::new(),
+            language: None,
+            syntax: None,
+            tab_width: DEFAULT_TAB_WIDTH,
         }
     }
 
     /// 指定されたパスからテキストを読み込み、エディタバッファを設定します。
     /// （App層によって、これが元ファイルか一時ファイルかが決定されます。）
-    pub fn load_from_file(&mut self, path: &Path) -> io::Result<()> {
-        let content = fs::read_to_string(path)?;
-        self.buffer = content;
+    /// 判別した文字コードを返します。UTF-8とUTF-16（BOMがある場合）以外は、`hint`が latin1 の場合だけ読み込みます。
+    pub fn load_from_file(&mut self, path: &Path, hint: Option<Charset>) -> io::Result<Charset> {
+        let (text, charset) = editorconfig::decode(&fs::read(path)?, hint)?;
+        self.buffer = TextBuffer::new(&text);
+        // 別の内容になったので、これまでの編集履歴は破棄する
+        self.history.clear();
+        self.revision += 1;
+        self.recent_splices.clear(); // 読み込む前の位置は、読み込んだ内容には対応しない
+        if let Some(syntax) = &mut self.syntax {
+            syntax.reset(); // バッファ全体が置き換わったので最初から解析し直す
+        }
         // ファイルを読み込んだら、カーソルを先頭に設定し、選択をクリア
         self.set_cursor_position(0, 0, false);
         // 新しいファイルの内容なのでスクロールオフセットもリセット
         self.scroll_offset_y = 0;
         self.scroll_offset_x = 0;
-        Ok(())
+        Ok(charset)
     }
 
     /// エディタバッファの内容を指定されたパスに書き込みます。
     /// （App層によって、これが元ファイルか一時ファイルかが決定されます。）
-    pub fn save_to_file(&self, path: &Path) -> io::Result<()> {
-        fs::write(path, &self.buffer)
+    /// `config`で行末の空白の削除や最後の改行が指定されていれば、書き込む前にバッファを整えます。（1回のUndoで取り消せます）
+    /// 文字コードは`config`の charset、指定がなければ読み込んだときの文字コード`encoding`で書き込みます。
+    pub fn save_to_file(
+        &mut self,
+        path: &Path,
+        config: &EditorConfig,
+        encoding: Charset,
+    ) -> io::Result<()> {
+        self.apply_save_fixes(config);
+        let charset = config.charset.unwrap_or(encoding);
+        if config.end_of_line.is_some() || charset != Charset::Utf8 {
+            fs::write(path, config.encode(&self.buffer.to_string(), charset)?)
+        } else {
+            self.buffer.write_to_file(path)
+        }
+    }
+
+    /// 保存の前に、EditorConfig の trim_trailing_whitespace と insert_final_newline に従ってバッファを整えます。
+    fn apply_save_fixes(&mut self, config: &EditorConfig) {
+        let text = self.buffer.to_string();
+        let mut edits: Vec<(usize, usize, &str)> = Vec::new(); // 置き換える範囲と文字列（先頭から順）
+        if config.trim_trailing_whitespace == Some(true) {
+            let mut offset = 0;
+            for line in text.split_inclusive('\n') {
+                let content = line.trim_end_matches(['\n', '\r']);
+                let trimmed = content.trim_end_matches([' ', '\t']);
+                if trimmed.len() < content.len() {
+                    edits.push((offset + trimmed.len(), offset + content.len(), ""));
+                }
+                offset += line.len();
+            }
+        }
+        match config.insert_final_newline {
+            Some(true) if !text.is_empty() && !text.ends_with(['\n', '\r']) => {
+                edits.push((text.len(), text.len(), "\n"));
+            }
+            Some(false) => {
+                let end = text.trim_end_matches(['\n', '\r']).len();
+                if end < text.len() {
+                    edits.push((end, text.len(), ""));
+                }
+            }
+            _ => {}
+        }
+        if edits.is_empty() {
+            return;
+        }
+
+        // 後ろから置き換えて、前の範囲のオフセットがずれないようにする
+        let (x, y) = (self.cursor.x, self.cursor.y);
+        self.begin_undo_group();
+        for (start, end, new_text) in edits.into_iter().rev() {
+            self.replace_buffer_range(start, end, new_text);
+        }
+        self.end_undo_group();
+        self.set_cursor_position(x, y, false);
+    }
+
+    /// 行`y`の文字位置`x`を、タブや全角文字の幅を考慮した表示上の桁位置に変換します。
+    pub fn display_column(&self, y: usize, x: usize) -> usize {
+        let mut column = 0;
+        for c in self.buffer.line(y).chars().take(x) {
+            column += char_display_width(c, column, self.tab_width);
+        }
+        column
+    }
+
+    /// `scroll_offset_y`の行から、`height`行分の画面上の行を返します。
+    /// `wrap`が`true`の場合は`width`桁で折り返し、そうでなければバッファの1行を画面上の1行とします。
+    pub fn screen_rows(&self, width: u16, height: u16, wrap: bool) -> Vec<ScreenRow> {
+        let mut rows = Vec::new();
+        for line in self.scroll_offset_y..self.buffer.len_lines() {
+            if rows.len() >= height as usize {
+                break;
+            }
+            let chars: Vec<char> = self.buffer.line(line).chars().collect();
+            let starts = if wrap {
+                wrap_line(&chars, self.tab_width, width as usize)
+            } else {
+                vec![0]
+            };
+            for (i, &start) in starts.iter().enumerate() {
+                let end = starts.get(i + 1).copied().unwrap_or(chars.len());
+                rows.push(ScreenRow { line, start, end });
+            }
+        }
+        rows.truncate(height as usize);
+        rows
+    }
+
+    /// 位置`(x, y)`の文字を表示する画面上の行の、`rows`の中での位置を返します。
+    /// 折り返した位置では次の画面上の行の先頭とし、表示範囲外であれば`None`を返します。
+    pub fn screen_row_of(rows: &[ScreenRow], x: usize, y: usize) -> Option<usize> {
+        rows.iter().rposition(|row| row.line == y && row.start <= x)
+    }
+
+    /// 画面上の行`row`の`column`桁目（描画領域の左端からの桁数）にある文字の位置`(x, y)`を返します。
+    /// 文字のない位置では、その行の末尾（画面上の行がなければバッファの最後の行の末尾）を返します。
+    /// 折り返し表示でない場合は、横方向のスクロールオフセットを加えて求めます。
+    pub fn position_at(
+        &self,
+        rows: &[ScreenRow],
+        row: u16,
+        column: u16,
+        wrap: bool,
+    ) -> (usize, usize) {
+        let Some(screen_row) = rows.get(row as usize) else {
+            let y = match rows.last() {
+                Some(last) => last.line,
+                None => self.buffer.len_lines().saturating_sub(1),
+            };
+            return (self.buffer.line_len_chars(y), y);
+        };
+        let scroll_x = if wrap { 0 } else { self.scroll_offset_x };
+        let target =
+            self.display_column(screen_row.line, screen_row.start) + scroll_x + column as usize;
+
+        let mut x = screen_row.start;
+        let mut display = self.display_column(screen_row.line, screen_row.start);
+        for c in self
+            .buffer
+            .line(screen_row.line)
+            .chars()
+            .skip(screen_row.start)
+            .take(screen_row.end - screen_row.start)
+        {
+            display += char_display_width(c, display, self.tab_width);
+            if display > target {
+                break;
+            }
+            x += 1;
+        }
+        // 折り返した行の末尾では、次の画面上の行の先頭と区別できないため、1文字手前に置く
+        if wrap && x == screen_row.end && x < self.buffer.line_len_chars(screen_row.line) {
+            x = x.saturating_sub(1).max(screen_row.start);
+        }
+        (x, screen_row.line)
+    }
+
+    /// 位置`(x, y)`にある単語を選択します。単語でない文字の場合は、その1文字を選択します。
+    pub fn select_word_at(&mut self, x: usize, y: usize) {
+        let chars: Vec<char> = self.buffer.line(y).chars().collect();
+        let x = x.min(chars.len());
+        let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+        let (start, end) = match chars.get(x) {
+            Some(&c) if is_word_char(c) => {
+                let start = chars[..x]
+                    .iter()
+                    .rposition(|&c| !is_word_char(c))
+                    .map_or(0, |i| i + 1);
+                let end = chars[x..]
+                    .iter()
+                    .position(|&c| !is_word_char(c))
+                    .map_or(chars.len(), |i| x + i);
+                (start, end)
+            }
+            Some(_) => (x, x + 1),
+            None => (x, x),
+        };
+        self.set_cursor_position(start, y, false);
+        self.set_cursor_position(end, y, true);
+    }
+
+    /// `from`行から`to`行まで（順序はどちらでもよい）の行全体を選択します。
+    /// カーソルは`to`の側の端に置きます。
+    pub fn select_lines(&mut self, from: usize, to: usize) {
+        let last = self.buffer.len_lines().saturating_sub(1);
+        let (from, to) = (from.min(last), to.min(last));
+        // 行末の改行まで選択するため、次の行の先頭まで（最後の行では行末まで）を範囲とする
+        let line_end = |y: usize| {
+            if y < last {
+                (0, y + 1)
+            } else {
+                (usize::MAX, y)
+            }
+        };
+        let (anchor, head) = if to >= from {
+            ((0, from), line_end(to))
+        } else {
+            (line_end(from), (0, to))
+        };
+        self.set_cursor_position(anchor.0, anchor.1, false);
+        self.set_cursor_position(head.0, head.1, true);
+    }
+
+    /// 表示位置を`delta`行だけスクロールします。（正の値で下へ）
+    /// 描画時にカーソルの位置へスクロールし直されないよう、カーソルを表示範囲に収まる行に移動します。
+    /// （選択範囲はそのまま残します）
+    pub fn scroll_by(&mut self, delta: i32, viewport_height: u16, padding_y: u16) {
+        let (viewport_height, padding_y) = (viewport_height as usize, padding_y as usize);
+        let total_lines = self.buffer.len_lines();
+        let max_scroll = total_lines.saturating_sub(viewport_height);
+        self.scroll_offset_y = self
+            .scroll_offset_y
+            .saturating_add_signed(delta as isize)
+            .min(max_scroll);
+
+        // adjust_viewport_offset がスクロールし直さない範囲
+        let top = if self.scroll_offset_y == 0 {
+            0
+        } else {
+            self.scroll_offset_y + padding_y
+        };
+        let bottom = if self.scroll_offset_y == max_scroll {
+            total_lines.saturating_sub(1)
+        } else {
+            (self.scroll_offset_y + viewport_height).saturating_sub(padding_y + 1)
+        };
+        let y = self.cursor.y.clamp(top.min(bottom), bottom);
+        if y != self.cursor.y {
+            self.cursor.y = y;
+            self.cursor.x = self.cursor.x.min(self.buffer.line_len_chars(y));
+        }
     }
 
     /// カーソルを新しい論理位置に移動させます。
     /// バッファの境界を考慮して位置を調整し、その後Cursorの状態を更新します。
     /// `extend_selection`が`true`の場合、選択範囲を維持または開始します。
-    pub fn set_cursor_position(&mut self, x: u16, y: u16, extend_selection: bool) {
-        let lines: Vec<&str> = self.buffer.lines().collect();
-        let num_lines = lines.len();
+    pub fn set_cursor_position(&mut self, x: usize, y: usize, extend_selection: bool) {
+        // バッファは空でも常に1行を持つ
+        let num_lines = self.buffer.len_lines();
 
-        let mut final_y = y;
         // Y座標をバッファの行数内にクランプ
-        if num_lines == 0 {
-            final_y = 0;
-        } else {
-            final_y = final_y.min((num_lines - 1) as u16);
-        }
+        let final_y = y.min(num_lines.saturating_sub(1));
 
-        let mut final_x = x;
         // X座標を現在の行の文字数内にクランプ
-        if num_lines > 0 {
-            let current_line_len = lines[final_y as usize].chars().count() as u16;
-            // `u16::MAX`が渡された場合は行末に設定
-            if x == u16::MAX {
-                final_x = current_line_len;
-            } else {
-                final_x = final_x.min(current_line_len);
-            }
+        let current_line_len = self.buffer.line_len_chars(final_y);
+        // `usize::MAX`が渡された場合は行末に設定
+        let final_x = if x == usize::MAX {
+            current_line_len
         } else {
-            final_x = 0;
-        }
+            x.min(current_line_len)
+        };
 
         // Cursorのupdate_positionメソッドを呼び出し、実際のカーソル位置を更新
         self.cursor
@@ -92,54 +428,49 @@ impl Editor {
     ///
     /// **重要:** このメソッドは`scroll_offset_y`と`scroll_offset_x`を設定します。
     /// 実際の描画を行う際は、`scroll_offset_y`から始まり、`scroll_offset_y + viewport_area.height`までの行を描画するのではなく、
-    /// 必ず `self.buffer.lines().count()`（バッファの実際の行数）を超えないようにしてください。
-    /// 例えば、`for i in self.scroll_offset_y .. min(self.scroll_offset_y + viewport_area.height, self.buffer.lines().count() as u16)`
+    /// 必ず `self.buffer.len_lines()`（バッファの実際の行数）を超えないようにしてください。
+    /// 例えば、`for i in self.scroll_offset_y .. min(self.scroll_offset_y + viewport_area.height, self.buffer.len_lines())`
     /// のようにループの終端を制限することで、存在しない行が表示されるのを防ぐことができます。
-    pub fn adjust_viewport_offset(&mut self, viewport_area: Rect) {
+    /// `padding_x`と`padding_y`は、カーソルと描画領域の端の間に空ける桁数と行数です。
+    pub fn adjust_viewport_offset(&mut self, viewport_area: Rect, padding_x: u16, padding_y: u16) {
         let cursor_y = self.cursor.y;
-        let cursor_x = self.cursor.x;
-        let viewport_height = viewport_area.height;
-        let viewport_width = viewport_area.width;
-
-        const PADDING_Y: u16 = 3; // 垂直方向のパディングを調整
-        const PADDING_X: u16 = 5; // 水平方向のパディングを調整
+        // 横方向は表示上の桁で扱う（タブや全角文字は1文字で複数の桁を使う）
+        let cursor_x = self.display_column(cursor_y, self.cursor.x);
+        let viewport_height = viewport_area.height as usize;
+        let viewport_width = viewport_area.width as usize;
+        let (padding_x, padding_y) = (padding_x as usize, padding_y as usize);
 
         // 垂直スクロール (Y軸)
         // カーソルが上端に近づいた場合
-        if cursor_y < self.scroll_offset_y + PADDING_Y {
-            self.scroll_offset_y = cursor_y.saturating_sub(PADDING_Y);
+        if cursor_y < self.scroll_offset_y + padding_y {
+            self.scroll_offset_y = cursor_y.saturating_sub(padding_y);
         }
         // カーソルが下端に近づいた場合
-        if cursor_y >= self.scroll_offset_y + viewport_height.saturating_sub(PADDING_Y) {
+        if cursor_y >= self.scroll_offset_y + viewport_height.saturating_sub(padding_y) {
             self.scroll_offset_y = cursor_y
                 .saturating_add(1)
                 .saturating_sub(viewport_height)
-                .saturating_add(PADDING_Y);
+                .saturating_add(padding_y);
         }
 
         // 水平スクロール (X軸) - 行の長さも考慮
-        let lines: Vec<&str> = self.buffer.lines().collect();
-        let current_line_len = if (cursor_y as usize) < lines.len() {
-            lines[cursor_y as usize].chars().count() as u16
-        } else {
-            0
-        };
+        let current_line_len = self.display_column(cursor_y, usize::MAX);
 
-        if cursor_x < self.scroll_offset_x + PADDING_X {
+        if cursor_x < self.scroll_offset_x + padding_x {
             // カーソルがビューポートの左端より左に移動した場合
-            self.scroll_offset_x = cursor_x.saturating_sub(PADDING_X);
-        } else if cursor_x >= self.scroll_offset_x + viewport_width.saturating_sub(PADDING_X) {
+            self.scroll_offset_x = cursor_x.saturating_sub(padding_x);
+        } else if cursor_x >= self.scroll_offset_x + viewport_width.saturating_sub(padding_x) {
             // カーソルがビューポートの右端より右に移動した場合
             // 注: 右端にカーソルがある場合、その文字は見えるべきなので +1 は不要な場合があるが、
             // ターミナルの表示幅によっては1文字分の余裕が欲しいこともあるため、ここでは簡潔に +1
             self.scroll_offset_x = cursor_x
                 .saturating_add(1)
                 .saturating_sub(viewport_width)
-                .saturating_add(PADDING_X);
+                .saturating_add(padding_x);
         }
 
         // スクロールオフセットがマイナスにならないように、またバッファの範囲を超えないように調整
-        let total_lines = self.buffer.lines().count() as u16;
+        let total_lines = self.buffer.len_lines();
         if total_lines > viewport_height {
             self.scroll_offset_y = self
                 .scroll_offset_y
@@ -173,16 +504,16 @@ impl Editor {
 
     /// カーソルを次の文字に移動します。
     pub fn next_char(&mut self, extend_selection: bool) {
-        let lines: Vec<&str> = self.buffer.lines().collect();
+        let num_lines = self.buffer.len_lines();
         let current_y = self.cursor.y;
         let current_x = self.cursor.x;
 
-        if (current_y as usize) < lines.len() {
-            let current_line_len = lines[current_y as usize].chars().count() as u16;
+        if current_y < num_lines {
+            let current_line_len = self.buffer.line_len_chars(current_y);
             if current_x < current_line_len {
                 // 現在の行内で次の文字へ
                 self.set_cursor_position(current_x.saturating_add(1), current_y, extend_selection);
-            } else if (current_y as usize + 1) < lines.len() {
+            } else if current_y + 1 < num_lines {
                 // 次の行が存在する場合
                 // 行末にいる場合は次の行の先頭へ
                 self.set_cursor_position(0, current_y.saturating_add(1), extend_selection);
@@ -207,8 +538,8 @@ impl Editor {
         } else if current_y > 0 {
             // 行頭にいる場合は前の行の末尾へ
             let previous_line_y = current_y.saturating_sub(1);
-            // 前の行の実際の長さを取得し、X座標を設定（`u16::MAX`でEditorに「行末」を伝える）
-            self.set_cursor_position(u16::MAX, previous_line_y, extend_selection);
+            // 前の行の実際の長さを取得し、X座標を設定（`usize::MAX`でEditorに「行末」を伝える）
+            self.set_cursor_position(usize::MAX, previous_line_y, extend_selection);
         } else {
             // バッファが空または最初の行の先頭にいる場合は何もしない
             self.set_cursor_position(current_x, current_y, extend_selection); // 現在の位置を再設定（実質何もしない）
@@ -228,7 +559,7 @@ impl Editor {
     /// カーソルを現在の行の末尾に移動します。
     pub fn move_cursor_to_line_end(&mut self, extend_selection: bool) {
         let current_y = self.cursor.y;
-        // u16::MAX を渡して、set_cursor_position に行末を計算させる
+        // usize::MAX を渡して、set_cursor_position に行末を計算させる
         self.set_cursor_position(
             self.cursor.get_potential_end_of_line_x(),
             current_y,
@@ -245,7 +576,7 @@ impl Editor {
     /// カーソルをドキュメントの末尾に移動します。
     pub fn move_cursor_to_document_end(&mut self, extend_selection: bool) {
         let (potential_y, potential_x) = self.cursor.get_potential_document_end_pos();
-        // u16::MAX を渡して、set_cursor_position にドキュメントの末尾を計算させる
+        // usize::MAX を渡して、set_cursor_position にドキュメントの末尾を計算させる
         self.set_cursor_position(potential_x, potential_y, extend_selection);
     }
 
@@ -255,22 +586,8 @@ impl Editor {
     pub fn get_selection_range(&self) -> Option<(usize, usize)> {
         if let Some((start_coords, end_coords)) = self.cursor.get_normalized_selection_coords() {
             // (y, x) 座標をバイトオフセットに変換するヘルパー関数
-            let coords_to_byte_offset = |y_coord: u16, x_coord: u16| -> usize {
-                let mut offset = 0;
-                for (i, line) in self.buffer.lines().enumerate() {
-                    if i == y_coord as usize {
-                        // 指定された行と列の文字オフセットまでのバイト数を計算
-                        offset += line
-                            .chars()
-                            .take(x_coord as usize)
-                            .map(|c| c.len_utf8())
-                            .sum::<usize>();
-                        break;
-                    }
-                    offset += line.len();
-                    offset += 1; // 改行文字（LF）のバイト長を仮定
-                }
-                offset
+            let coords_to_byte_offset = |y_coord: usize, x_coord: usize| -> usize {
+                self.buffer.pos_to_byte(y_coord, x_coord)
             };
 
             let start_byte_offset = coords_to_byte_offset(start_coords.0, start_coords.1);
@@ -289,18 +606,18 @@ impl Editor {
 
     /// バッファの内容全体を選択します。
     pub fn select_all(&mut self) {
-        // ドキュメントの先頭にカーソルを移動し、選択を開始
-        self.set_cursor_position(0, 0, true);
+        // ドキュメントの先頭にカーソルを移動し、そこを選択の開始点とする
+        self.set_cursor_position(0, 0, false);
 
         // ドキュメントの末尾にカーソルを移動して選択を完了
-        // set_cursor_position が u16::MAX を適切に処理することを期待
-        self.set_cursor_position(u16::MAX, u16::MAX, true);
+        // set_cursor_position が usize::MAX を適切に処理することを期待
+        self.set_cursor_position(usize::MAX, usize::MAX, true);
     }
 
     /// 選択された範囲のテキストをコピーします。
     pub fn copy_selection(&self) -> Option<String> {
         if let Some((start, end)) = self.get_selection_range() {
-            Some(self.buffer[start..end].to_string())
+            Some(self.buffer.slice_to_string(start..end))
         } else {
             None
         }
@@ -309,26 +626,12 @@ impl Editor {
     /// 選択された範囲のテキストを切り取り、バッファから削除します。
     pub fn cut_selection(&mut self) -> Option<String> {
         if let Some((start_byte_offset, end_byte_offset)) = self.get_selection_range() {
-            let cut_text = self.buffer[start_byte_offset..end_byte_offset].to_string();
-
-            // 切り取り後のカーソル位置を、選択範囲の開始位置に調整する
-            // バイトオフセットから新しいカーソル座標を計算
-            let lines_before_cut: Vec<&str> = self.buffer[..start_byte_offset].lines().collect();
-            let new_y = (lines_before_cut.len() as u16).saturating_sub(1); // 切り取り開始行
-            let new_x = if new_y == u16::MAX {
-                // バッファの先頭で切り取りの場合
-                0
-            } else {
-                lines_before_cut
-                    .last()
-                    .map_or(0, |last_line| last_line.chars().count() as u16)
-            };
-
-            self.buffer
-                .replace_range(start_byte_offset..end_byte_offset, ""); // 選択範囲を削除
+            let cut_text = self
+                .buffer
+                .slice_to_string(start_byte_offset..end_byte_offset);
 
-            // カーソル位置を切り取った後の位置に設定し、選択を解除
-            self.set_cursor_position(new_x, new_y, false);
+            // 選択範囲を削除し、カーソルを選択範囲の開始位置に移動（選択は解除）
+            self.apply_edit(start_byte_offset, end_byte_offset, "", EditKind::Other);
             Some(cut_text)
         } else {
             None
@@ -336,33 +639,19 @@ impl Editor {
     }
 
     /// 指定されたテキストをカーソル位置にペーストします。
+    /// 選択範囲がある場合は、選択範囲をテキストで置き換えます。
     pub fn paste_text(&mut self, text: &str) {
-        if self.cursor.is_selecting() {
-            self.cut_selection(); // 選択範囲がある場合はまず切り取る
-        }
-
-        let current_offset = self.get_cursor_byte_offset(); // 現在のカーソル位置のバイトオフセット
-        self.buffer.insert_str(current_offset, text); // テキストを挿入
-
-        let new_cursor_offset = current_offset + text.len(); // 新しいカーソル位置のバイトオフセット
-        self.set_cursor_from_byte_offset(new_cursor_offset, false); // カーソル位置を更新し、選択を解除
+        let (start, end) = self.get_edit_range();
+        self.apply_edit(start, end, text, EditKind::Other);
     }
 
     /// カーソル位置に文字を挿入します。
+    /// 選択範囲がある場合は、選択範囲を文字で置き換えます。
     pub fn insert_char(&mut self, c: char) {
-        if self.cursor.is_selecting() {
-            self.cut_selection(); // 選択範囲がある場合はまず切り取る
-        }
-
-        let current_offset = self.get_cursor_byte_offset(); // 現在のカーソル位置のバイトオフセット
-        self.buffer.insert(current_offset, c); // 文字を挿入
-
-        // カーソル位置を更新（改行の場合は次の行の先頭、それ以外はX座標を1進める）
-        if c == '\n' {
-            self.set_cursor_position(0, self.cursor.y.saturating_add(1), false);
-        } else {
-            self.set_cursor_position(self.cursor.x.saturating_add(1), self.cursor.y, false);
-        }
+        let (start, end) = self.get_edit_range();
+        let mut char_buf = [0u8; 4];
+        // カーソル位置は挿入した文字の直後に更新される（改行の場合は次の行の先頭になる）
+        self.apply_edit(start, end, c.encode_utf8(&mut char_buf), EditKind::Insert);
     }
 
     /// カーソル位置の文字、または選択範囲を削除します。（Backspace相当）
@@ -375,17 +664,9 @@ impl Editor {
         let current_offset = self.get_cursor_byte_offset(); // 現在のカーソル位置のバイトオフセット
         if current_offset > 0 {
             // 文字の境界を考慮して、前の文字のバイト開始位置を特定
-            let mut char_start_offset = current_offset;
-            // マルチバイト文字の途中にカーソルがある場合は、文字の先頭まで戻る
-            while char_start_offset > 0 && !self.buffer.is_char_boundary(char_start_offset - 1) {
-                char_start_offset -= 1;
-            }
-            if char_start_offset > 0 {
-                // 前の文字（char_start_offset - 1 から始まる文字）を削除
-                self.buffer.remove(char_start_offset - 1);
-                // カーソル位置を新しい位置に調整（選択はクリア）
-                self.set_cursor_from_byte_offset(char_start_offset - 1, false);
-            }
+            let char_start_offset = self.buffer.prev_char_boundary(current_offset);
+            // 前の文字を削除し、カーソル位置を新しい位置に調整（選択はクリア）
+            self.apply_edit(char_start_offset, current_offset, "", EditKind::Backspace);
         }
     }
 
@@ -397,30 +678,11 @@ impl Editor {
         }
 
         let current_offset = self.get_cursor_byte_offset(); // 現在のカーソル位置のバイトオフセット
-        if current_offset < self.buffer.len() {
-            // 現在のカーソル位置から次の文字のバイト終了位置を特定
-            let mut char_start_for_deletion = current_offset;
-            // マルチバイト文字の途中にカーソルがある場合は、文字の先頭まで進む
-            while char_start_for_deletion < self.buffer.len()
-                && !self.buffer.is_char_boundary(char_start_for_deletion)
-            {
-                char_start_for_deletion += 1;
-            }
-
-            // `chars().next()` を使って現在のカーソル位置の文字（または次の文字）のバイト長を取得
-            let char_len_to_delete = self.buffer[char_start_for_deletion..]
-                .chars()
-                .next()
-                .map_or(0, |c| c.len_utf8());
-
-            if char_len_to_delete > 0 {
-                self.buffer.replace_range(
-                    char_start_for_deletion..(char_start_for_deletion + char_len_to_delete),
-                    "",
-                );
-                // Deleteキーの場合、カーソル位置は変更しない（選択はクリア）
-                self.set_cursor_position(self.cursor.x, self.cursor.y, false);
-            }
+        if current_offset < self.buffer.len_bytes() {
+            // 現在のカーソル位置にある文字のバイト終了位置を特定
+            let char_end_offset = self.buffer.next_char_boundary(current_offset);
+            // Deleteキーの場合、カーソル位置は変更しない（選択はクリア）
+            self.apply_edit(current_offset, char_end_offset, "", EditKind::DeleteForward);
         }
     }
 
@@ -431,84 +693,284 @@ impl Editor {
         end_byte_offset: usize,
         new_text: &str,
     ) {
-        if start_byte_offset <= end_byte_offset && end_byte_offset <= self.buffer.len() {
-            self.buffer
-                .replace_range(start_byte_offset..end_byte_offset, new_text);
-            let new_cursor_offset = start_byte_offset + new_text.len(); // 置換後の新しいカーソル位置
-            self.set_cursor_from_byte_offset(new_cursor_offset, false); // カーソル位置を更新し、選択をクリア
+        if start_byte_offset <= end_byte_offset && end_byte_offset <= self.buffer.len_bytes() {
+            // 置換後の新しいカーソル位置は置換したテキストの直後（選択はクリア）
+            self.apply_edit(
+                start_byte_offset,
+                end_byte_offset,
+                new_text,
+                EditKind::Other,
+            );
         }
     }
 
-    /// 現在のカーソル位置をバイトオフセットに変換します。
-    fn get_cursor_byte_offset(&self) -> usize {
-        let mut offset = 0;
-        for (current_y, line) in self.buffer.lines().enumerate() {
-            if current_y == self.cursor.y as usize {
-                // 現在の行のカーソルX位置までのバイト数を計算
-                offset += line
-                    .chars()
-                    .take(self.cursor.x as usize)
-                    .map(|c| c.len_utf8())
-                    .sum::<usize>();
-                break;
+    /// 選択範囲にかかる各行（選択がなければカーソル行）のコメントを切り替えます。
+    /// 空行以外の全ての行がコメントであれば外し、そうでなければ言語の行コメント記号を付けます。
+    /// 行コメントのない言語では、各行をブロックコメントの記号で囲みます。1回のUndoで元に戻せます。
+    /// 言語が分からないか、言語にコメントの記号がない場合は何もせず`false`を返します。
+    pub fn toggle_comment(&mut self) -> bool {
+        let Some(language) = &self.language else {
+            return false;
+        };
+        let (open, close) = match (language.line_comment, language.block_comment) {
+            (Some(token), _) => (token, ""),
+            (None, Some((open, close))) => (open, close),
+            (None, None) => return false,
+        };
+
+        // 選択範囲が行頭で終わる場合、その行は対象にしない
+        let (start, end) = self.get_edit_range();
+        let (first, _) = self.buffer.byte_to_pos(start);
+        let (last, last_x) = self.buffer.byte_to_pos(end);
+        let last = if last > first && last_x == 0 {
+            last - 1
+        } else {
+            last
+        };
+        let lines: Vec<(usize, String)> = (first..=last)
+            .map(|y| (y, self.buffer.line(y).to_string()))
+            .filter(|(_, line)| !line.trim().is_empty())
+            .collect();
+        let indent_len = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+        let content_end = |line: &str| line.trim_end_matches([' ', '\t', '\r']).len();
+        let uncomment = !lines.is_empty()
+            && lines.iter().all(|(_, line)| {
+                let content = &line[indent_len(line)..content_end(line)];
+                content.len() >= open.len() + close.len()
+                    && content.starts_with(open)
+                    && content.ends_with(close)
+            });
+
+        // 置き換える範囲と文字列（先頭から順、重なりなし）
+        let mut edits: Vec<(usize, usize, String)> = Vec::new();
+        let min_indent = lines
+            .iter()
+            .map(|(_, line)| indent_len(line))
+            .min()
+            .unwrap_or(0);
+        for (y, line) in &lines {
+            let line_start = self.buffer.line_to_byte(*y);
+            if uncomment {
+                let open_start = indent_len(line);
+                let mut open_end = open_start + open.len();
+                let mut close_start = content_end(line) - close.len();
+                if close_start > open_end && line[open_end..].starts_with(' ') {
+                    open_end += 1;
+                }
+                if !close.is_empty() && close_start > open_end && line[..close_start].ends_with(' ')
+                {
+                    close_start -= 1;
+                }
+                edits.push((
+                    line_start + open_start,
+                    line_start + open_end,
+                    String::new(),
+                ));
+                if !close.is_empty() {
+                    let close_end = content_end(line);
+                    edits.push((
+                        line_start + close_start,
+                        line_start + close_end,
+                        String::new(),
+                    ));
+                }
+            } else {
+                // インデントの異なる行は、最も浅いインデントの位置に記号をそろえる
+                let at = line_start + min_indent;
+                edits.push((at, at, format!("{} ", open)));
+                if !close.is_empty() {
+                    let at = line_start + content_end(line);
+                    edits.push((at, at, format!(" {}", close)));
+                }
+            }
+        }
+
+        // 選択範囲とカーソルは、編集後も同じ文字の位置に残す
+        let shift = |offset: usize| {
+            let mut shifted = offset;
+            for (start, end, text) in &edits {
+                let (start, end) = (*start, *end);
+                if offset >= end {
+                    shifted = shifted + text.len() - (end - start);
+                } else if offset > start {
+                    shifted -= offset - start;
+                }
             }
-            offset += line.len(); // 行のバイト長
-            offset += 1; // 改行文字 (LF) のバイト長を仮定
+            shifted
+        };
+        let anchor = self
+            .cursor
+            .selection_start
+            .map(|(x, y)| shift(self.buffer.pos_to_byte(y, x)));
+        let head = shift(self.get_cursor_byte_offset());
+
+        self.begin_undo_group();
+        for (start, end, text) in edits.iter().rev() {
+            self.replace_buffer_range(*start, *end, text);
+        }
+        self.end_undo_group();
+        if let Some(anchor) = anchor {
+            self.set_cursor_from_byte_offset(anchor, false);
+            self.set_cursor_from_byte_offset(head, true);
+        } else {
+            self.set_cursor_from_byte_offset(head, false);
         }
-        offset
+        true
     }
 
-    /// バイトオフセットからカーソル位置 (x, y) を設定します。
-    fn set_cursor_from_byte_offset(&mut self, byte_offset: usize, extend_selection: bool) {
-        let mut current_offset = 0;
-        let mut y = 0;
-        let mut x_chars_count = 0;
+    /// 直前の編集操作を元に戻します。
+    /// 元に戻す操作がなかった場合は`false`を返します。
+    pub fn undo(&mut self) -> bool {
+        let Some(transaction) = self.history.undo().cloned() else {
+            return false;
+        };
+        // 変更を逆順に取り消していく
+        for edit in transaction.edits.iter().rev() {
+            self.splice(
+                edit.offset,
+                edit.offset + edit.inserted.len(),
+                &edit.deleted,
+            );
+        }
+        // カーソルと選択範囲を変更前の状態に戻す
+        self.cursor = transaction.cursor_before;
+        true
+    }
 
-        for line in self.buffer.lines() {
-            let line_len_bytes = line.len();
-            // 改行文字を含む行の全長。Rustのlines()は改行を含まないので、ここで+1する
-            let line_with_newline_len = line_len_bytes + 1; // LFを仮定
-
-            // バイトオフセットが現在の行内にあるかチェック
-            if byte_offset >= current_offset && byte_offset <= current_offset + line_len_bytes {
-                let relative_offset = byte_offset - current_offset;
-
-                x_chars_count = 0;
-                let mut current_byte_in_line = 0;
-                for c in line.chars() {
-                    let char_len_bytes = c.len_utf8();
-                    if current_byte_in_line + char_len_bytes > relative_offset {
-                        // オフセットが現在の文字の途中にあれば、その文字の先頭にカーソルを置く
-                        break;
-                    }
-                    current_byte_in_line += char_len_bytes;
-                    x_chars_count += 1;
-                }
+    /// 元に戻した編集操作をやり直します。
+    /// やり直す操作がなかった場合は`false`を返します。
+    pub fn redo(&mut self) -> bool {
+        let Some(transaction) = self.history.redo().cloned() else {
+            return false;
+        };
+        for edit in &transaction.edits {
+            self.splice(
+                edit.offset,
+                edit.offset + edit.deleted.len(),
+                &edit.inserted,
+            );
+        }
+        self.cursor = transaction.cursor_after;
+        true
+    }
 
-                // 行の文字数を超えないように調整
-                x_chars_count = x_chars_count.min(line.chars().count());
-                break;
-            }
+    /// 複数の編集を1回のUndoで取り消せるようにまとめ始めます。
+    pub fn begin_undo_group(&mut self) {
+        self.history.begin_group(self.cursor);
+    }
+
+    /// `begin_undo_group`で開始した編集のまとまりを閉じます。
+    pub fn end_undo_group(&mut self) {
+        self.history.end_group();
+    }
+
+    /// 編集操作の対象となる範囲（選択範囲、なければカーソル位置）をバイトオフセットで返します。
+    fn get_edit_range(&self) -> (usize, usize) {
+        self.get_selection_range().unwrap_or_else(|| {
+            let offset = self.get_cursor_byte_offset();
+            (offset, offset)
+        })
+    }
+
+    /// バッファの指定範囲を置き換え、その変更を履歴に記録します。
+    /// 全ての編集操作はこのメソッドを経由します。
+    /// カーソルは挿入したテキストの直後に移動し、選択は解除されます。
+    fn apply_edit(&mut self, start: usize, end: usize, text: &str, kind: EditKind) {
+        let cursor_before = self.cursor;
+        let deleted = self.buffer.slice_to_string(start..end);
+        self.splice(start, end, text);
+        self.set_cursor_from_byte_offset(start + text.len(), false);
+        self.history.record(
+            Edit {
+                offset: start,
+                deleted,
+                inserted: text.to_string(),
+            },
+            kind,
+            cursor_before,
+            self.cursor,
+        );
+    }
+
+    /// 履歴に記録せずにバッファの指定範囲を置き換えます。（Undo/Redoの適用用）
+    fn splice(&mut self, start: usize, end: usize, text: &str) {
+        // 構文木を増分的に更新できるよう、編集前の位置を控えておく
+        let positions = self.syntax.is_some().then(|| {
+            (
+                syntax::byte_to_point(&self.buffer, start),
+                syntax::byte_to_point(&self.buffer, end),
+            )
+        });
+        self.buffer.replace(start..end, text);
+        self.revision += 1;
+        if self.recent_splices.len() >= MAX_RECENT_SPLICES {
+            self.recent_splices.pop_front();
+        }
+        self.recent_splices.push_back(Splice {
+            revision: self.revision,
+            start,
+            old_end: end,
+            new_end: start + text.len(),
+        });
+
+        if let (Some(syntax), Some((start_position, old_end_position))) =
+            (&mut self.syntax, positions)
+        {
+            let new_end = start + text.len();
+            syntax.edit(&InputEdit {
+                start_byte: start,
+                old_end_byte: end,
+                new_end_byte: new_end,
+                start_position,
+                old_end_position,
+                new_end_position: syntax::byte_to_point(&self.buffer, new_end),
+            });
+        }
+    }
+
+    /// リビジョン`revision`の時点のバイトオフセットを、その後の変更に合わせて現在のバッファの位置に移します。
+    /// その後の変更が全ては記録に残っていない場合は`None`を返します。
+    pub fn map_offset_since(&self, revision: u64, offset: usize) -> Option<usize> {
+        let count = usize::try_from(self.revision.checked_sub(revision)?).ok()?;
+        let first = self.recent_splices.len().checked_sub(count)?;
+        let splices = self.recent_splices.range(first..);
+        if count > 0 && self.recent_splices[first].revision != revision + 1 {
+            return None;
+        }
+        Some(splices.fold(offset, |offset, splice| splice.map(offset)))
+    }
 
-            current_offset += line_with_newline_len;
-            y += 1;
+    /// 構文木が編集によって古くなっていれば再解析します。描画の前に呼び出します。
+    pub fn update_syntax(&mut self) {
+        if let Some(syntax) = &mut self.syntax {
+            syntax.update(&self.buffer);
         }
+    }
+
+    /// 現在のカーソル位置をバイトオフセットに変換します。
+    fn get_cursor_byte_offset(&self) -> usize {
+        self.buffer.pos_to_byte(self.cursor.y, self.cursor.x)
+    }
+
+    /// バイトオフセットからカーソル位置 (x, y) を設定します。
+    fn set_cursor_from_byte_offset(&mut self, byte_offset: usize, extend_selection: bool) {
+        let (y, x) = self.buffer.byte_to_pos(byte_offset);
         // set_cursor_positionを通じて、Cursorのupdate_positionを呼び出す
-        self.set_cursor_position(x_chars_count as u16, y as u16, extend_selection);
+        self.set_cursor_position(x, y, extend_selection);
     }
 
     /// カーソル位置の括弧に対応する括弧の位置を検索します。
     /// 戻り値は (y, x) のタプルです。
-    pub fn find_matching_paren(&self) -> Option<(u16, u16)> {
-        let lines: Vec<&str> = self.buffer.lines().collect();
-        let current_y = self.cursor.y as usize;
-        let current_x = self.cursor.x as usize;
+    pub fn find_matching_paren(&self) -> Option<(usize, usize)> {
+        let num_lines = self.buffer.len_lines();
+        let current_y = self.cursor.y;
+        let current_x = self.cursor.x;
 
-        if current_y >= lines.len() {
+        if current_y >= num_lines {
             return None;
         }
 
-        let current_line_chars: Vec<char> = lines[current_y].chars().collect();
+        let current_line_chars: Vec<char> = self.buffer.line(current_y).chars().collect();
         // カーソルが現在の行の文字数の境界にいる場合も考慮
         if current_x > current_line_chars.len() {
             return None;
@@ -545,12 +1007,12 @@ impl Editor {
                     balance -= 1;
                 }
                 if balance == 0 {
-                    return Some((current_y as u16, x_idx as u16));
+                    return Some((current_y, x_idx));
                 }
             }
             // 後続の行
-            for (y_idx, _item) in lines.iter().enumerate().skip(current_y + 1) {
-                let line_chars: Vec<char> = lines[y_idx].chars().collect();
+            for y_idx in (current_y + 1)..num_lines {
+                let line_chars: Vec<char> = self.buffer.line(y_idx).chars().collect();
                 for (x_idx, ch) in line_chars.iter().enumerate() {
                     if *ch == open_paren {
                         balance += 1;
@@ -558,7 +1020,7 @@ impl Editor {
                         balance -= 1;
                     }
                     if balance == 0 {
-                        return Some((y_idx as u16, x_idx as u16));
+                        return Some((y_idx, x_idx));
                     }
                 }
             }
@@ -579,12 +1041,12 @@ impl Editor {
                     balance -= 1;
                 }
                 if balance == 0 {
-                    return Some((current_y as u16, x_idx as u16));
+                    return Some((current_y, x_idx));
                 }
             }
             // 前の行
             for y_idx in (0..current_y).rev() {
-                let line_chars: Vec<char> = lines[y_idx].chars().collect();
+                let line_chars: Vec<char> = self.buffer.line(y_idx).chars().collect();
                 for x_idx in (0..line_chars.len()).rev() {
                     let ch = line_chars[x_idx];
                     if ch == close_paren {
@@ -593,7 +1055,7 @@ impl Editor {
                         balance -= 1;
                     }
                     if balance == 0 {
-                        return Some((y_idx as u16, x_idx as u16));
+                        return Some((y_idx, x_idx));
                     }
                 }
             }
@@ -605,15 +1067,14 @@ impl Editor {
     /// 実際の補完は、言語サーバープロトコル (LSP) などで行われるのが一般的です。
     pub fn get_completion_suggestions(&self) -> Vec<String> {
         let mut suggestions = Vec::new();
-        let lines: Vec<&str> = self.buffer.lines().collect();
-        let current_y = self.cursor.y as usize;
+        let current_y = self.cursor.y;
 
-        if current_y >= lines.len() {
+        if current_y >= self.buffer.len_lines() {
             return suggestions;
         }
 
-        let current_line_chars: Vec<char> = lines[current_y].chars().collect();
-        let current_x = self.cursor.x as usize;
+        let current_line_chars: Vec<char> = self.buffer.line(current_y).chars().collect();
+        let current_x = self.cursor.x;
 
         // カーソル前の単語を取得
         let mut prefix = String::new();
@@ -641,7 +1102,8 @@ impl Editor {
 
         // 仮の識別子リスト (バッファ内の単語から取得)
         let mut identifiers: std::collections::HashSet<String> = std::collections::HashSet::new();
-        for line_str in self.buffer.lines() {
+        for line in self.buffer.lines() {
+            let line_str = line.to_string();
             // 英数字とアンダースコア以外の文字で単語を分割
             for word in line_str.split(|c: char| !(c.is_alphanumeric() || c == '_')) {
                 if !word.is_empty() && !keywords.contains(&word) {
@@ -670,32 +1132,195 @@ impl Editor {
     }
 
     /// 指定されたクエリでバッファを検索し、マッチ位置を保存します。
-    pub fn search(&mut self, query: &str) {
+    /// カーソル位置以降で最初のマッチ（なければ先頭のマッチ）にカーソルを移動します。
+    /// バッファ全体を1つの文字列として検索するため、正規表現は行をまたいでマッチできます。
+    /// 正規表現モードでクエリが正しい正規表現でない場合はエラーを返します。
+    pub fn search(&mut self, query: &str) -> Result<(), regex::Error> {
         self.search_query = query.to_string();
         self.search_matches.clear();
         self.current_search_idx = None;
+        self.search_regex = None;
 
         if query.is_empty() {
-            return;
+            return Ok(());
         }
 
-        // 全ての行を走査し、クエリにマッチする位置を収集
-        for (y, line) in self.buffer.lines().enumerate() {
-            // `match_indices` はバイトオフセットを返すため、文字オフセットに変換が必要
-            for (byte_x, _) in line.match_indices(query) {
-                // バイトオフセットから文字オフセットに変換
-                let char_x = line[..byte_x].chars().count();
-                self.search_matches.push((y as u16, char_x as u16));
+        let regex = self.search_options.build_regex(query)?;
+        let text = self.search_text();
+        let (scope_start, scope_end) = self.active_search_scope().unwrap_or((0, text.len()));
+
+        // 検索範囲の終端でテキストを切り詰め、範囲の先頭から順にマッチを収集する
+        // （`find_at`は切り詰める前の文脈を見るため、範囲の先頭でも`\b`や`^`が正しく判定される）
+        let haystack = &text[..scope_end];
+        let mut pos = scope_start;
+        while pos <= haystack.len() {
+            let Some(found) = regex.find_at(haystack, pos) else {
+                break;
+            };
+            if found.is_empty() {
+                // 空文字列へのマッチ（`^`や`a*`など）は検索結果として扱わず、1文字進めて続ける
+                pos = match haystack[found.end()..].chars().next() {
+                    Some(c) => found.end() + c.len_utf8(),
+                    None => break,
+                };
+                continue;
             }
+            let (y, x) = self.buffer.byte_to_pos(found.start());
+            self.search_matches.push(SearchMatch {
+                y,
+                x,
+                start: found.start(),
+                end: found.end(),
+            });
+            pos = found.end();
         }
+        self.search_regex = Some(regex);
 
         if !self.search_matches.is_empty() {
-            self.current_search_idx = Some(0);
-            let (y, x) = self.search_matches[0];
-            self.set_cursor_position(x, y, false); // 最初のマッチ位置にカーソルを移動（選択はクリア）
+            // カーソル位置以降の最初のマッチを探す（見つからなければ先頭に戻る）
+            let cursor_pos_y_x = (self.cursor.y, self.cursor.x);
+            let first_idx = self
+                .search_matches
+                .iter()
+                .position(|found| (found.y, found.x) >= cursor_pos_y_x)
+                .unwrap_or(0);
+            self.current_search_idx = Some(first_idx);
+            let found = self.search_matches[first_idx];
+            self.set_cursor_position(found.x, found.y, false); // マッチ位置にカーソルを移動（選択はクリア）
+        }
+        Ok(())
+    }
+
+    /// 検索に使う、バッファ全体を連結した文字列を返します。
+    /// 検索バーへの入力のたびにバッファを連結し直さないよう、バッファが変更されるまで使い回します。
+    fn search_text(&mut self) -> Arc<str> {
+        match &self.search_text {
+            Some((revision, text)) if *revision == self.revision => text.clone(),
+            _ => {
+                let text: Arc<str> = self.buffer.to_string().into();
+                self.search_text = Some((self.revision, text.clone()));
+                text
+            }
         }
     }
 
+    /// 選択範囲内検索が有効な場合、その対象範囲を返します。
+    pub fn active_search_scope(&self) -> Option<(usize, usize)> {
+        if !self.search_options.in_selection {
+            return None;
+        }
+        self.search_scope.map(|(start, end)| {
+            (
+                start.min(self.buffer.len_bytes()),
+                end.min(self.buffer.len_bytes()),
+            )
+        })
+    }
+
+    /// 現在の検索結果を置換テキストで置き換え、その後ろの次の検索結果に移動します。
+    /// 正規表現モードでは、置換テキスト中の`$1`や`${name}`がキャプチャグループに展開されます。
+    /// 置換した場合は`true`を返します。
+    pub fn replace_current_match(&mut self, replacement: &str) -> bool {
+        let (Some(idx), Some(regex)) = (self.current_search_idx, self.search_regex.clone()) else {
+            return false;
+        };
+        let found = self.search_matches[idx];
+        let text = self.search_text();
+        let new_text = self.expand_replacement(&regex, &text, &found, replacement);
+        self.replace_buffer_range(found.start, found.end, &new_text);
+        self.shift_search_scope(found.start, found.end, new_text.len());
+
+        // 置換後のカーソル位置（置換したテキストの直後）から検索し直す
+        let query = self.search_query.clone();
+        let _ = self.search(&query); // 直前に同じクエリで検索できているため失敗しない
+        true
+    }
+
+    /// 全ての検索結果を置換テキストで置き換えます。
+    /// 全ての置換は1回のUndoでまとめて元に戻せます。置換した件数を返します。
+    pub fn replace_all_matches(&mut self, replacement: &str) -> usize {
+        let Some(regex) = self.search_regex.clone() else {
+            return 0;
+        };
+        // 置換前のバッファを基準に、全ての置換後テキストを先に求めておく
+        let text = self.search_text();
+        let replacements: Vec<(usize, usize, String)> = self
+            .search_matches
+            .iter()
+            .map(|found| {
+                (
+                    found.start,
+                    found.end,
+                    self.expand_replacement(&regex, &text, found, replacement),
+                )
+            })
+            .collect();
+        if replacements.is_empty() {
+            return 0;
+        }
+
+        // 後ろから置換することで、前にあるマッチのオフセットがずれないようにする
+        self.begin_undo_group();
+        for (start, end, new_text) in replacements.iter().rev() {
+            self.replace_buffer_range(*start, *end, new_text);
+            self.shift_search_scope(*start, *end, new_text.len());
+        }
+        self.end_undo_group();
+
+        let query = self.search_query.clone();
+        let _ = self.search(&query);
+        replacements.len()
+    }
+
+    /// 検索結果1件に対する置換後のテキストを求めます。
+    /// 正規表現モードの場合のみ、キャプチャグループの参照を展開します。
+    fn expand_replacement(
+        &self,
+        regex: &Regex,
+        text: &str,
+        found: &SearchMatch,
+        replacement: &str,
+    ) -> String {
+        if !self.search_options.regex {
+            return replacement.to_string();
+        }
+        // 検索時と同じ範囲・位置から正規表現を再適用してキャプチャを取得する
+        let haystack = match self.active_search_scope() {
+            Some((_, scope_end)) => &text[..scope_end],
+            None => text,
+        };
+        let mut expanded = String::new();
+        match regex.captures_at(haystack, found.start) {
+            Some(captures) => captures.expand(replacement, &mut expanded),
+            None => expanded.push_str(replacement),
+        }
+        expanded
+    }
+
+    /// `start..end`が長さ`new_len`のテキストに置換されたのに合わせて、検索範囲をずらします。
+    fn shift_search_scope(&mut self, start: usize, end: usize, new_len: usize) {
+        let Some((scope_start, scope_end)) = &mut self.search_scope else {
+            return;
+        };
+        let shift = |offset: usize| (offset + new_len).saturating_sub(end - start);
+        if end <= *scope_start {
+            // 範囲より前での置換は、範囲全体をずらす
+            *scope_start = shift(*scope_start);
+            *scope_end = shift(*scope_end);
+        } else if start < *scope_end {
+            // 範囲内での置換は、範囲の終端だけをずらす
+            *scope_end = shift(*scope_end);
+        }
+    }
+
+    /// 検索結果と選択範囲内検索の対象範囲をクリアします。（検索クエリは次回の検索のために残します）
+    pub fn clear_search(&mut self) {
+        self.search_matches.clear();
+        self.current_search_idx = None;
+        self.search_scope = None;
+        self.search_text = None; // 検索しない間はバッファの複製を持たない
+    }
+
     /// 次の検索結果に移動します。
     pub fn next_search_match(&mut self) {
         if self.search_matches.is_empty() {
@@ -707,12 +1332,13 @@ impl Editor {
         let mut closest_next_idx = 0; // 現在位置より後のマッチがない場合のフォールバック（最初のマッチ）
 
         // 現在のカーソル位置より「後」にあるマッチを検索
-        for (i, &(match_y, match_x)) in self.search_matches.iter().enumerate() {
+        for (i, found) in self.search_matches.iter().enumerate() {
+            let (match_y, match_x) = (found.y, found.x);
             // Cursorの座標は (x, y) なので、比較を (y, x) に合わせて行う
             if (match_y > current_cursor_pos.1) || // 次の行
-               (match_y == current_cursor_pos.1 && match_x >= current_cursor_pos.0)
+               (match_y == current_cursor_pos.1 && match_x > current_cursor_pos.0)
             {
-                // 同じ行でカーソル位置以降
+                // 同じ行でカーソル位置より後（カーソル位置のマッチ自体は除く）
                 closest_next_idx = i;
                 next_idx_found = true;
                 break;
@@ -724,8 +1350,8 @@ impl Editor {
         let final_idx = if next_idx_found { closest_next_idx } else { 0 };
 
         self.current_search_idx = Some(final_idx);
-        let (y, x) = self.search_matches[final_idx];
-        self.set_cursor_position(x, y, false); // 検索結果に移動（選択はクリア）
+        let found = self.search_matches[final_idx];
+        self.set_cursor_position(found.x, found.y, false); // 検索結果に移動（選択はクリア）
     }
 
     /// 前の検索結果に移動します。
@@ -739,13 +1365,14 @@ impl Editor {
         let mut closest_prev_idx = self.search_matches.len().saturating_sub(1); // 現在位置より前のマッチがない場合のフォールバック（最後のマッチ）
 
         // 現在のカーソル位置より「前」にあるマッチを検索 (リストを逆順に走査)
-        for (i, &(match_y, match_x)) in self.search_matches.iter().rev().enumerate() {
+        for (i, found) in self.search_matches.iter().rev().enumerate() {
+            let (match_y, match_x) = (found.y, found.x);
             let original_idx = self.search_matches.len() - 1 - i; // 元のインデックスに変換
             // Cursorの座標は (x, y) なので、比較を (y, x) に合わせて行う
             if (match_y < current_cursor_pos.1) || // 前の行
-               (match_y == current_cursor_pos.1 && match_x <= current_cursor_pos.0)
+               (match_y == current_cursor_pos.1 && match_x < current_cursor_pos.0)
             {
-                // 同じ行でカーソル位置以前
+                // 同じ行でカーソル位置より前（カーソル位置のマッチ自体は除く）
                 closest_prev_idx = original_idx;
                 prev_idx_found = true;
                 break;
@@ -760,7 +1387,274 @@ impl Editor {
         };
 
         self.current_search_idx = Some(final_idx);
-        let (y, x) = self.search_matches[final_idx];
-        self.set_cursor_position(x, y, false); // 検索結果に移動（選択はクリア）
+        let found = self.search_matches[final_idx];
+        self.set_cursor_position(found.x, found.y, false); // 検索結果に移動（選択はクリア）
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::language::LanguageRegistry;
+
+    /// 言語の行コメント記号でコメントを付け外しでき、1回のUndoで元に戻せることを確かめます。
+    #[test]
+    fn toggles_line_comments() {
+        let text = "fn main() {\n    let a = 1;\n\n        let b = 2;\n}\n";
+        let mut editor = Editor::new(text.to_string());
+        editor.language = LanguageRegistry::default().by_name("rust").cloned();
+        editor.set_cursor_position(4, 1, false);
+        editor.set_cursor_position(0, 4, true); // 行頭で終わる選択範囲の最後の行は対象外
+        assert!(editor.toggle_comment());
+        assert_eq!(
+            editor.buffer.to_string(),
+            "fn main() {\n    // let a = 1;\n\n    //     let b = 2;\n}\n"
+        );
+        // 選択範囲は同じ文字の位置に残る
+        assert_eq!(editor.cursor.selection_start, Some((7, 1)));
+        assert_eq!((editor.cursor.x, editor.cursor.y), (0, 4));
+
+        assert!(editor.toggle_comment());
+        assert_eq!(editor.buffer.to_string(), text);
+        assert!(editor.undo());
+        assert!(editor.undo());
+        assert_eq!(editor.buffer.to_string(), text);
+    }
+
+    /// 行コメントのない言語では、各行をブロックコメントで囲むことを確かめます。
+    #[test]
+    fn toggles_block_comments_without_line_comment() {
+        let mut editor = Editor::new("# Title\n".to_string());
+        editor.language = LanguageRegistry::default().by_name("markdown").cloned();
+        assert!(editor.toggle_comment());
+        assert_eq!(editor.buffer.to_string(), "<!-- # Title -->\n");
+        assert!(editor.toggle_comment());
+        assert_eq!(editor.buffer.to_string(), "# Title\n");
+
+        editor.language = LanguageRegistry::default().by_name("json").cloned();
+        assert!(!editor.toggle_comment());
+    }
+
+    /// 正規表現モードの置換で、`$1`や`${name}`がキャプチャグループに展開されることを確かめます。
+    #[test]
+    fn replace_expands_capture_groups() {
+        let mut editor = Editor::new("let a = 1;\nlet bc = 22;\n".to_string());
+        editor.search_options.regex = true;
+        editor.search(r"let (\w+) = (?P<value>\d+)").unwrap();
+        assert_eq!(editor.search_matches.len(), 2);
+        assert!(editor.replace_current_match("const $1: i32 = ${value}"));
+        assert_eq!(
+            editor.buffer.to_string(),
+            "const a: i32 = 1;\nlet bc = 22;\n"
+        );
+
+        // 正規表現モードでなければ、`$1`はそのまま挿入される
+        let mut editor = Editor::new("a-b".to_string());
+        editor.search("-").unwrap();
+        assert_eq!(editor.replace_all_matches("$1"), 1);
+        assert_eq!(editor.buffer.to_string(), "a$1b");
+    }
+
+    /// 全ての置換が1回のUndoで元に戻せることを確かめます。
+    #[test]
+    fn replace_all_is_one_undo_step() {
+        let mut editor = Editor::new("foo foo\nfoo\n".to_string());
+        editor.insert_char('x');
+        editor.search("foo").unwrap();
+        assert_eq!(editor.replace_all_matches("barbaz"), 3);
+        assert_eq!(editor.buffer.to_string(), "xbarbaz barbaz\nbarbaz\n");
+        assert!(editor.search_matches.is_empty());
+
+        assert!(editor.undo());
+        assert_eq!(editor.buffer.to_string(), "xfoo foo\nfoo\n");
+        assert!(editor.redo());
+        assert_eq!(editor.buffer.to_string(), "xbarbaz barbaz\nbarbaz\n");
+    }
+
+    /// 検索結果を1件ずつ、置換したり飛ばしたりしながら進められることを確かめます。
+    #[test]
+    fn steps_through_matches_replacing_or_skipping() {
+        let mut editor = Editor::new("cat cat cat cat".to_string());
+        editor.search("cat").unwrap();
+        assert_eq!(editor.current_search_idx, Some(0));
+
+        assert!(editor.replace_current_match("dog")); // 1件目を置換
+        assert_eq!(editor.current_search_idx, Some(0));
+        assert_eq!(editor.cursor.x, 4);
+        editor.next_search_match(); // 2件目は飛ばす
+        assert_eq!(editor.cursor.x, 8);
+        assert!(editor.replace_current_match("dog")); // 3件目を置換
+        assert_eq!(editor.buffer.to_string(), "dog cat dog cat");
+
+        // 最後の検索結果を置換すると、先頭の残っている検索結果に戻る
+        assert!(editor.replace_current_match("dog"));
+        assert_eq!(editor.buffer.to_string(), "dog cat dog dog");
+        assert_eq!(editor.search_matches.len(), 1);
+        assert_eq!(editor.cursor.x, 4);
+        assert!(editor.replace_current_match("dog"));
+        assert!(!editor.replace_current_match("dog"));
+        assert_eq!(editor.buffer.to_string(), "dog dog dog dog");
+    }
+
+    /// 検索用に連結した内容は使い回されるが、バッファを編集すると検索結果に反映されることを確かめます。
+    #[test]
+    fn search_reflects_edits_after_cached_search() {
+        let mut editor = Editor::new("foo bar\n".to_string());
+        editor.search("ba").unwrap();
+        editor.search("bar").unwrap();
+        assert_eq!(editor.search_matches.len(), 1);
+
+        editor.set_cursor_position(0, 1, false);
+        for c in "bar".chars() {
+            editor.insert_char(c);
+        }
+        editor.search("bar").unwrap();
+        assert_eq!(editor.search_matches.len(), 2);
+        assert_eq!(
+            (editor.search_matches[1].y, editor.search_matches[1].x),
+            (1, 0)
+        );
+    }
+
+    /// 連続した入力・Backspace・Deleteが、それぞれ1回のUndoで取り消されることを確かめます。
+    #[test]
+    fn merges_runs_of_typing_and_deletion() {
+        let mut editor = Editor::new(String::new());
+        for c in "hello".chars() {
+            editor.insert_char(c);
+        }
+        editor.insert_char('\n');
+        for c in "world".chars() {
+            editor.insert_char(c);
+        }
+        assert!(editor.undo());
+        assert_eq!(editor.buffer.to_string(), "hello\n");
+        assert!(editor.undo());
+        assert_eq!(editor.buffer.to_string(), "hello");
+        assert!(editor.undo());
+        assert_eq!(editor.buffer.to_string(), "");
+        assert!(!editor.undo());
+
+        let mut editor = Editor::new("abcdef".to_string());
+        editor.set_cursor_position(4, 0, false);
+        editor.delete_previous_char();
+        editor.delete_previous_char();
+        editor.delete_current_char();
+        editor.delete_current_char();
+        assert_eq!(editor.buffer.to_string(), "ab");
+        assert!(editor.undo());
+        assert_eq!(editor.buffer.to_string(), "abef");
+        assert!(editor.undo());
+        assert_eq!(editor.buffer.to_string(), "abcdef");
+        assert!(!editor.undo());
+    }
+
+    /// Undoで変更前の、Redoで変更後のカーソルと選択範囲が復元されることを確かめます。
+    #[test]
+    fn undo_and_redo_restore_cursor_and_selection() {
+        let mut editor = Editor::new("one two\n".to_string());
+        editor.set_cursor_position(4, 0, false);
+        editor.set_cursor_position(7, 0, true);
+        let selected = editor.cursor;
+        editor.insert_char('2');
+        assert_eq!(editor.buffer.to_string(), "one 2\n");
+        let after = editor.cursor;
+        assert_eq!((after.x, after.y), (5, 0));
+
+        editor.set_cursor_position(0, 1, false);
+        assert!(editor.undo());
+        assert_eq!(editor.buffer.to_string(), "one two\n");
+        assert_eq!(editor.cursor, selected);
+
+        assert!(editor.redo());
+        assert_eq!(editor.buffer.to_string(), "one 2\n");
+        assert_eq!(editor.cursor, after);
+        assert!(!editor.redo());
+
+        // やり直した操作に、続けて入力した文字は連結されない
+        editor.insert_char('!');
+        assert!(editor.undo());
+        assert_eq!(editor.buffer.to_string(), "one 2\n");
+    }
+
+    /// 読み込んだバイト列を、そのまま保存すると元と同じバイト列に戻ることを確かめます。
+    #[test]
+    fn save_round_trips_loaded_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        let cases: Vec<(Vec<u8>, Charset)> = vec![
+            (b"hello\nworld\n".to_vec(), Charset::Utf8),
+            (
+                [&[0xEF, 0xBB, 0xBF][..], "こんにちは\r\n".as_bytes()].concat(),
+                Charset::Utf8Bom,
+            ),
+            (
+                Charset::Utf16Le.encode("日本語\n").unwrap(),
+                Charset::Utf16Le,
+            ),
+            (Charset::Utf16Be.encode("abc\n").unwrap(), Charset::Utf16Be),
+        ];
+        for (bytes, expected) in cases {
+            fs::write(&path, &bytes).unwrap();
+            let mut editor = Editor::new(String::new());
+            let charset = editor.load_from_file(&path, None).unwrap();
+            assert_eq!(charset, expected);
+            assert!(!editor.buffer.to_string().starts_with('\u{feff}'));
+            editor
+                .save_to_file(&path, &EditorConfig::default(), charset)
+                .unwrap();
+            assert_eq!(fs::read(&path).unwrap(), bytes, "{:?}", expected);
+        }
+    }
+
+    /// latin1 は .editorconfig で指定されている場合だけ読み込み、同じバイト列で保存することを確かめます。
+    #[test]
+    fn latin1_round_trips_only_with_hint() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("latin1.txt");
+        let bytes = b"caf\xe9\n".to_vec();
+        fs::write(&path, &bytes).unwrap();
+
+        let mut editor = Editor::new(String::new());
+        assert!(editor.load_from_file(&path, None).is_err());
+        let charset = editor.load_from_file(&path, Some(Charset::Latin1)).unwrap();
+        assert_eq!(editor.buffer.to_string(), "café\n");
+        editor
+            .save_to_file(&path, &EditorConfig::default(), charset)
+            .unwrap();
+        assert_eq!(fs::read(&path).unwrap(), bytes);
+    }
+
+    /// UTF-8 でも UTF-16 でもないファイル（Shift_JIS など）は読み込まないことを確かめます。
+    #[test]
+    fn refuses_undecodable_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sjis.txt");
+        fs::write(&path, b"\x93\xfa\x96\x7b\x8c\xea\n").unwrap(); // 「日本語」の Shift_JIS
+        let mut editor = Editor::new(String::new());
+        let error = editor.load_from_file(&path, None).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    /// 65535行を超えるファイルでも、カーソルとスクロール位置が正しく扱えることを確かめます。
+    #[test]
+    fn handles_lines_beyond_u16_range() {
+        let text = (0..70_000)
+            .map(|i| format!("line {}\n", i))
+            .collect::<String>();
+        let mut editor = Editor::new(text);
+        editor.set_cursor_position(3, 69_999, false);
+        assert_eq!((editor.cursor.x, editor.cursor.y), (3, 69_999));
+
+        let area = Rect::new(0, 0, 80, 20);
+        editor.adjust_viewport_offset(area, 0, 0);
+        assert_eq!(editor.scroll_offset_y, 69_999 - 19);
+        let rows = editor.screen_rows(area.width, area.height, false);
+        let row = Editor::screen_row_of(&rows, editor.cursor.x, editor.cursor.y).unwrap();
+        assert_eq!(rows[row].line, 69_999);
+        assert_eq!(editor.position_at(&rows, row as u16, 3, false), (3, 69_999));
+
+        editor.insert_char('x');
+        assert_eq!(editor.buffer.line(69_999).to_string(), "linxe 69999");
     }
 }
@@ -0,0 +1,605 @@
+// src/app/editorconfig.rs
+
+use super::App;
+use super::settings::{is_specified, resolve_settings};
+use crate::{emsg, msg};
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// EditorConfig の設定ファイルの名前
+const EDITORCONFIG_FILE_NAME: &str = ".editorconfig";
+
+/// インデントに使う文字です。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentStyle {
+    Tab,
+    Space,
+}
+
+/// 保存時の改行コードです。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndOfLine {
+    Lf,
+    CrLf,
+    Cr,
+}
+
+impl EndOfLine {
+    /// 改行コードの文字列を返します。
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EndOfLine::Lf => "\n",
+            EndOfLine::CrLf => "\r\n",
+            EndOfLine::Cr => "\r",
+        }
+    }
+}
+
+/// ファイルの文字コードです。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    Latin1,
+    Utf8,
+    Utf8Bom,
+    Utf16Be,
+    Utf16Le,
+}
+
+impl Charset {
+    /// テキストをこの文字コードでバイト列に変換します。
+    /// BOMを付ける文字コード（`utf-8-bom`、UTF-16）の場合は、先頭にBOMを付けます。
+    pub fn encode(self, text: &str) -> io::Result<Vec<u8>> {
+        let bytes = match self {
+            Charset::Utf8 => text.as_bytes().to_vec(),
+            Charset::Utf8Bom => [&[0xEF, 0xBB, 0xBF], text.as_bytes()].concat(),
+            Charset::Latin1 => text
+                .chars()
+                .map(|c| {
+                    u8::try_from(u32::from(c)).map_err(|_| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("latin1 で表せない文字 {:?} が含まれています", c),
+                        )
+                    })
+                })
+                .collect::<io::Result<Vec<u8>>>()?,
+            Charset::Utf16Be => [0xFE, 0xFF]
+                .into_iter()
+                .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+                .collect(),
+            Charset::Utf16Le => [0xFF, 0xFE]
+                .into_iter()
+                .chain(text.encode_utf16().flat_map(u16::to_le_bytes))
+                .collect(),
+        };
+        Ok(bytes)
+    }
+}
+
+/// ファイルの内容をテキストに変換し、判別した文字コードと合わせて返します。
+/// UTF-8（BOMの有無を区別する）と、BOMのあるUTF-16を判別します。BOMはテキストには含めません。
+/// それ以外のファイルは、`hint`（.editorconfig の charset）が latin1 の場合だけ latin1 として読み込み、
+/// そうでなければ保存時に内容を壊さないよう、エラーとして読み込みません。
+pub fn decode(bytes: &[u8], hint: Option<Charset>) -> io::Result<(String, Charset)> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    if let Some(rest) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF])
+        && let Ok(text) = std::str::from_utf8(rest)
+    {
+        return Ok((text.to_string(), Charset::Utf8Bom));
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Ok((text.to_string(), Charset::Utf8));
+    }
+    let utf16 = |rest: &[u8], to_u16: fn([u8; 2]) -> u16| {
+        if !rest.len().is_multiple_of(2) {
+            return Err(invalid("UTF-16 のファイルの長さが奇数です"));
+        }
+        let units = rest.chunks(2).map(|pair| to_u16([pair[0], pair[1]]));
+        char::decode_utf16(units)
+            .collect::<Result<String, _>>()
+            .map_err(|_| invalid("UTF-16 として正しくない内容が含まれています"))
+    };
+    match bytes {
+        [0xFE, 0xFF, rest @ ..] => Ok((utf16(rest, u16::from_be_bytes)?, Charset::Utf16Be)),
+        [0xFF, 0xFE, rest @ ..] => Ok((utf16(rest, u16::from_le_bytes)?, Charset::Utf16Le)),
+        _ if hint == Some(Charset::Latin1) => Ok((
+            bytes.iter().map(|&b| char::from(b)).collect(),
+            Charset::Latin1,
+        )),
+        _ => Err(invalid(
+            "UTF-8 でも UTF-16 でもない文字コードのファイルは開けません",
+        )),
+    }
+}
+
+/// ファイルに適用される EditorConfig のプロパティです。指定のないプロパティは`None`です。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EditorConfig {
+    pub indent_style: Option<IndentStyle>,
+    pub indent_size: Option<usize>, // インデント1段の幅（`tab`の場合はtab_widthと同じ）
+    pub tab_width: Option<usize>,   // タブ文字の表示幅
+    pub end_of_line: Option<EndOfLine>,
+    pub charset: Option<Charset>,
+    pub trim_trailing_whitespace: Option<bool>,
+    pub insert_final_newline: Option<bool>,
+    pub max_line_length: Option<usize>, // ルーラーを表示する桁（`off`の場合はNone）
+    pub sources: Vec<PathBuf>,          // 適用した .editorconfig のパス（ルートに近い順）
+}
+
+impl EditorConfig {
+    /// 指定されたファイルに適用されるプロパティを、ファイルのあるディレクトリから上にたどって集めます。
+    /// `root = true`の .editorconfig があれば、それより上は探しません。
+    /// 読み込めなかった .editorconfig があれば、その内容をエラーの一覧で返します。
+    pub fn for_path(path: &Path) -> (Self, Vec<String>) {
+        let path = &std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let mut files = Vec::new();
+        let mut errors = Vec::new();
+        let mut directory = path.parent();
+        while let Some(dir) = directory {
+            let config_path = dir.join(EDITORCONFIG_FILE_NAME);
+            match fs::read_to_string(&config_path) {
+                Ok(text) => {
+                    let file = IniFile::parse(&text);
+                    let root = file.root;
+                    files.push((dir.to_path_buf(), config_path, file));
+                    if root {
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => errors.push(format!("{:?} を読み込めません: {}", config_path, e)),
+            }
+            directory = dir.parent();
+        }
+
+        // ルートに近いファイルから順に適用し、近いファイル・後のセクションの値で上書きする
+        let mut properties: HashMap<String, String> = HashMap::new();
+        let mut sources = Vec::new();
+        for (dir, config_path, file) in files.into_iter().rev() {
+            let Ok(relative) = path.strip_prefix(&dir) else {
+                continue;
+            };
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            let mut applied = false;
+            for section in &file.sections {
+                if !section.matches(&relative) {
+                    continue;
+                }
+                applied = true;
+                for (key, value) in &section.properties {
+                    if value == "unset" {
+                        properties.remove(key);
+                    } else {
+                        properties.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            if applied {
+                sources.push(config_path);
+            }
+        }
+
+        let mut config = Self::from_properties(&properties);
+        config.sources = sources;
+        (config, errors)
+    }
+
+    /// プロパティの値からEditorConfigを作成します。解釈できない値は指定がないものとして扱います。
+    fn from_properties(properties: &HashMap<String, String>) -> Self {
+        let get = |key: &str| properties.get(key).map(String::as_str);
+        let number = |key: &str| {
+            get(key)
+                .and_then(|v| v.parse::<usize>().ok())
+                .filter(|n| *n > 0)
+        };
+        let boolean = |key: &str| match get(key) {
+            Some("true") => Some(true),
+            Some("false") => Some(false),
+            _ => None,
+        };
+
+        let indent_style = match get("indent_style") {
+            Some("tab") => Some(IndentStyle::Tab),
+            Some("space") => Some(IndentStyle::Space),
+            _ => None,
+        };
+        // indent_size と tab_width は、片方だけ指定された場合にもう片方から補う
+        let tab_width = number("tab_width");
+        let indent_size_is_tab = get("indent_size") == Some("tab")
+            || (get("indent_size").is_none() && indent_style == Some(IndentStyle::Tab));
+        let indent_size = if indent_size_is_tab {
+            tab_width
+        } else {
+            number("indent_size")
+        };
+        let tab_width = tab_width.or(indent_size);
+
+        Self {
+            indent_style,
+            indent_size,
+            tab_width,
+            end_of_line: match get("end_of_line") {
+                Some("lf") => Some(EndOfLine::Lf),
+                Some("crlf") => Some(EndOfLine::CrLf),
+                Some("cr") => Some(EndOfLine::Cr),
+                _ => None,
+            },
+            charset: match get("charset") {
+                Some("latin1") => Some(Charset::Latin1),
+                Some("utf-8") => Some(Charset::Utf8),
+                Some("utf-8-bom") => Some(Charset::Utf8Bom),
+                Some("utf-16be") => Some(Charset::Utf16Be),
+                Some("utf-16le") => Some(Charset::Utf16Le),
+                _ => None,
+            },
+            trim_trailing_whitespace: boolean("trim_trailing_whitespace"),
+            insert_final_newline: boolean("insert_final_newline"),
+            max_line_length: number("max_line_length"),
+            sources: Vec::new(),
+        }
+    }
+
+    /// 保存するテキストを、改行コードの指定に従って変換し、文字コード`charset`のバイト列にします。
+    pub fn encode(&self, text: &str, charset: Charset) -> io::Result<Vec<u8>> {
+        let text = match self.end_of_line {
+            Some(end_of_line) => {
+                // 混在している改行コードも全て指定の改行コードに揃える
+                let mut converted = String::with_capacity(text.len());
+                let mut chars = text.chars().peekable();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\r' => {
+                            chars.next_if_eq(&'\n');
+                            converted.push_str(end_of_line.as_str());
+                        }
+                        '\n' => converted.push_str(end_of_line.as_str()),
+                        c => converted.push(c),
+                    }
+                }
+                converted
+            }
+            None => text.to_string(),
+        };
+        charset.encode(&text)
+    }
+}
+
+/// 解釈した .editorconfig の内容です。
+struct IniFile {
+    root: bool,             // `root = true`が指定されているか
+    sections: Vec<Section>, // セクション（ファイル内の順）
+}
+
+/// .editorconfig の1つのセクション（`[*.rs]`など）です。
+struct Section {
+    pattern: Option<Regex>,  // ファイル名のパターン（解釈できない場合はNone）
+    ranges: Vec<(i64, i64)>, // パターン内の`{1..3}`の範囲（正規表現のキャプチャの順）
+    properties: Vec<(String, String)>, // プロパティ（名前と値は小文字にする）
+}
+
+impl IniFile {
+    /// .editorconfig の内容を解釈します。解釈できない行は無視します。
+    fn parse(text: &str) -> Self {
+        let mut root = false;
+        let mut sections: Vec<Section> = Vec::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if let Some(glob) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                let (pattern, ranges) = glob_to_regex(glob);
+                sections.push(Section {
+                    pattern,
+                    ranges,
+                    properties: Vec::new(),
+                });
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let key = key.trim().to_lowercase();
+            let value = value.trim().to_lowercase();
+            match sections.last_mut() {
+                Some(section) => section.properties.push((key, value)),
+                // 最初のセクションより前（プリアンブル）では root だけを扱う
+                None if key == "root" => root = value == "true",
+                None => {}
+            }
+        }
+        Self { root, sections }
+    }
+}
+
+impl Section {
+    /// .editorconfig のあるディレクトリからの相対パスが、このセクションのパターンに一致するかを返します。
+    fn matches(&self, relative_path: &str) -> bool {
+        let Some(pattern) = &self.pattern else {
+            return false;
+        };
+        let Some(captures) = pattern.captures(relative_path) else {
+            return false;
+        };
+        // `{1..3}`の範囲は、正規表現では数字として一致させた後に値を確かめる
+        // （`{a,b{1..3}}`の一致しなかった選択肢の中の範囲は、確かめる必要がない）
+        self.ranges
+            .iter()
+            .enumerate()
+            .all(|(i, (low, high))| match captures.get(i + 1) {
+                Some(m) => m
+                    .as_str()
+                    .parse::<i64>()
+                    .is_ok_and(|n| (*low..=*high).contains(&n)),
+                None => true,
+            })
+    }
+}
+
+/// .editorconfig のパターンを正規表現に変換します。`{1..3}`の範囲も合わせて返します。
+/// `/`を含まないパターンは、どのディレクトリのファイル名にも一致します。
+fn glob_to_regex(glob: &str) -> (Option<Regex>, Vec<(i64, i64)>) {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut ranges = Vec::new();
+    let body = translate_glob(&chars, &mut ranges);
+    let pattern = if glob.contains('/') {
+        format!("^{}$", body.strip_prefix('/').unwrap_or(&body))
+    } else {
+        format!("^(?:.*/)?{}$", body)
+    };
+    (Regex::new(&pattern).ok(), ranges)
+}
+
+/// パターンの文字列を正規表現の文字列に変換します。（`{a,b}`の中身は再帰的に変換する）
+fn translate_glob(chars: &[char], ranges: &mut Vec<(i64, i64)>) -> String {
+    let mut regex = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if i + 1 < chars.len() => {
+                regex.push_str(&regex::escape(&chars[i + 1].to_string()));
+                i += 1;
+            }
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                // `a/**/b`は`a/b`にも一致する
+                if (regex.is_empty() || regex.ends_with('/')) && chars.get(i + 2) == Some(&'/') {
+                    regex.push_str("(?:.*/)?");
+                    i += 2;
+                } else {
+                    regex.push_str(".*");
+                    i += 1;
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => match chars[i + 1..].iter().position(|&c| c == ']') {
+                Some(len) => {
+                    let class: String = chars[i + 1..i + 1 + len].iter().collect();
+                    let (negated, class) = match class.strip_prefix('!') {
+                        Some(rest) => (true, rest.to_string()),
+                        None => (false, class),
+                    };
+                    let escaped = class.replace('\\', "\\\\").replace('[', "\\[");
+                    regex.push_str(&format!("[{}{}]", if negated { "^" } else { "" }, escaped));
+                    i += len + 1;
+                }
+                None => regex.push_str("\\["),
+            },
+            '{' => match matching_brace(chars, i) {
+                Some(end) => {
+                    let inner: String = chars[i + 1..end].iter().collect();
+                    if let Some((low, high)) = inner
+                        .split_once("..")
+                        .and_then(|(l, h)| Some((l.parse::<i64>().ok()?, h.parse::<i64>().ok()?)))
+                    {
+                        ranges.push((low.min(high), low.max(high)));
+                        regex.push_str("([+-]?[0-9]+)");
+                    } else if inner.contains(',') {
+                        let alternatives: Vec<String> = split_alternatives(&chars[i + 1..end])
+                            .into_iter()
+                            .map(|alternative| translate_glob(alternative, ranges))
+                            .collect();
+                        regex.push_str(&format!("(?:{})", alternatives.join("|")));
+                    } else {
+                        // `{single}`のように選択肢が1つの場合は文字どおりに扱う
+                        regex.push_str(&regex::escape(&format!("{{{}}}", inner)));
+                    }
+                    i = end;
+                }
+                None => regex.push_str("\\{"),
+            },
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+    regex
+}
+
+/// `start`の`{`に対応する`}`の位置を返します。
+fn matching_brace(chars: &[char], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// `{a,b}`の中身を、入れ子の`{}`の中以外の`,`で分割します。
+fn split_alternatives(chars: &[char]) -> Vec<&[char]> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&chars[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    parts.push(&chars[start..]);
+    parts
+}
+
+impl App {
+    /// 指定されたファイルに適用される .editorconfig を読み込み、タブの表示幅を設定します。
+    pub fn load_editorconfig(&mut self, index: usize) {
+        let (config, errors) = match &self.documents[index].target_path {
+            Some(path) => EditorConfig::for_path(path),
+            None => (EditorConfig::default(), Vec::new()),
+        };
+        for error in errors {
+            emsg!(self, "EditorConfig: {}", error);
+        }
+        if !config.sources.is_empty() && config != self.documents[index].editorconfig {
+            msg!(self, "EditorConfig {:?} を適用しました。", config.sources);
+        }
+        let document = &mut self.documents[index];
+        document.editorconfig = config;
+        // タブの表示幅は、EditorConfig の指定がなければ設定の tab_size に合わせる
+        let language = document.editor.language.as_ref().map(|l| l.name);
+        let tab_size = resolve_settings(&self.settings_files, language).tab_size;
+        document.editor.tab_width = document.editorconfig.tab_width.unwrap_or(tab_size);
+    }
+
+    /// Tabキーで挿入するインデント1段分の文字列を返します。
+    /// EditorConfig の indent_style と indent_size、設定の tab_size、言語のインデント単位の順に優先します。
+    pub fn indent_text(&self) -> String {
+        let config = &self.document().editorconfig;
+        if config.indent_style == Some(IndentStyle::Tab) {
+            return "\t".to_string();
+        }
+        if let Some(indent_size) = config.indent_size {
+            return " ".repeat(indent_size);
+        }
+        let language = self.editor().language.as_ref();
+        let tab_size_specified =
+            is_specified(&self.settings_files, language.map(|l| l.name), "tab_size");
+        match language {
+            Some(language) if !tab_size_specified => language.indent_unit.to_string(),
+            _ => " ".repeat(self.language_settings().tab_size),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// パターンが .editorconfig のあるディレクトリからの相対パスに一致するかを返します。
+    fn matches(glob: &str, path: &str) -> bool {
+        let (pattern, ranges) = glob_to_regex(glob);
+        Section {
+            pattern,
+            ranges,
+            properties: Vec::new(),
+        }
+        .matches(path)
+    }
+
+    /// EditorConfig の仕様の例を元にしたパターンと、一致するか（`true`）しないか（`false`）の表です。
+    #[test]
+    fn glob_matches_spec_examples() {
+        let cases = [
+            // `/`を含まないパターンは、どのディレクトリのファイル名にも一致する
+            ("*", "foo.rs", true),
+            ("*", "src/foo.rs", true),
+            ("*.rs", "src/app/foo.rs", true),
+            ("*.rs", "foo.rs.bak", false),
+            ("Makefile", "sub/Makefile", true),
+            // `*`と`?`は`/`に一致しない
+            ("?.txt", "a.txt", true),
+            ("?.txt", "ab.txt", false),
+            ("src/*.rs", "src/app/foo.rs", false),
+            // `/`を含むパターンは、.editorconfig のあるディレクトリからのパス
+            ("src/*.rs", "src/foo.rs", true),
+            ("src/*.rs", "other/src/foo.rs", false),
+            ("/top.txt", "top.txt", true),
+            ("/top.txt", "sub/top.txt", false),
+            // `**`は`/`を含む任意の文字列に一致する
+            ("lib/**.js", "lib/a.js", true),
+            ("lib/**.js", "lib/x/y/a.js", true),
+            ("**/foo.txt", "foo.txt", true),
+            ("**/foo.txt", "a/b/foo.txt", true),
+            ("a/**/b", "a/b", true),
+            ("a/**/b", "a/x/y/b", true),
+            ("a/**/b", "ab", false),
+            // `{a,b}`は選択肢のいずれか
+            ("*.{js,py}", "a.js", true),
+            ("*.{js,py}", "x/a.py", true),
+            ("*.{js,py}", "a.rb", false),
+            ("{package.json,.travis.yml}", ".travis.yml", true),
+            ("{single}", "{single}", true),
+            ("{single}", "single", false),
+            // `{1..3}`は範囲内の整数
+            ("file{1..3}.txt", "file1.txt", true),
+            ("file{1..3}.txt", "file3.txt", true),
+            ("file{1..3}.txt", "file4.txt", false),
+            ("file{1..3}.txt", "filex.txt", false),
+            ("file{-3..-1}.txt", "file-2.txt", true),
+            ("{foo,bar{1..3}}", "foo", true),
+            ("{foo,bar{1..3}}", "bar2", true),
+            ("{foo,bar{1..3}}", "bar5", false),
+            // `[abc]`と`[!abc]`は文字の集合
+            ("[abc].txt", "b.txt", true),
+            ("[abc].txt", "d.txt", false),
+            ("[!abc].txt", "d.txt", true),
+            ("[!abc].txt", "a.txt", false),
+            ("[a-c].txt", "c.txt", true),
+            // 特殊文字はエスケープして文字どおりに扱う
+            ("\\*.txt", "*.txt", true),
+            ("\\*.txt", "a.txt", false),
+            ("a+b.txt", "a+b.txt", true),
+        ];
+        for (glob, path, expected) in cases {
+            assert_eq!(matches(glob, path), expected, "[{}] と {}", glob, path);
+        }
+    }
+
+    /// インデントは EditorConfig、設定の tab_size、言語のインデント単位の順に優先されることを確かめます。
+    #[test]
+    fn indent_text_falls_back_to_language_indent_unit() {
+        use crate::app::settings::SettingsFile;
+
+        let mut app = App::default();
+        assert_eq!(app.indent_text(), "    ");
+        app.editor_mut().language = app.languages.by_name("javascript").cloned();
+        assert_eq!(app.indent_text(), "  ");
+
+        let (file, errors) = SettingsFile::parse(
+            Path::new("settings.toml"),
+            "[language.javascript]\ntab_size = 3\n",
+            &app.languages,
+        )
+        .unwrap();
+        assert!(errors.is_empty());
+        app.settings_files = vec![file];
+        assert_eq!(app.indent_text(), "   ");
+
+        app.document_mut().editorconfig.indent_size = Some(8);
+        assert_eq!(app.indent_text(), "        ");
+        app.document_mut().editorconfig.indent_style = Some(IndentStyle::Tab);
+        assert_eq!(app.indent_text(), "\t");
+    }
+}
@@ -0,0 +1,431 @@
+// src/app/explorer.rs
+
+use super::input::InputLine;
+use super::{App, companion_path};
+use crate::{emsg, msg};
+use ignore::WalkBuilder;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// ファイルツリーの1行（ファイルまたはディレクトリ）です。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExplorerEntry {
+    pub path: PathBuf,
+    pub name: String,
+    pub depth: usize, // ルートからの深さ（ルート直下が0）
+    pub is_dir: bool,
+}
+
+/// ファイルツリーで入力中の操作です。
+#[derive(Debug, Clone)]
+pub enum ExplorerPrompt {
+    Create {
+        directory: PathBuf, // 作成先のディレクトリ
+        input: InputLine,   // 作成するファイル名（末尾が`/`ならディレクトリ）
+    },
+    Rename {
+        path: PathBuf,    // 名前を変更するファイル
+        input: InputLine, // 新しい名前
+    },
+    Delete {
+        path: PathBuf, // 削除するファイル（y で確定）
+    },
+}
+
+/// サイドバーのファイルツリーの状態を管理します。
+/// ルートはカレントディレクトリで、`.gitignore`で除外されたファイルと隠しファイルは表示しません。
+#[derive(Debug, Clone)]
+pub struct Explorer {
+    pub visible: bool,                  // サイドバーを表示しているか
+    pub focused: bool,                  // キー入力をファイルツリーで処理するか
+    pub root: PathBuf,                  // ツリーのルートディレクトリ
+    pub entries: Vec<ExplorerEntry>,    // 展開されているディレクトリの中身を含む、表示する行の一覧
+    pub selected: usize,                // 選択している行
+    pub scroll_offset: usize,           // 表示している先頭の行
+    pub prompt: Option<ExplorerPrompt>, // 入力中の操作（ある場合のみSome）
+    expanded: HashSet<PathBuf>,         // 展開しているディレクトリ
+}
+
+impl Default for Explorer {
+    /// カレントディレクトリをルートとするファイルツリーを作成します。
+    fn default() -> Self {
+        let root = fs::canonicalize(".")
+            .or_else(|_| env::current_dir())
+            .unwrap_or_else(|_| PathBuf::from("."));
+        Self::new(root)
+    }
+}
+
+impl Explorer {
+    /// 指定されたディレクトリをルートとするファイルツリーを作成します。中身は表示する時に読み込みます。
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            visible: false,
+            focused: false,
+            root,
+            entries: Vec::new(),
+            selected: 0,
+            scroll_offset: 0,
+            prompt: None,
+            expanded: HashSet::new(),
+        }
+    }
+
+    /// 選択している行を返します。
+    pub fn selected_entry(&self) -> Option<&ExplorerEntry> {
+        self.entries.get(self.selected)
+    }
+
+    /// ディレクトリが展開されているかどうかを返します。
+    pub fn is_expanded(&self, path: &Path) -> bool {
+        self.expanded.contains(path)
+    }
+
+    /// ディレクトリの中身を読み直し、表示する行の一覧を作り直します。
+    /// 選択していたファイルが残っていれば、その行を選択したままにします。
+    pub fn refresh(&mut self) {
+        let selected_path = self.selected_entry().map(|entry| entry.path.clone());
+        self.entries.clear();
+        let root = self.root.clone();
+        self.collect_entries(&root, 0);
+        // 削除されたディレクトリの展開状態は捨てる
+        self.expanded.retain(|path| path.is_dir());
+        if let Some(path) = selected_path {
+            self.select_path(&path);
+        }
+        self.selected = self.selected.min(self.entries.len().saturating_sub(1));
+    }
+
+    /// 指定されたパスの行を選択します。行がなければ何もせず`false`を返します。
+    pub fn select_path(&mut self, path: &Path) -> bool {
+        match self.entries.iter().position(|entry| entry.path == path) {
+            Some(index) => {
+                self.selected = index;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 指定されたファイルが見えるように、その親ディレクトリを全て展開して選択します。
+    pub fn reveal(&mut self, path: &Path) {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return;
+        };
+        let mut directory = self.root.clone();
+        if let Some(parent) = relative.parent() {
+            for component in parent.components() {
+                directory.push(component);
+                self.expanded.insert(directory.clone());
+            }
+        }
+        self.refresh();
+        self.select_path(path);
+    }
+
+    /// 選択を`delta`行だけ移動します。
+    pub fn move_selection(&mut self, delta: isize) {
+        let last = self.entries.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    /// 選択している行が表示されるように、スクロール位置を調整します。
+    pub fn adjust_scroll(&mut self, height: usize) {
+        if self.selected < self.scroll_offset {
+            self.scroll_offset = self.selected;
+        } else if height > 0 && self.selected >= self.scroll_offset + height {
+            self.scroll_offset = self.selected + 1 - height;
+        }
+    }
+
+    /// ディレクトリの中身を（展開されているサブディレクトリも含めて）`entries`に追加します。
+    fn collect_entries(&mut self, directory: &Path, depth: usize) {
+        for (path, is_dir) in list_directory(directory) {
+            let name = path
+                .file_name()
+                .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+            let expanded = is_dir && self.expanded.contains(&path);
+            self.entries.push(ExplorerEntry {
+                path: path.clone(),
+                name,
+                depth,
+                is_dir,
+            });
+            if expanded {
+                self.collect_entries(&path, depth + 1);
+            }
+        }
+    }
+}
+
+impl App {
+    /// ファイルツリーのサイドバーの表示を切り替えます。
+    /// 非表示なら表示してフォーカスを移し、表示中でフォーカスがなければフォーカスを移し、それ以外は閉じます。
+    pub fn toggle_explorer(&mut self) {
+        let explorer = &mut self.explorer;
+        if !explorer.visible {
+            explorer.visible = true;
+            explorer.focused = true;
+            match self.documents[self.active_document].target_path.clone() {
+                Some(path) => {
+                    let path = fs::canonicalize(&path).unwrap_or(path);
+                    self.explorer.reveal(&path);
+                }
+                None => self.explorer.refresh(),
+            }
+        } else if !explorer.focused {
+            explorer.focused = true;
+        } else {
+            explorer.visible = false;
+            explorer.focused = false;
+            explorer.prompt = None;
+        }
+    }
+
+    /// 選択している行を開きます。ディレクトリなら展開・折りたたみを切り替え、
+    /// ファイルならエディタで開いてフォーカスをエディタに移します。
+    pub fn explorer_open_selected(&mut self) {
+        let Some(entry) = self.explorer.selected_entry().cloned() else {
+            return;
+        };
+        if entry.is_dir {
+            if !self.explorer.expanded.remove(&entry.path) {
+                self.explorer.expanded.insert(entry.path);
+            }
+            self.explorer.refresh();
+        } else {
+            self.open_file(&entry.path);
+            self.explorer.focused = false;
+        }
+    }
+
+    /// 選択しているディレクトリを展開します。既に展開されていれば最初の子に移動します。
+    pub fn explorer_expand(&mut self) {
+        let Some(entry) = self.explorer.selected_entry().cloned() else {
+            return;
+        };
+        if !entry.is_dir {
+            return;
+        }
+        if self.explorer.expanded.insert(entry.path) {
+            self.explorer.refresh();
+        } else if self
+            .explorer
+            .entries
+            .get(self.explorer.selected + 1)
+            .is_some_and(|next| next.depth > entry.depth)
+        {
+            self.explorer.selected += 1;
+        }
+    }
+
+    /// 選択しているディレクトリを折りたたみます。
+    /// 展開されていないか、ファイルの場合は親ディレクトリの行に移動します。
+    pub fn explorer_collapse(&mut self) {
+        let Some(entry) = self.explorer.selected_entry().cloned() else {
+            return;
+        };
+        if entry.is_dir && self.explorer.expanded.remove(&entry.path) {
+            self.explorer.refresh();
+        } else if let Some(parent) = entry.path.parent() {
+            self.explorer.select_path(parent);
+        }
+    }
+
+    /// ファイルツリーを読み直します。
+    pub fn explorer_refresh(&mut self) {
+        self.explorer.refresh();
+        msg!(self, "ファイルツリーを更新しました。");
+    }
+
+    /// 新しいファイルの名前の入力を始めます。
+    /// 選択しているのがディレクトリならその中に、ファイルなら同じディレクトリに作成します。
+    pub fn explorer_start_create(&mut self) {
+        let directory = match self.explorer.selected_entry() {
+            Some(entry) if entry.is_dir => entry.path.clone(),
+            Some(entry) => entry
+                .path
+                .parent()
+                .map_or_else(|| self.explorer.root.clone(), Path::to_path_buf),
+            None => self.explorer.root.clone(),
+        };
+        self.explorer.prompt = Some(ExplorerPrompt::Create {
+            directory,
+            input: InputLine::default(),
+        });
+    }
+
+    /// 選択しているファイルの新しい名前の入力を始めます。
+    pub fn explorer_start_rename(&mut self) {
+        let Some(entry) = self.explorer.selected_entry() else {
+            return;
+        };
+        self.explorer.prompt = Some(ExplorerPrompt::Rename {
+            path: entry.path.clone(),
+            input: InputLine::new(&entry.name),
+        });
+    }
+
+    /// 選択しているファイルを削除するかどうかの確認を始めます。
+    pub fn explorer_start_delete(&mut self) {
+        let Some(entry) = self.explorer.selected_entry() else {
+            return;
+        };
+        self.explorer.prompt = Some(ExplorerPrompt::Delete {
+            path: entry.path.clone(),
+        });
+    }
+
+    /// 入力中の操作（作成・名前の変更・削除）を実行します。
+    pub fn confirm_explorer_prompt(&mut self) {
+        let Some(prompt) = self.explorer.prompt.take() else {
+            return;
+        };
+        match prompt {
+            ExplorerPrompt::Create { directory, input } => {
+                let name = input.text.trim();
+                if !name.is_empty() {
+                    self.explorer_create(&directory, name);
+                }
+            }
+            ExplorerPrompt::Rename { path, input } => {
+                let name = input.text.trim();
+                if !name.is_empty() {
+                    self.explorer_rename(&path, name);
+                }
+            }
+            ExplorerPrompt::Delete { path } => self.explorer_delete(&path),
+        }
+    }
+
+    /// ファイルを作成して開きます。名前の末尾が`/`の場合はディレクトリを作成します。
+    fn explorer_create(&mut self, directory: &Path, name: &str) {
+        let path = directory.join(name);
+        let result = if name.ends_with('/') {
+            fs::create_dir_all(&path)
+        } else {
+            path.parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::File::create_new(&path).map(|_| ()))
+        };
+        if let Err(e) = result {
+            emsg!(self, "{:?} を作成できませんでした: {}", path, e);
+            return;
+        }
+        msg!(self, "{:?} を作成しました。", path);
+        let path = fs::canonicalize(&path).unwrap_or(path);
+        self.explorer.reveal(&path);
+        if path.is_file() {
+            self.open_file(&path);
+            self.explorer.focused = false;
+        }
+    }
+
+    /// ファイル（ディレクトリ）の名前を変更します。開いているファイルは新しいパスに付け替えます。
+    fn explorer_rename(&mut self, path: &Path, name: &str) {
+        let new_path = path
+            .parent()
+            .map_or_else(|| PathBuf::from(name), |parent| parent.join(name));
+        if new_path.exists() {
+            emsg!(self, "{:?} は既に存在します。", new_path);
+            return;
+        }
+        let affected = self.documents_under(path);
+        if let Err(e) = fs::rename(path, &new_path) {
+            emsg!(self, "{:?} の名前を変更できませんでした: {}", path, e);
+            return;
+        }
+        msg!(self, "{:?} を {:?} に変更しました。", path, new_path);
+
+        for (index, relative) in affected {
+            let renamed = if relative.as_os_str().is_empty() {
+                new_path.clone()
+            } else {
+                new_path.join(relative)
+            };
+            self.move_document(index, renamed);
+        }
+        self.explorer.refresh();
+        self.explorer.select_path(&new_path);
+    }
+
+    /// 開いているファイルのパスを付け替え、一時ファイルとUndo履歴のファイルも移動します。
+    fn move_document(&mut self, index: usize, new_path: PathBuf) {
+        let temp_path = companion_path(&new_path, "inedit");
+        let history_path = companion_path(&new_path, "inedit-undo");
+        let document = &mut self.documents[index];
+        let moves = [
+            (document.temp_path.replace(temp_path.clone()), temp_path),
+            (
+                document.history_path.replace(history_path.clone()),
+                history_path,
+            ),
+        ];
+        document.target_path = Some(new_path);
+        for (old, new) in moves {
+            if let Some(old) = old
+                && old.exists()
+                && let Err(e) = fs::rename(&old, &new)
+            {
+                emsg!(self, "警告: {:?} を移動できませんでした: {}", old, e);
+            }
+        }
+        self.load_editorconfig(index); // 移動先に適用される .editorconfig を読み直す
+    }
+
+    /// ファイル（ディレクトリの場合は中身ごと）を削除します。
+    /// 開いているファイルは、保存していない変更がある状態として残します。
+    fn explorer_delete(&mut self, path: &Path) {
+        let affected = self.documents_under(path);
+        let result = if path.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        };
+        if let Err(e) = result {
+            emsg!(self, "{:?} を削除できませんでした: {}", path, e);
+            return;
+        }
+        msg!(self, "{:?} を削除しました。", path);
+        for (index, _) in affected {
+            self.documents[index].saved_revision = None;
+            self.documents[index].disk_hash = None;
+        }
+        self.explorer.refresh();
+    }
+
+    /// 指定されたパス（ディレクトリならその中）にある、開いているファイルのインデックスと、
+    /// そのパスからの相対パスを返します。
+    fn documents_under(&self, path: &Path) -> Vec<(usize, PathBuf)> {
+        self.documents
+            .iter()
+            .enumerate()
+            .filter_map(|(index, document)| {
+                let target = fs::canonicalize(document.target_path.as_deref()?).ok()?;
+                let relative = target.strip_prefix(path).ok()?.to_path_buf();
+                Some((index, relative))
+            })
+            .collect()
+    }
+}
+
+/// ディレクトリ直下のファイルとディレクトリを、ディレクトリを先にして名前順に返します。
+/// `.gitignore`などで除外されたファイルと隠しファイルは含めません。
+fn list_directory(directory: &Path) -> Vec<(PathBuf, bool)> {
+    let mut items: Vec<(PathBuf, bool)> = WalkBuilder::new(directory)
+        .max_depth(Some(1))
+        .require_git(false)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.depth() == 1)
+        .map(|entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            (entry.into_path(), is_dir)
+        })
+        .collect();
+    items.sort_by_key(|(_, is_dir)| !is_dir);
+    items
+}
@@ -0,0 +1,282 @@
+// src/app/finder.rs
+
+use super::App;
+use super::fuzzy::fuzzy_match;
+use super::input::InputLine;
+use crate::msg;
+use ignore::WalkBuilder;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+/// バックグラウンドで一度に送るファイル数
+const INDEX_BATCH_SIZE: usize = 256;
+/// バイナリファイルかどうかを判定するために読む先頭のバイト数
+const BINARY_CHECK_BYTES: usize = 1024;
+/// プレビューに読み込む先頭のバイト数と行数
+const PREVIEW_BYTES: u64 = 64 * 1024;
+const PREVIEW_LINES: usize = 200;
+
+/// 作業ディレクトリ以下のファイルの一覧です。
+/// 大きなリポジトリでも入力が止まらないよう、一覧はバックグラウンドのスレッドで作成し、少しずつ受け取ります。
+pub struct FileIndex {
+    pub root: PathBuf,
+    pub files: Vec<String>,                  // ルートからの相対パス
+    receiver: Option<Receiver<Vec<String>>>, // 一覧の作成中のみSome
+}
+
+impl FileIndex {
+    /// ルート以下のファイルの一覧の作成を、バックグラウンドで始めます。
+    /// `.gitignore`などで除外されたファイル、隠しファイル、バイナリファイルは含めません。
+    pub fn build(root: PathBuf) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let walk_root = root.clone();
+        thread::spawn(move || {
+            let mut batch = Vec::with_capacity(INDEX_BATCH_SIZE);
+            let walker = WalkBuilder::new(&walk_root).require_git(false).build();
+            for entry in walker.filter_map(Result::ok) {
+                if !entry.file_type().is_some_and(|t| t.is_file()) || is_binary(entry.path()) {
+                    continue;
+                }
+                let Ok(relative) = entry.path().strip_prefix(&walk_root) else {
+                    continue;
+                };
+                batch.push(relative.to_string_lossy().into_owned());
+                if batch.len() >= INDEX_BATCH_SIZE {
+                    // 受け取る側（ファインダー）が閉じられたら打ち切る
+                    if sender.send(std::mem::take(&mut batch)).is_err() {
+                        return;
+                    }
+                }
+            }
+            let _ = sender.send(batch);
+        });
+        Self {
+            root,
+            files: Vec::new(),
+            receiver: Some(receiver),
+        }
+    }
+
+    /// バックグラウンドで見つかったファイルを一覧に取り込みます。増えた場合は`true`を返します。
+    pub fn poll(&mut self) -> bool {
+        let Some(receiver) = &self.receiver else {
+            return false;
+        };
+        let count = self.files.len();
+        loop {
+            match receiver.try_recv() {
+                Ok(batch) => self.files.extend(batch),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.receiver = None;
+                    break;
+                }
+            }
+        }
+        self.files.len() > count
+    }
+
+    /// 一覧の作成が終わったかどうかを返します。
+    pub fn is_complete(&self) -> bool {
+        self.receiver.is_none()
+    }
+}
+
+/// ファインダーの候補1件です。
+#[derive(Debug, Clone)]
+pub struct FinderMatch {
+    pub file: usize,           // FileIndex::files のインデックス
+    pub score: i64,            // あいまい検索の得点
+    pub positions: Vec<usize>, // クエリにマッチした文字の位置（強調表示に使用）
+}
+
+/// 選択しているファイルの先頭部分です。
+#[derive(Debug, Clone)]
+pub struct FilePreview {
+    pub file: usize,        // FileIndex::files のインデックス
+    pub lines: Vec<String>, // 先頭の行（読めなかった場合は空）
+}
+
+/// ファイルをあいまい検索して開くポップアップ（ファインダー）の状態です。
+pub struct Finder {
+    pub input: InputLine,             // 検索クエリの入力欄
+    pub index: FileIndex,             // 検索対象のファイルの一覧
+    pub matches: Vec<FinderMatch>,    // クエリにマッチしたファイル（得点順）
+    pub selected: usize,              // 選択している候補
+    pub scroll_offset: usize,         // 表示している先頭の候補
+    pub preview: Option<FilePreview>, // 選択している候補のプレビュー
+    matched_query: String,            // matchesを計算したクエリ
+    matched_count: usize,             // matchesの計算に含めたファイルの数
+}
+
+impl Finder {
+    /// 作業ディレクトリ以下のファイルを対象とするファインダーを作成します。
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            input: InputLine::default(),
+            index: FileIndex::build(root),
+            matches: Vec::new(),
+            selected: 0,
+            scroll_offset: 0,
+            preview: None,
+            matched_query: String::new(),
+            matched_count: 0,
+        }
+    }
+
+    /// 選択している候補の相対パスを返します。
+    pub fn selected_file(&self) -> Option<&str> {
+        self.matches
+            .get(self.selected)
+            .map(|m| self.index.files[m.file].as_str())
+    }
+
+    /// クエリの変更や、新しく見つかったファイルを候補に反映します。
+    /// クエリが前回のクエリを延長したものなら、前回の候補だけを検索し直します。
+    pub fn update_matches(&mut self) {
+        let query = self.input.text.clone();
+        if query != self.matched_query {
+            let narrowing =
+                !self.matched_query.is_empty() && query.starts_with(&self.matched_query);
+            let candidates: Vec<usize> = if narrowing {
+                self.matches.iter().map(|m| m.file).collect()
+            } else {
+                (0..self.matched_count).collect()
+            };
+            self.matches = self.score(&query, candidates);
+            self.matched_query = query;
+            self.selected = 0;
+            self.scroll_offset = 0;
+        } else if self.matched_count == self.index.files.len() {
+            return;
+        }
+        let new_files = (self.matched_count..self.index.files.len()).collect();
+        let new_matches = self.score(&self.matched_query, new_files);
+        self.matches.extend(new_matches);
+        self.matched_count = self.index.files.len();
+
+        // 得点の高い順、同点なら短いパス、名前の順に並べる
+        let files = &self.index.files;
+        self.matches.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| files[a.file].len().cmp(&files[b.file].len()))
+                .then_with(|| files[a.file].cmp(&files[b.file]))
+        });
+        self.selected = self.selected.min(self.matches.len().saturating_sub(1));
+    }
+
+    /// 指定されたファイルをクエリで検索し、マッチしたものを返します。
+    fn score(&self, query: &str, candidates: Vec<usize>) -> Vec<FinderMatch> {
+        candidates
+            .into_iter()
+            .filter_map(|file| {
+                fuzzy_match(query, &self.index.files[file]).map(|m| FinderMatch {
+                    file,
+                    score: m.score,
+                    positions: m.positions,
+                })
+            })
+            .collect()
+    }
+
+    /// 選択を`delta`件だけ移動します。
+    pub fn move_selection(&mut self, delta: isize) {
+        let last = self.matches.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    /// 選択している候補が表示されるように、スクロール位置を調整します。
+    pub fn adjust_scroll(&mut self, height: usize) {
+        if self.selected < self.scroll_offset {
+            self.scroll_offset = self.selected;
+        } else if height > 0 && self.selected >= self.scroll_offset + height {
+            self.scroll_offset = self.selected + 1 - height;
+        }
+    }
+
+    /// 選択している候補のプレビューを読み込みます。（既に読み込んでいれば何もしません）
+    pub fn update_preview(&mut self) {
+        let Some(file) = self.matches.get(self.selected).map(|m| m.file) else {
+            self.preview = None;
+            return;
+        };
+        if self.preview.as_ref().is_some_and(|p| p.file == file) {
+            return;
+        }
+        let path = self.index.root.join(&self.index.files[file]);
+        self.preview = Some(FilePreview {
+            file,
+            lines: read_preview(&path),
+        });
+    }
+}
+
+impl App {
+    /// ファインダーを開き、作業ディレクトリ以下のファイルの一覧の作成を始めます。
+    pub fn open_finder(&mut self) {
+        let root = self.explorer.root.clone();
+        self.finder = Some(Finder::new(root));
+        self.update_finder();
+    }
+
+    /// バックグラウンドで見つかったファイルと入力中のクエリを候補に反映します。（定期的に呼び出します）
+    pub fn update_finder(&mut self) {
+        let Some(finder) = &mut self.finder else {
+            return;
+        };
+        finder.index.poll();
+        finder.update_matches();
+        finder.update_preview();
+    }
+
+    /// ファインダーの選択を移動します。
+    pub fn finder_move_selection(&mut self, delta: isize) {
+        if let Some(finder) = &mut self.finder {
+            finder.move_selection(delta);
+            finder.update_preview();
+        }
+    }
+
+    /// 選択している候補のファイルを開き、ファインダーを閉じます。
+    pub fn confirm_finder(&mut self) {
+        let Some(finder) = self.finder.take() else {
+            return;
+        };
+        match finder.selected_file() {
+            Some(file) => {
+                let path = finder.index.root.join(file);
+                self.open_file(&path);
+            }
+            None => msg!(self, "一致するファイルがありません。"),
+        }
+    }
+}
+
+/// ファイルの先頭部分にNULバイトが含まれていれば、バイナリファイルとみなします。
+fn is_binary(path: &Path) -> bool {
+    let mut buffer = [0; BINARY_CHECK_BYTES];
+    match File::open(path).and_then(|mut file| file.read(&mut buffer)) {
+        Ok(read) => buffer[..read].contains(&0),
+        Err(_) => true, // 読めないファイルは候補にしない
+    }
+}
+
+/// プレビュー用に、ファイルの先頭の行を読み込みます。
+fn read_preview(path: &Path) -> Vec<String> {
+    let mut content = Vec::new();
+    if File::open(path)
+        .and_then(|file| file.take(PREVIEW_BYTES).read_to_end(&mut content))
+        .is_err()
+    {
+        return Vec::new();
+    }
+    String::from_utf8_lossy(&content)
+        .lines()
+        .take(PREVIEW_LINES)
+        .map(|line| line.replace('\t', "    "))
+        .collect()
+}
@@ -0,0 +1,176 @@
+// src/app/fuzzy.rs
+
+/// あいまい検索でクエリにマッチした結果です。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,            // 大きいほどよく一致している
+    pub positions: Vec<usize>, // クエリの各文字がマッチした位置（文字単位）
+}
+
+const SCORE_MATCH: i64 = 16; // 1文字マッチするごとの得点
+const BONUS_CONSECUTIVE: i64 = 12; // 直前の文字に続けてマッチした場合の加点
+const BONUS_SEGMENT_START: i64 = 12; // パスの区切り（`/`）の直後にマッチした場合の加点
+const BONUS_WORD_START: i64 = 8; // 単語の先頭（`_`や`-`の後、大文字）にマッチした場合の加点
+const PENALTY_GAP_START: i64 = 3; // マッチの間に文字を飛ばした場合の減点
+const PENALTY_GAP_EXTEND: i64 = 1; // 飛ばした文字1つごとの減点
+const PENALTY_LEADING: i64 = 1; // 最初のマッチより前の文字1つごとの減点（上限あり）
+const MAX_LEADING_PENALTY: i64 = 10;
+
+/// `query`の文字が`candidate`にこの順で含まれていれば（部分列として一致すれば）、その得点を返します。
+/// クエリに大文字が含まれない場合は大文字と小文字を区別しません（スマートケース）。
+/// 連続したマッチや、パスの区切り・単語の先頭でのマッチほど高い得点になります。
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let ignore_case = !query.chars().any(char::is_uppercase);
+    let normalize = |c: char| {
+        if ignore_case {
+            c.to_lowercase().next().unwrap_or(c)
+        } else {
+            c
+        }
+    };
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(normalize)
+        .collect();
+    if query.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+    let original: Vec<char> = candidate.chars().collect();
+    let text: Vec<char> = original.iter().copied().map(normalize).collect();
+
+    // 部分列として一致しない候補は、得点を計算する前に除外する
+    let mut remaining = query.iter().peekable();
+    for c in &text {
+        if remaining.peek() == Some(&c) {
+            remaining.next();
+        }
+    }
+    if remaining.peek().is_some() {
+        return None;
+    }
+
+    let bonus = |j: usize| -> i64 {
+        match j.checked_sub(1).map(|k| original[k]) {
+            None | Some('/') | Some('\\') => BONUS_SEGMENT_START,
+            Some('_' | '-' | '.' | ' ') => BONUS_WORD_START,
+            Some(previous) if previous.is_lowercase() && original[j].is_uppercase() => {
+                BONUS_WORD_START
+            }
+            _ => 0,
+        }
+    };
+
+    // scores[i][j]: クエリのi文字目を候補のj文字目にマッチさせた場合の最高得点
+    // from[i][j]: その時にクエリのi-1文字目がマッチした位置
+    const NONE: i64 = i64::MIN / 2;
+    let (n, m) = (query.len(), text.len());
+    let mut scores = vec![vec![NONE; m]; n];
+    let mut from = vec![vec![0usize; m]; n];
+    for j in 0..m {
+        if text[j] == query[0] {
+            let leading = (j as i64 * PENALTY_LEADING).min(MAX_LEADING_PENALTY);
+            scores[0][j] = SCORE_MATCH + bonus(j) - leading;
+        }
+    }
+    for i in 1..n {
+        // 文字を飛ばしてマッチする場合の、直前の行の最高得点（減点込み）とその位置
+        let mut gap_best = NONE;
+        let mut gap_from = 0;
+        for j in i..m {
+            if j >= 2 {
+                gap_best -= PENALTY_GAP_EXTEND;
+                let candidate = scores[i - 1][j - 2] - PENALTY_GAP_START;
+                if candidate > gap_best {
+                    gap_best = candidate;
+                    gap_from = j - 2;
+                }
+            }
+            if text[j] != query[i] {
+                continue;
+            }
+            let consecutive = scores[i - 1][j - 1] + BONUS_CONSECUTIVE;
+            let (previous, k) = if consecutive >= gap_best {
+                (consecutive, j - 1)
+            } else {
+                (gap_best, gap_from)
+            };
+            // マッチできない位置（NONE）から続く場合は除く
+            if previous > NONE / 2 {
+                scores[i][j] = previous + SCORE_MATCH + bonus(j);
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let (mut j, score) = scores[n - 1]
+        .iter()
+        .copied()
+        .enumerate()
+        .max_by_key(|&(j, score)| (score, std::cmp::Reverse(j)))?;
+    if score <= NONE / 2 {
+        return None;
+    }
+    let mut positions = vec![0; n];
+    for i in (0..n).rev() {
+        positions[i] = j;
+        j = from[i][j];
+    }
+    Some(FuzzyMatch { score, positions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(query: &str, candidate: &str) -> i64 {
+        fuzzy_match(query, candidate).unwrap().score
+    }
+
+    #[test]
+    fn matches_only_subsequences() {
+        assert_eq!(fuzzy_match("abc", "acb"), None);
+        assert_eq!(
+            fuzzy_match("abc", "a_b_c").unwrap().positions,
+            vec![0, 2, 4]
+        );
+        // 空のクエリ（空白のみを含む）は全ての候補に一致する
+        assert_eq!(
+            fuzzy_match(" ", "anything"),
+            Some(FuzzyMatch {
+                score: 0,
+                positions: Vec::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn ranks_prefix_and_segment_start_higher() {
+        // 先頭やパスの区切りの直後での一致は、単語の途中での一致より上位になる
+        assert!(score("main", "main.rs") > score("main", "domain.rs"));
+        assert!(score("app", "src/app.rs") > score("app", "src/wrapper.rs"));
+        // 連続した一致は、飛び飛びの一致より上位になる
+        assert!(score("abc", "abc_x") > score("abc", "a_b_c"));
+    }
+
+    #[test]
+    fn ranks_word_boundaries_higher() {
+        // `_`の後や大文字（キャメルケース）の単語の先頭での一致は、近くても単語の途中での一致より上位になる
+        assert!(score("fb", "foo_bar.rs") > score("fb", "fabric.rs"));
+        assert!(score("fb", "fooBar.rs") > score("fb", "fabric.rs"));
+        assert_eq!(fuzzy_match("fb", "foo_bar").unwrap().positions, vec![0, 4]);
+    }
+
+    #[test]
+    fn uses_smart_case() {
+        // 小文字だけのクエリは大文字と小文字を区別しない
+        assert!(fuzzy_match("readme", "README.md").is_some());
+        // 大文字を含むクエリは区別する
+        assert!(fuzzy_match("Readme", "readme.md").is_none());
+        assert!(fuzzy_match("README", "README.md").is_some());
+        assert!(fuzzy_match("RM", "src/rm.rs").is_none());
+    }
+}
@@ -0,0 +1,884 @@
+// src/app/git.rs
+
+use flate2::read::ZlibDecoder;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// gitのオブジェクトID（SHA-1）です。
+type ObjectId = [u8; 20];
+
+/// deltaの元のオブジェクトをたどる回数の上限です。（壊れたpackで参照が循環していても止まるように）
+const MAX_DELTA_DEPTH: usize = 50;
+
+/// gitオブジェクトの種類です。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ObjectKind {
+    Commit,
+    Tree,
+    Blob,
+    Tag,
+}
+
+/// ローカルの`.git`ディレクトリを直接読み取り、HEADやインデックスにあるファイルの内容を取り出します。
+/// gitコマンドには依存せず、loose object と packfile（delta圧縮を含む）の読み取りに対応しています。
+#[derive(Debug, Clone)]
+pub struct GitRepo {
+    work_dir: PathBuf,   // 作業ツリーのルート
+    git_dir: PathBuf, // HEADとインデックスがあるディレクトリ（worktreeの場合はその専用ディレクトリ）
+    common_dir: PathBuf, // オブジェクトと参照があるディレクトリ（通常はgit_dirと同じ）
+}
+
+impl GitRepo {
+    /// 指定されたファイルを含むリポジトリを、親ディレクトリをさかのぼって探します。
+    pub fn discover(path: &Path) -> Option<Self> {
+        let path = fs::canonicalize(path)
+            .or_else(|_| {
+                // まだ存在しないファイルの場合は、親ディレクトリから探す
+                path.parent()
+                    .map_or_else(|| fs::canonicalize("."), fs::canonicalize)
+            })
+            .ok()?;
+        for dir in path.ancestors() {
+            let dot_git = dir.join(".git");
+            let git_dir = if dot_git.is_dir() {
+                dot_git
+            } else if dot_git.is_file() {
+                // worktreeやサブモジュールでは、`.git`は実際のディレクトリを指すファイルになっている
+                let content = fs::read_to_string(&dot_git).ok()?;
+                let target = content.strip_prefix("gitdir:")?.trim();
+                dir.join(target)
+            } else {
+                continue;
+            };
+            let common_dir = match fs::read_to_string(git_dir.join("commondir")) {
+                Ok(common) => git_dir.join(common.trim()),
+                Err(_) => git_dir.clone(),
+            };
+            return Some(Self {
+                work_dir: dir.to_path_buf(),
+                git_dir,
+                common_dir,
+            });
+        }
+        None
+    }
+
+    /// 作業ツリーのルートからの相対パスを、gitの形式（`/`区切り）で返します。
+    pub fn relative_path(&self, path: &Path) -> Option<String> {
+        let path = match fs::canonicalize(path) {
+            Ok(path) => path,
+            Err(_) => fs::canonicalize(path.parent()?)
+                .ok()?
+                .join(path.file_name()?),
+        };
+        let relative = path.strip_prefix(&self.work_dir).ok()?;
+        let components: Option<Vec<&str>> = relative.iter().map(|c| c.to_str()).collect();
+        Some(components?.join("/"))
+    }
+
+    /// HEADのコミットにおけるファイルの内容を返します。HEADにファイルがなければ`None`を返します。
+    pub fn read_head_file(&self, relative_path: &str) -> io::Result<Option<Vec<u8>>> {
+        let Some(commit_id) = self.resolve_head()? else {
+            return Ok(None); // まだコミットがない
+        };
+        let (kind, commit) = self.read_object(&commit_id)?;
+        if kind != ObjectKind::Commit {
+            return Err(invalid_data("HEADがコミットを指していません"));
+        }
+        let mut tree_id = commit
+            .split(|&b| b == b'\n')
+            .find_map(|line| line.strip_prefix(b"tree "))
+            .and_then(parse_hex_id)
+            .ok_or_else(|| invalid_data("コミットにツリーがありません"))?;
+
+        // パスの各要素をたどってツリーを下りていく
+        let mut components = relative_path.split('/').peekable();
+        while let Some(name) = components.next() {
+            let (kind, tree) = self.read_object(&tree_id)?;
+            if kind != ObjectKind::Tree {
+                return Ok(None);
+            }
+            let Some(entry_id) = find_tree_entry(&tree, name.as_bytes()) else {
+                return Ok(None);
+            };
+            if components.peek().is_none() {
+                let (kind, blob) = self.read_object(&entry_id)?;
+                return Ok((kind == ObjectKind::Blob).then_some(blob));
+            }
+            tree_id = entry_id;
+        }
+        Ok(None)
+    }
+
+    /// インデックス（ステージングエリア）にあるファイルの内容を返します。
+    /// インデックスにファイルがなければ`None`を返します。
+    pub fn read_index_file(&self, relative_path: &str) -> io::Result<Option<Vec<u8>>> {
+        let index = match fs::read(self.git_dir.join("index")) {
+            Ok(index) => index,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let Some(blob_id) = find_index_entry(&index, relative_path.as_bytes())? else {
+            return Ok(None);
+        };
+        let (kind, blob) = self.read_object(&blob_id)?;
+        Ok((kind == ObjectKind::Blob).then_some(blob))
+    }
+
+    /// HEADが指すコミットのIDを返します。まだコミットがない場合は`None`を返します。
+    fn resolve_head(&self) -> io::Result<Option<ObjectId>> {
+        let head = fs::read_to_string(self.git_dir.join("HEAD"))?;
+        match head.trim().strip_prefix("ref:") {
+            Some(ref_name) => self.resolve_ref(ref_name.trim()),
+            None => parse_hex_id(head.trim().as_bytes())
+                .map(Some)
+                .ok_or_else(|| invalid_data("HEADの形式が正しくありません")),
+        }
+    }
+
+    /// 参照名（`refs/heads/main`など）をオブジェクトIDに解決します。
+    fn resolve_ref(&self, ref_name: &str) -> io::Result<Option<ObjectId>> {
+        // worktree固有の参照を優先し、なければ共通の参照を探す
+        for dir in [&self.git_dir, &self.common_dir] {
+            if let Ok(content) = fs::read_to_string(dir.join(ref_name)) {
+                let content = content.trim();
+                if let Some(target) = content.strip_prefix("ref:") {
+                    return self.resolve_ref(target.trim());
+                }
+                return parse_hex_id(content.as_bytes())
+                    .map(Some)
+                    .ok_or_else(|| invalid_data("参照の形式が正しくありません"));
+            }
+        }
+        // `git gc`などでまとめられた参照を探す
+        let packed = match fs::read_to_string(self.common_dir.join("packed-refs")) {
+            Ok(packed) => packed,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        Ok(packed
+            .lines()
+            .filter(|line| !line.starts_with('#') && !line.starts_with('^'))
+            .filter_map(|line| line.split_once(' '))
+            .find(|(_, name)| *name == ref_name)
+            .and_then(|(id, _)| parse_hex_id(id.as_bytes())))
+    }
+
+    /// オブジェクトを読み込み、種類と内容を返します。
+    fn read_object(&self, id: &ObjectId) -> io::Result<(ObjectKind, Vec<u8>)> {
+        self.read_object_at_depth(id, 0)
+    }
+
+    /// オブジェクトを読み込みます。`depth`はここまでにたどったdeltaの数です。
+    fn read_object_at_depth(
+        &self,
+        id: &ObjectId,
+        depth: usize,
+    ) -> io::Result<(ObjectKind, Vec<u8>)> {
+        if let Some(object) = self.read_loose_object(id)? {
+            return Ok(object);
+        }
+        let pack_dir = self.common_dir.join("objects").join("pack");
+        for entry in fs::read_dir(&pack_dir)? {
+            let idx_path = entry?.path();
+            if idx_path.extension().is_none_or(|ext| ext != "idx") {
+                continue;
+            }
+            if let Some(offset) = find_pack_offset(&idx_path, id)? {
+                let mut pack = BufReader::new(File::open(idx_path.with_extension("pack"))?);
+                return self.read_pack_object(&mut pack, offset, depth);
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("オブジェクト {} が見つかりません", to_hex(id)),
+        ))
+    }
+
+    /// loose object（`objects/xx/xxxx...`）を読み込みます。存在しない場合は`None`を返します。
+    fn read_loose_object(&self, id: &ObjectId) -> io::Result<Option<(ObjectKind, Vec<u8>)>> {
+        let hex = to_hex(id);
+        let path = self
+            .common_dir
+            .join("objects")
+            .join(&hex[..2])
+            .join(&hex[2..]);
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let mut data = Vec::new();
+        ZlibDecoder::new(BufReader::new(file)).read_to_end(&mut data)?;
+
+        // "<種類> <サイズ>\0<内容>" の形式
+        let header_end = data
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| invalid_data("オブジェクトのヘッダーが正しくありません"))?;
+        let kind = match data[..header_end].split(|&b| b == b' ').next() {
+            Some(b"commit") => ObjectKind::Commit,
+            Some(b"tree") => ObjectKind::Tree,
+            Some(b"blob") => ObjectKind::Blob,
+            Some(b"tag") => ObjectKind::Tag,
+            _ => return Err(invalid_data("不明なオブジェクトの種類です")),
+        };
+        data.drain(..=header_end);
+        Ok(Some((kind, data)))
+    }
+
+    /// packfile の指定された位置にあるオブジェクトを読み込みます。delta は元のオブジェクトに適用して復元します。
+    /// `depth`はここまでにたどったdeltaの数で、`MAX_DELTA_DEPTH`を超える連鎖はエラーにします。
+    fn read_pack_object(
+        &self,
+        pack: &mut BufReader<File>,
+        offset: u64,
+        depth: usize,
+    ) -> io::Result<(ObjectKind, Vec<u8>)> {
+        pack.seek(SeekFrom::Start(offset))?;
+
+        // 種類とサイズ: 先頭バイトの4〜6ビット目が種類、残りが可変長のサイズ
+        let mut byte = read_u8(pack)?;
+        let type_code = (byte >> 4) & 0x07;
+        let mut size = (byte & 0x0f) as usize;
+        let mut shift = 4;
+        while byte & 0x80 != 0 {
+            byte = read_u8(pack)?;
+            size |= ((byte & 0x7f) as usize)
+                .checked_shl(shift)
+                .ok_or_else(|| invalid_data("オブジェクトのサイズが大きすぎます"))?;
+            shift += 7;
+        }
+        if (type_code == 6 || type_code == 7) && depth >= MAX_DELTA_DEPTH {
+            return Err(invalid_data("deltaの連鎖が深すぎます"));
+        }
+
+        let kind = match type_code {
+            1 => ObjectKind::Commit,
+            2 => ObjectKind::Tree,
+            3 => ObjectKind::Blob,
+            4 => ObjectKind::Tag,
+            6 => {
+                // OFS_DELTA: 元のオブジェクトはこのオブジェクトより前の相対位置にある
+                let invalid_base = || invalid_data("deltaの参照先が正しくありません");
+                let mut byte = read_u8(pack)?;
+                let mut distance = (byte & 0x7f) as u64;
+                while byte & 0x80 != 0 {
+                    byte = read_u8(pack)?;
+                    // checked_shl は上位ビットのあふれを検出しないため、128倍で確かめる
+                    distance = distance
+                        .checked_add(1)
+                        .and_then(|d| d.checked_mul(1 << 7))
+                        .ok_or_else(invalid_base)?
+                        | (byte & 0x7f) as u64;
+                }
+                if distance == 0 {
+                    return Err(invalid_base()); // 自分自身は参照できない
+                }
+                let delta = inflate(pack, size)?;
+                let base_offset = offset.checked_sub(distance).ok_or_else(invalid_base)?;
+                let (kind, base) = self.read_pack_object(pack, base_offset, depth + 1)?;
+                return Ok((kind, apply_delta(&base, &delta)?));
+            }
+            7 => {
+                // REF_DELTA: 元のオブジェクトはIDで指定される
+                let mut base_id = [0u8; 20];
+                pack.read_exact(&mut base_id)?;
+                let delta = inflate(pack, size)?;
+                let (kind, base) = self.read_object_at_depth(&base_id, depth + 1)?;
+                return Ok((kind, apply_delta(&base, &delta)?));
+            }
+            _ => return Err(invalid_data("不明なオブジェクトの種類です")),
+        };
+        Ok((kind, inflate(pack, size)?))
+    }
+}
+
+/// 現在位置からzlibで圧縮されたデータを展開します。
+fn inflate(reader: &mut impl Read, size: usize) -> io::Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(size);
+    ZlibDecoder::new(reader).read_to_end(&mut data)?;
+    if data.len() != size {
+        return Err(invalid_data("展開後のサイズが一致しません"));
+    }
+    Ok(data)
+}
+
+/// packのインデックスファイル（version 2）から、オブジェクトのpack内での位置を探します。
+fn find_pack_offset(idx_path: &Path, id: &ObjectId) -> io::Result<Option<u64>> {
+    let idx = fs::read(idx_path)?;
+    let truncated = || invalid_data("packインデックスが途中で終わっています");
+    if idx.len() < 8 + 256 * 4 || idx[..4] != [0xff, b't', b'O', b'c'] || read_be_u32(&idx, 4) != 2
+    {
+        return Err(invalid_data("対応していない形式のpackインデックスです"));
+    }
+    // fanout表: 先頭バイトがi以下のオブジェクトの数
+    let fanout = |i: usize| read_be_u32(&idx, 8 + i * 4) as usize;
+    let count = fanout(255);
+    let ids_start = 8 + 256 * 4;
+    // IDの表、CRCの表の後ろに4バイトの位置の表、さらに大きなpack用の8バイトの位置の表が続く
+    let offsets_start = ids_start + count * 24;
+    if idx.len() < offsets_start + count * 4 {
+        return Err(truncated());
+    }
+
+    // 先頭バイトが同じIDの範囲を二分探索する
+    let mut lower = if id[0] == 0 {
+        0
+    } else {
+        fanout(id[0] as usize - 1)
+    };
+    let mut upper = fanout(id[0] as usize).min(count);
+    let position = loop {
+        if lower >= upper {
+            return Ok(None);
+        }
+        let middle = (lower + upper) / 2;
+        let start = ids_start + middle * 20;
+        match idx[start..start + 20].cmp(id) {
+            std::cmp::Ordering::Less => lower = middle + 1,
+            std::cmp::Ordering::Greater => upper = middle,
+            std::cmp::Ordering::Equal => break middle,
+        }
+    };
+
+    let offset = read_be_u32(&idx, offsets_start + position * 4);
+    if offset & 0x8000_0000 == 0 {
+        return Ok(Some(offset as u64));
+    }
+    let large_start = offsets_start + count * 4 + (offset & 0x7fff_ffff) as usize * 8;
+    if idx.len() < large_start + 8 {
+        return Err(truncated());
+    }
+    let high = read_be_u32(&idx, large_start) as u64;
+    let low = read_be_u32(&idx, large_start + 4) as u64;
+    Ok(Some((high << 32) | low))
+}
+
+/// deltaを元のオブジェクトに適用し、復元したオブジェクトの内容を返します。
+fn apply_delta(base: &[u8], delta: &[u8]) -> io::Result<Vec<u8>> {
+    let mut pos = 0;
+    let base_size = read_delta_size(delta, &mut pos)?;
+    let result_size = read_delta_size(delta, &mut pos)?;
+    if base_size != base.len() {
+        return Err(invalid_data(
+            "deltaの元のオブジェクトのサイズが一致しません",
+        ));
+    }
+
+    let mut result = Vec::with_capacity(result_size);
+    while pos < delta.len() {
+        let command = read_delta_byte(delta, &mut pos)?;
+        if command & 0x80 != 0 {
+            // 元のオブジェクトからのコピー: 下位4ビットが位置、次の3ビットがサイズの各バイトの有無
+            let mut copy_offset = 0usize;
+            for i in 0..4 {
+                if command & (1 << i) != 0 {
+                    copy_offset |= (read_delta_byte(delta, &mut pos)? as usize) << (8 * i);
+                }
+            }
+            let mut copy_size = 0usize;
+            for i in 0..3 {
+                if command & (0x10 << i) != 0 {
+                    copy_size |= (read_delta_byte(delta, &mut pos)? as usize) << (8 * i);
+                }
+            }
+            if copy_size == 0 {
+                copy_size = 0x10000;
+            }
+            let source = base
+                .get(copy_offset..copy_offset + copy_size)
+                .ok_or_else(|| invalid_data("deltaのコピー範囲が正しくありません"))?;
+            result.extend_from_slice(source);
+        } else if command != 0 {
+            // deltaに含まれるデータの挿入
+            let data = delta
+                .get(pos..pos + command as usize)
+                .ok_or_else(|| invalid_data("deltaが途中で終わっています"))?;
+            result.extend_from_slice(data);
+            pos += command as usize;
+        } else {
+            return Err(invalid_data("deltaに不正な命令が含まれています"));
+        }
+    }
+    if result.len() != result_size {
+        return Err(invalid_data("deltaを適用した結果のサイズが一致しません"));
+    }
+    Ok(result)
+}
+
+/// deltaの先頭にある可変長のサイズ（下位から7ビットずつ）を読み取ります。
+fn read_delta_size(delta: &[u8], pos: &mut usize) -> io::Result<usize> {
+    let mut size = 0;
+    let mut shift = 0;
+    loop {
+        let byte = read_delta_byte(delta, pos)?;
+        size |= ((byte & 0x7f) as usize) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Ok(size);
+        }
+    }
+}
+
+fn read_delta_byte(delta: &[u8], pos: &mut usize) -> io::Result<u8> {
+    let byte = *delta
+        .get(*pos)
+        .ok_or_else(|| invalid_data("deltaが途中で終わっています"))?;
+    *pos += 1;
+    Ok(byte)
+}
+
+/// ツリーオブジェクトから指定された名前のエントリを探し、そのIDを返します。
+/// エントリは "<モード> <名前>\0<20バイトのID>" の繰り返しです。
+fn find_tree_entry(tree: &[u8], name: &[u8]) -> Option<ObjectId> {
+    let mut rest = tree;
+    while !rest.is_empty() {
+        let space = rest.iter().position(|&b| b == b' ')?;
+        let nul = space + rest[space..].iter().position(|&b| b == 0)?;
+        let entry_name = &rest[space + 1..nul];
+        let id: ObjectId = rest.get(nul + 1..nul + 21)?.try_into().ok()?;
+        if entry_name == name {
+            return Some(id);
+        }
+        rest = &rest[nul + 21..];
+    }
+    None
+}
+
+/// インデックスファイル（version 2〜4）から、指定されたパスのエントリ（ステージ0）のIDを探します。
+fn find_index_entry(index: &[u8], path: &[u8]) -> io::Result<Option<ObjectId>> {
+    if index.len() < 12 || &index[..4] != b"DIRC" {
+        return Err(invalid_data("インデックスの形式が正しくありません"));
+    }
+    let version = read_be_u32(index, 4);
+    if !(2..=4).contains(&version) {
+        return Err(invalid_data("対応していないバージョンのインデックスです"));
+    }
+    let count = read_be_u32(index, 8) as usize;
+    let truncated = || invalid_data("インデックスが途中で終わっています");
+
+    let mut pos = 12;
+    let mut previous_name: Vec<u8> = Vec::new();
+    for _ in 0..count {
+        // 統計情報（40バイト）、ID（20バイト）、フラグ（2バイト）
+        let entry_start = pos;
+        let id: ObjectId = index
+            .get(pos + 40..pos + 60)
+            .ok_or_else(truncated)?
+            .try_into()
+            .map_err(|_| truncated())?;
+        let flags = u16::from_be_bytes(
+            index
+                .get(pos + 60..pos + 62)
+                .ok_or_else(truncated)?
+                .try_into()
+                .map_err(|_| truncated())?,
+        );
+        pos += 62;
+        if version >= 3 && flags & 0x4000 != 0 {
+            pos += 2; // 拡張フラグ
+        }
+        let stage = (flags >> 12) & 0x3;
+
+        let name = if version == 4 {
+            // 直前のエントリの名前の末尾を何バイト削るか（可変長整数）に続けて、残りの名前がある
+            let mut byte = *index.get(pos).ok_or_else(truncated)?;
+            pos += 1;
+            let mut strip = (byte & 0x7f) as usize;
+            while byte & 0x80 != 0 {
+                byte = *index.get(pos).ok_or_else(truncated)?;
+                pos += 1;
+                strip = ((strip + 1) << 7) | (byte & 0x7f) as usize;
+            }
+            let nul = pos
+                + index[pos..]
+                    .iter()
+                    .position(|&b| b == 0)
+                    .ok_or_else(truncated)?;
+            let keep = previous_name
+                .len()
+                .checked_sub(strip)
+                .ok_or_else(truncated)?;
+            let mut name = previous_name[..keep].to_vec();
+            name.extend_from_slice(&index[pos..nul]);
+            pos = nul + 1;
+            name
+        } else {
+            // 名前の後ろは、エントリ全体が8バイトの倍数になるようNULで埋められている
+            let nul = pos
+                + index[pos..]
+                    .iter()
+                    .position(|&b| b == 0)
+                    .ok_or_else(truncated)?;
+            let name = index[pos..nul].to_vec();
+            pos = entry_start + (nul - entry_start + 8) / 8 * 8;
+            name
+        };
+
+        if name == path && stage == 0 {
+            return Ok(Some(id));
+        }
+        previous_name = name;
+    }
+    Ok(None)
+}
+
+/// 40文字の16進数の文字列をオブジェクトIDに変換します。
+fn parse_hex_id(hex: &[u8]) -> Option<ObjectId> {
+    let hex = hex.get(..40)?;
+    let mut id = [0u8; 20];
+    for (i, pair) in hex.chunks(2).enumerate() {
+        let pair = std::str::from_utf8(pair).ok()?;
+        id[i] = u8::from_str_radix(pair, 16).ok()?;
+    }
+    Some(id)
+}
+
+/// オブジェクトIDを16進数の文字列に変換します。
+fn to_hex(id: &ObjectId) -> String {
+    id.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    let mut byte = [0u8; 1];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn read_be_u32(data: &[u8], pos: usize) -> u32 {
+    u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]])
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    /// テスト用の一時ディレクトリで git コマンドを実行します。（利用者の設定には影響されない）
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args([
+                "-c",
+                "commit.gpgsign=false",
+                "-c",
+                "init.defaultBranch=main",
+            ])
+            .args(args)
+            .current_dir(dir)
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .env("GIT_CONFIG_GLOBAL", "/dev/null")
+            .output()
+            .expect("git コマンドを実行できません");
+        assert!(
+            output.status.success(),
+            "git {:?}: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    /// ファイルを書き込みます。（親ディレクトリも作成する）
+    fn write(dir: &Path, relative_path: &str, content: &str) {
+        let path = dir.join(relative_path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    /// 差分（delta）になりやすい、少しずつ異なる長めの内容を作成します。
+    fn long_content(revision: usize) -> String {
+        (0..200)
+            .map(|line| {
+                if line % 50 == revision % 50 {
+                    format!("line {} changed in revision {}\n", line, revision)
+                } else {
+                    format!(
+                        "line {} of a long file that compresses well as a delta\n",
+                        line
+                    )
+                }
+            })
+            .collect()
+    }
+
+    /// 共通の接頭辞を持つパスを含むファイルを、何度かに分けてコミットしたリポジトリを作成します。
+    /// 最後のコミットでの各ファイルの内容を返します。
+    fn create_repo(dir: &Path) -> Vec<(String, String)> {
+        git(dir, &["init", "-q"]);
+        let mut files = Vec::new();
+        for revision in 0..5 {
+            files = vec![
+                ("README.md".to_string(), format!("readme {}\n", revision)),
+                ("src/app/editor.rs".to_string(), long_content(revision)),
+                (
+                    "src/app/editor_block.rs".to_string(),
+                    long_content(revision + 1),
+                ),
+                ("src/app.rs".to_string(), long_content(revision + 2)),
+                ("src/main.rs".to_string(), "fn main() {}\n".to_string()),
+            ];
+            for (path, content) in &files {
+                write(dir, path, content);
+            }
+            git(dir, &["add", "-A"]);
+            git(
+                dir,
+                &["commit", "-q", "-m", &format!("revision {}", revision)],
+            );
+        }
+        files
+    }
+
+    /// HEAD とインデックスから読み取った内容が、コミットした内容と一致することを確かめます。
+    fn assert_reads_committed_files(dir: &Path, files: &[(String, String)]) {
+        let repo = GitRepo::discover(&dir.join("README.md")).expect("リポジトリが見つかりません");
+        for (path, content) in files {
+            let relative_path = repo.relative_path(&dir.join(path)).unwrap();
+            assert_eq!(&relative_path, path);
+            let head = repo.read_head_file(path).unwrap();
+            assert_eq!(
+                head.as_deref(),
+                Some(content.as_bytes()),
+                "HEAD の {}",
+                path
+            );
+            let index = repo.read_index_file(path).unwrap();
+            assert_eq!(
+                index.as_deref(),
+                Some(content.as_bytes()),
+                "インデックスの {}",
+                path
+            );
+        }
+        assert_eq!(repo.read_head_file("src/missing.rs").unwrap(), None);
+        assert_eq!(repo.read_index_file("src/missing.rs").unwrap(), None);
+        assert_eq!(repo.read_head_file("src/app").unwrap(), None); // ディレクトリはファイルではない
+    }
+
+    /// パックファイル内の差分（delta）で格納されたオブジェクトの数を返します。
+    fn count_deltas(dir: &Path) -> usize {
+        let pack_dir = dir.join(".git/objects/pack");
+        let idx = fs::read_dir(&pack_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .find(|path| path.extension().is_some_and(|ext| ext == "idx"))
+            .expect("パックファイルがありません");
+        git(dir, &["verify-pack", "-v", idx.to_str().unwrap()])
+            .lines()
+            .filter(|line| line.contains(" blob ") && line.split_whitespace().count() == 7)
+            .count()
+    }
+
+    #[test]
+    fn reads_loose_objects() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = create_repo(dir.path());
+        assert!(
+            !dir.path()
+                .join(".git/objects/pack")
+                .read_dir()
+                .unwrap()
+                .any(|_| true)
+        );
+        assert_reads_committed_files(dir.path(), &files);
+    }
+
+    #[test]
+    fn reads_staged_content_separately_from_head() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = create_repo(dir.path());
+        write(dir.path(), "src/main.rs", "fn main() { staged(); }\n");
+        git(dir.path(), &["add", "src/main.rs"]);
+        write(dir.path(), "src/main.rs", "fn main() { unstaged(); }\n");
+
+        let repo = GitRepo::discover(dir.path()).unwrap();
+        let head = repo.read_head_file("src/main.rs").unwrap();
+        assert_eq!(head.as_deref(), Some(files[4].1.as_bytes()));
+        let index = repo.read_index_file("src/main.rs").unwrap();
+        assert_eq!(index.as_deref(), Some(&b"fn main() { staged(); }\n"[..]));
+    }
+
+    #[test]
+    fn reads_packed_objects_with_offset_deltas() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = create_repo(dir.path());
+        git(dir.path(), &["gc", "-q", "--prune=now"]);
+        assert!(
+            count_deltas(dir.path()) > 0,
+            "OFS_DELTA のオブジェクトがありません"
+        );
+        assert_reads_committed_files(dir.path(), &files);
+    }
+
+    #[test]
+    fn reads_packed_objects_with_ref_deltas() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = create_repo(dir.path());
+        // オフセットではなくオブジェクトIDで差分の元を指す（REF_DELTA）パックを作成する
+        git(
+            dir.path(),
+            &[
+                "-c",
+                "repack.useDeltaBaseOffset=false",
+                "repack",
+                "-q",
+                "-a",
+                "-d",
+                "-f",
+            ],
+        );
+        git(dir.path(), &["prune"]);
+        assert!(
+            count_deltas(dir.path()) > 0,
+            "REF_DELTA のオブジェクトがありません"
+        );
+        assert_reads_committed_files(dir.path(), &files);
+    }
+
+    /// インデックスのバージョンを返します。
+    fn index_version(dir: &Path) -> u32 {
+        read_be_u32(&fs::read(dir.join(".git/index")).unwrap(), 4)
+    }
+
+    #[test]
+    fn reads_index_versions_2_and_4() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = create_repo(dir.path());
+        // v4 はパスを直前のエントリとの共通部分を除いて格納する
+        for version in [4, 2] {
+            git(
+                dir.path(),
+                &["update-index", "--index-version", &version.to_string()],
+            );
+            assert_eq!(index_version(dir.path()), version);
+            assert_reads_committed_files(dir.path(), &files);
+        }
+    }
+
+    #[test]
+    fn reads_index_v3_extended_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = create_repo(dir.path());
+        // intent-to-add のエントリは拡張フラグを持つため、インデックスは v3 以上になる
+        write(dir.path(), "src/app/new.rs", "new\n");
+        git(dir.path(), &["add", "-N", "src/app/new.rs"]);
+        for version in [3, 4] {
+            git(
+                dir.path(),
+                &["update-index", "--index-version", &version.to_string()],
+            );
+            assert_eq!(index_version(dir.path()), version);
+            assert_reads_committed_files(dir.path(), &files);
+        }
+    }
+
+    /// 壊れたpackを読むためのリポジトリを作ります。`objects`に packfile と（`ids`の分だけ）インデックスを書き込みます。
+    /// `objects`は各オブジェクトの種類・内容（deltaの場合は参照先を含む）で、`ids`は先頭から順に対応するIDです。
+    fn corrupt_pack_repo(dir: &Path, objects: &[Vec<u8>], ids: &[ObjectId]) -> (GitRepo, Vec<u64>) {
+        let pack_dir = dir.join("objects/pack");
+        fs::create_dir_all(&pack_dir).unwrap();
+        let mut pack = b"PACK".to_vec();
+        pack.extend(2u32.to_be_bytes());
+        pack.extend((objects.len() as u32).to_be_bytes());
+        let mut offsets = Vec::new();
+        for object in objects {
+            offsets.push(pack.len() as u64);
+            pack.extend(object);
+        }
+        fs::write(pack_dir.join("test.pack"), pack).unwrap();
+
+        let mut idx = vec![0xff, b't', b'O', b'c', 0, 0, 0, 2];
+        for i in 0..256 {
+            let count = ids.iter().filter(|id| id[0] as usize <= i).count() as u32;
+            idx.extend(count.to_be_bytes());
+        }
+        ids.iter().for_each(|id| idx.extend(id));
+        ids.iter().for_each(|_| idx.extend([0; 4])); // CRC
+        for offset in offsets.iter().take(ids.len()) {
+            idx.extend((*offset as u32).to_be_bytes());
+        }
+        fs::write(pack_dir.join("test.idx"), idx).unwrap();
+
+        let repo = GitRepo {
+            work_dir: dir.to_path_buf(),
+            git_dir: dir.to_path_buf(),
+            common_dir: dir.to_path_buf(),
+        };
+        (repo, offsets)
+    }
+
+    /// packのオブジェクトを作ります。（`base`はdeltaの参照先、`data`は圧縮前の内容）
+    fn pack_object(type_code: u8, base: &[u8], data: &[u8]) -> Vec<u8> {
+        use flate2::Compression;
+        use flate2::write::ZlibEncoder;
+        use std::io::Write;
+
+        assert!(data.len() < 16);
+        let mut object = vec![(type_code << 4) | data.len() as u8];
+        object.extend(base);
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        object.extend(encoder.finish().unwrap());
+        object
+    }
+
+    /// 元の1バイトのオブジェクトを、同じ1バイトの内容に置き換える delta
+    const DELTA_X: &[u8] = &[1, 1, 1, b'x'];
+
+    #[test]
+    fn rejects_offset_delta_to_itself() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo, offsets) = corrupt_pack_repo(dir.path(), &[pack_object(6, &[0], DELTA_X)], &[]);
+        let mut pack =
+            BufReader::new(File::open(dir.path().join("objects/pack/test.pack")).unwrap());
+        let error = repo.read_pack_object(&mut pack, offsets[0], 0).unwrap_err();
+        assert_eq!(error.to_string(), "deltaの参照先が正しくありません");
+
+        // 距離が u64 に収まらない場合
+        let (repo, offsets) =
+            corrupt_pack_repo(dir.path(), &[pack_object(6, &[0xff; 11], DELTA_X)], &[]);
+        let mut pack =
+            BufReader::new(File::open(dir.path().join("objects/pack/test.pack")).unwrap());
+        let error = repo.read_pack_object(&mut pack, offsets[0], 0).unwrap_err();
+        assert_eq!(error.to_string(), "deltaの参照先が正しくありません");
+    }
+
+    #[test]
+    fn rejects_ref_delta_cycles() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = [0x42; 20];
+        let (repo, _) = corrupt_pack_repo(dir.path(), &[pack_object(7, &id, DELTA_X)], &[id]);
+        let error = repo.read_object(&id).unwrap_err();
+        assert_eq!(error.to_string(), "deltaの連鎖が深すぎます");
+    }
+
+    #[test]
+    fn limits_offset_delta_chains() {
+        let dir = tempfile::tempdir().unwrap();
+        // 先頭の blob に続けて、それぞれ直前のオブジェクトを参照する delta を並べる
+        let mut objects = vec![pack_object(3, &[], b"x")];
+        for _ in 0..=MAX_DELTA_DEPTH {
+            let distance = objects.last().unwrap().len() as u8;
+            assert!(distance < 0x80);
+            objects.push(pack_object(6, &[distance], DELTA_X));
+        }
+        let (repo, offsets) = corrupt_pack_repo(dir.path(), &objects, &[]);
+        let mut pack =
+            BufReader::new(File::open(dir.path().join("objects/pack/test.pack")).unwrap());
+        let object = repo
+            .read_pack_object(&mut pack, offsets[MAX_DELTA_DEPTH], 0)
+            .unwrap();
+        assert_eq!(object, (ObjectKind::Blob, b"x".to_vec()));
+        let error = repo
+            .read_pack_object(&mut pack, offsets[MAX_DELTA_DEPTH + 1], 0)
+            .unwrap_err();
+        assert_eq!(error.to_string(), "deltaの連鎖が深すぎます");
+    }
+}
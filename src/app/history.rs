@@ -0,0 +1,298 @@
+// src/app/history.rs
+
+use super::cursor::Cursor;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// 履歴に保持する操作の最大数。これを超えた古い操作は破棄されます。
+const MAX_HISTORY_LEN: usize = 1000;
+
+/// 履歴ファイルの形式のバージョン。互換性のない変更を加えた場合は値を上げます。
+const HISTORY_FILE_VERSION: u32 = 1;
+
+/// バッファに対する1回の変更（置換）を表します。
+/// 挿入は `deleted` が空、削除は `inserted` が空の置換として表現します。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Edit {
+    pub offset: usize,    // 変更開始位置 (バイトオフセット)
+    pub deleted: String,  // 変更によって削除されたテキスト
+    pub inserted: String, // 変更によって挿入されたテキスト
+}
+
+/// 変更の種類。連続した入力を1つの操作にまとめるかどうかの判定に使います。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EditKind {
+    Insert,        // 文字入力
+    Backspace,     // Backspaceによる削除
+    DeleteForward, // Deleteキーによる削除
+    Other,         // 貼り付け・切り取り・置換など、まとめない変更
+}
+
+/// 1回の「元に戻す」「やり直し」で取り消される変更のまとまりです。
+/// 変更前後のカーソル（選択範囲を含む）も一緒に保存します。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transaction {
+    pub edits: Vec<Edit>,
+    pub cursor_before: Cursor,
+    pub cursor_after: Cursor,
+    pub kind: EditKind,
+}
+
+impl Transaction {
+    /// 直前の変更に新しい変更を連結できる場合は連結し、`true`を返します。
+    fn try_merge(&mut self, edit: &Edit, kind: EditKind) -> bool {
+        if self.kind != kind || self.edits.len() != 1 {
+            return false;
+        }
+        let last = &mut self.edits[0];
+        match kind {
+            // 直前の入力の直後への入力のみを連結する（改行で区切る）
+            EditKind::Insert => {
+                if edit.deleted.is_empty()
+                    && last.offset + last.inserted.len() == edit.offset
+                    && !last.inserted.ends_with('\n')
+                    && !edit.inserted.contains('\n')
+                {
+                    last.inserted.push_str(&edit.inserted);
+                    return true;
+                }
+            }
+            // Backspaceは削除位置が前に伸びていく
+            EditKind::Backspace => {
+                if edit.inserted.is_empty()
+                    && last.inserted.is_empty()
+                    && edit.offset + edit.deleted.len() == last.offset
+                {
+                    last.offset = edit.offset;
+                    last.deleted.insert_str(0, &edit.deleted);
+                    return true;
+                }
+            }
+            // Deleteは同じ位置で削除が後ろに伸びていく
+            EditKind::DeleteForward => {
+                if edit.inserted.is_empty()
+                    && last.inserted.is_empty()
+                    && edit.offset == last.offset
+                {
+                    last.deleted.push_str(&edit.deleted);
+                    return true;
+                }
+            }
+            EditKind::Other => {}
+        }
+        false
+    }
+}
+
+/// Undo/Redoの履歴を管理します。
+/// この構造体はバッファそのものには触れず、変更の記録と取り出しのみを担当します。
+/// 実際のバッファへの適用はEditor側で行います。
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct History {
+    pub undo_stack: VecDeque<Transaction>, // 古い操作を先頭から捨てるためVecDequeで持つ
+    pub redo_stack: Vec<Transaction>,
+    #[serde(skip)]
+    group_depth: usize, // begin_group/end_groupの入れ子の深さ
+}
+
+/// 履歴ファイルに書き込む内容です。
+#[derive(Serialize)]
+struct HistoryFileRef<'a> {
+    version: u32,
+    content_hash: u64, // 履歴を保存した時点のバッファ内容のハッシュ
+    history: &'a History,
+}
+
+/// 履歴ファイルから読み込む内容です。
+#[derive(Deserialize)]
+struct HistoryFile {
+    version: u32,
+    content_hash: u64,
+    history: History,
+}
+
+impl History {
+    /// 新しい変更を履歴に記録します。
+    /// 連続した入力は直前の操作に連結され、1回のUndoでまとめて取り消されます。
+    pub fn record(
+        &mut self,
+        edit: Edit,
+        kind: EditKind,
+        cursor_before: Cursor,
+        cursor_after: Cursor,
+    ) {
+        // 新しい変更を行ったら、やり直しの履歴は無効になる
+        self.redo_stack.clear();
+
+        if let Some(last) = self.undo_stack.back_mut() {
+            // グループ化中は、全ての変更を開いているトランザクションに追加する
+            if self.group_depth > 0 {
+                last.edits.push(edit);
+                last.cursor_after = cursor_after;
+                return;
+            }
+            if last.try_merge(&edit, kind) {
+                last.cursor_after = cursor_after;
+                return;
+            }
+        }
+
+        self.push(Transaction {
+            edits: vec![edit],
+            cursor_before,
+            cursor_after,
+            kind,
+        });
+    }
+
+    /// 複数の変更を1回のUndoで取り消せるようにまとめ始めます。
+    /// `end_group`が呼ばれるまでに記録された変更は、1つの操作として扱われます。
+    pub fn begin_group(&mut self, cursor: Cursor) {
+        if self.group_depth == 0 {
+            self.redo_stack.clear();
+            self.push(Transaction {
+                edits: Vec::new(),
+                cursor_before: cursor,
+                cursor_after: cursor,
+                kind: EditKind::Other,
+            });
+        }
+        self.group_depth += 1;
+    }
+
+    /// `begin_group`で開始したまとまりを閉じます。
+    /// 変更が1つも記録されなかった場合、空の操作は履歴から取り除きます。
+    pub fn end_group(&mut self) {
+        if self.group_depth == 0 {
+            return;
+        }
+        self.group_depth -= 1;
+        if self.group_depth == 0
+            && self
+                .undo_stack
+                .back()
+                .is_some_and(|transaction| transaction.edits.is_empty())
+        {
+            self.undo_stack.pop_back();
+        }
+    }
+
+    /// 元に戻す操作を取り出し、やり直し用の履歴に移します。
+    pub fn undo(&mut self) -> Option<&Transaction> {
+        let transaction = self.undo_stack.pop_back()?;
+        self.redo_stack.push(transaction);
+        self.redo_stack.last()
+    }
+
+    /// やり直す操作を取り出し、元に戻す用の履歴に移します。
+    pub fn redo(&mut self) -> Option<&Transaction> {
+        let mut transaction = self.redo_stack.pop()?;
+        // やり直した操作に後続の入力が連結されないようにする
+        transaction.kind = EditKind::Other;
+        self.undo_stack.push_back(transaction);
+        self.undo_stack.back()
+    }
+
+    /// 直前の操作に、この後の入力が連結されないようにします。
+    /// 保存した時点の内容に、Undoでちょうど戻れるようにするために使います。
+    pub fn seal(&mut self) {
+        if let Some(last) = self.undo_stack.back_mut() {
+            last.kind = EditKind::Other;
+        }
+    }
+
+    /// 履歴が空かどうかを返します。
+    pub fn is_empty(&self) -> bool {
+        self.undo_stack.is_empty() && self.redo_stack.is_empty()
+    }
+
+    /// 履歴を全て破棄します。（ファイルを読み込み直した場合など）
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.group_depth = 0;
+    }
+
+    /// 履歴をファイルに保存します。
+    /// `content_hash`には現在のバッファ内容のハッシュを渡します。
+    pub fn save_to_file(&self, path: &Path, content_hash: u64) -> io::Result<()> {
+        let file = HistoryFileRef {
+            version: HISTORY_FILE_VERSION,
+            content_hash,
+            history: self,
+        };
+        let data = serde_json::to_vec(&file).map_err(io::Error::other)?;
+        fs::write(path, data)
+    }
+
+    /// ファイルに保存された履歴を読み込みます。
+    /// 保存時のバッファ内容と`content_hash`が一致しない場合（ファイルが外部で変更された場合など）、
+    /// 履歴は現在の内容に適用できないため`None`を返します。
+    pub fn load_from_file(path: &Path, content_hash: u64) -> io::Result<Option<Self>> {
+        let data = fs::read(path)?;
+        let file: HistoryFile = serde_json::from_slice(&data)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if file.version != HISTORY_FILE_VERSION || file.content_hash != content_hash {
+            return Ok(None);
+        }
+        Ok(Some(file.history))
+    }
+
+    fn push(&mut self, transaction: Transaction) {
+        self.undo_stack.push_back(transaction);
+        if self.undo_stack.len() > MAX_HISTORY_LEN {
+            self.undo_stack.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(offset: usize, text: &str) -> Edit {
+        Edit {
+            offset,
+            deleted: String::new(),
+            inserted: text.to_string(),
+        }
+    }
+
+    /// 履歴が上限を超えると、最も古い操作から破棄されることを確かめます。
+    #[test]
+    fn drops_oldest_transactions_beyond_limit() {
+        let mut history = History::default();
+        for i in 0..MAX_HISTORY_LEN + 5 {
+            // 改行を含む入力は連結されないので、1回ごとに1つの操作になる
+            history.record(
+                insert(i, "\n"),
+                EditKind::Insert,
+                Cursor::new(0, i),
+                Cursor::new(0, i + 1),
+            );
+        }
+        assert_eq!(history.undo_stack.len(), MAX_HISTORY_LEN);
+        assert_eq!(history.undo_stack.front().unwrap().edits[0].offset, 5);
+
+        let mut undone = 0;
+        while history.undo().is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, MAX_HISTORY_LEN);
+    }
+
+    /// 種類の異なる変更や、離れた位置への入力は連結しないことを確かめます。
+    #[test]
+    fn does_not_merge_unrelated_edits() {
+        let mut history = History::default();
+        let cursor = Cursor::default();
+        history.record(insert(0, "a"), EditKind::Insert, cursor, cursor);
+        history.record(insert(5, "b"), EditKind::Insert, cursor, cursor);
+        history.record(insert(6, "c"), EditKind::Other, cursor, cursor);
+        history.record(insert(7, "d"), EditKind::Other, cursor, cursor);
+        assert_eq!(history.undo_stack.len(), 4);
+    }
+}
@@ -0,0 +1,166 @@
+// src/app/hunks.rs
+
+use super::App;
+use super::diff::Hunk;
+use crate::msg;
+
+impl App {
+    /// カーソル位置より後ろにある次の変更箇所（ハンク）の先頭に移動します。末尾まで来たら先頭に戻ります。
+    pub fn next_hunk(&mut self) {
+        self.document_mut().refresh_diff_if_stale();
+        let cursor_y = self.editor().cursor.y;
+        let target = match self
+            .document()
+            .diff_hunks
+            .iter()
+            .find(|h| h.new_start > cursor_y)
+        {
+            Some(hunk) => Some(*hunk),
+            None => {
+                let first = self.document().diff_hunks.first().copied();
+                if first.is_some() {
+                    msg!(self, "最初の変更箇所に戻りました。");
+                }
+                first
+            }
+        };
+        self.move_to_hunk(target);
+    }
+
+    /// カーソル位置より前にある変更箇所（ハンク）の先頭に移動します。先頭まで来たら末尾に戻ります。
+    pub fn previous_hunk(&mut self) {
+        self.document_mut().refresh_diff_if_stale();
+        let cursor_y = self.editor().cursor.y;
+        let target = match self
+            .document()
+            .diff_hunks
+            .iter()
+            .rev()
+            .find(|h| h.new_start < cursor_y)
+        {
+            Some(hunk) => Some(*hunk),
+            None => {
+                let last = self.document().diff_hunks.last().copied();
+                if last.is_some() {
+                    msg!(self, "最後の変更箇所に戻りました。");
+                }
+                last
+            }
+        };
+        self.move_to_hunk(target);
+    }
+
+    /// カーソル行を含む変更箇所（ハンク）を返します。
+    /// ファイル末尾の行が削除された場合は、最終行にその削除のハンクが含まれるものとします。
+    pub fn hunk_at_cursor(&self) -> Option<Hunk> {
+        let cursor_y = self.editor().cursor.y;
+        let line_count = self.editor().buffer.len_lines();
+        self.document()
+            .diff_hunks
+            .iter()
+            .find(|hunk| {
+                hunk.contains_line(cursor_y)
+                    || (hunk.new_len == 0
+                        && hunk.new_start == line_count
+                        && cursor_y + 1 == line_count)
+            })
+            .copied()
+    }
+
+    /// カーソル行の変更箇所について、比較元の内容のプレビュー表示を切り替えます。
+    pub fn toggle_hunk_preview(&mut self) {
+        self.document_mut().refresh_diff_if_stale();
+        if self.hunk_preview_visible {
+            self.hunk_preview_visible = false;
+        } else if self.hunk_at_cursor().is_some() {
+            self.hunk_preview_visible = true;
+        } else {
+            msg!(self, "カーソル行に変更箇所がありません。");
+        }
+    }
+
+    /// カーソル行の変更箇所（ハンク）だけを比較元の内容に戻します。1回のUndoで取り消せます。
+    pub fn revert_hunk(&mut self) {
+        self.document_mut().refresh_diff_if_stale();
+        let Some(hunk) = self.hunk_at_cursor() else {
+            msg!(self, "カーソル行に変更箇所がありません。");
+            return;
+        };
+
+        let original_lines: Vec<&str> = self.document().original_buffer.split('\n').collect();
+        let buffer = &self.editor().buffer;
+        let at_end_of_file = hunk.new_start + hunk.new_len == buffer.len_lines();
+
+        // 比較元でのハンクの範囲（バイトオフセット）
+        let original_line_start = |y: usize| -> usize {
+            original_lines[..y.min(original_lines.len())]
+                .iter()
+                .map(|line| line.len() + 1)
+                .sum()
+        };
+        let (original_start, original_end) = hunk_byte_range(
+            hunk.old_start,
+            hunk.old_len,
+            at_end_of_file,
+            self.document().original_buffer.len(),
+            original_line_start,
+        );
+        // 現在のバッファでのハンクの範囲（バイトオフセット）
+        let (buffer_start, buffer_end) = hunk_byte_range(
+            hunk.new_start,
+            hunk.new_len,
+            at_end_of_file,
+            buffer.len_bytes(),
+            |y| {
+                if y >= buffer.len_lines() {
+                    buffer.len_bytes() + 1 // 最終行の後ろにある仮想的な改行の直後
+                } else {
+                    buffer.line_to_byte(y)
+                }
+            },
+        );
+
+        let original_text =
+            self.document().original_buffer[original_start..original_end].to_string();
+        self.editor_mut()
+            .replace_buffer_range(buffer_start, buffer_end, &original_text);
+        let y = hunk.new_start.min(self.editor().buffer.len_lines() - 1);
+        self.editor_mut().set_cursor_position(0, y, false);
+        self.hunk_preview_visible = false;
+        self.document_mut().calculate_diff_status(); // 戻した結果をすぐにガターに反映する
+        msg!(
+            self,
+            "変更箇所を{}の内容に戻しました。",
+            self.document().diff_base.label()
+        );
+    }
+
+    /// 指定されたハンクの先頭行に移動します。
+    fn move_to_hunk(&mut self, hunk: Option<Hunk>) {
+        let Some(hunk) = hunk else {
+            msg!(self, "変更箇所はありません。");
+            return;
+        };
+        let y = hunk.new_start.min(self.editor().buffer.len_lines() - 1);
+        self.editor_mut().set_cursor_position(0, y, false);
+    }
+}
+
+/// ハンクの行範囲 (`start`行目から`len`行) に対応するバイト範囲を求めます。
+/// `line_start(y)`は`y`行目の先頭のバイトオフセット（行数以上なら末尾＋1）を返す関数です。
+/// ハンクがファイル末尾まで続く場合、最終行には改行がないため、代わりに直前の改行を範囲に含めます。
+fn hunk_byte_range(
+    start: usize,
+    len: usize,
+    at_end_of_file: bool,
+    text_len: usize,
+    line_start: impl Fn(usize) -> usize,
+) -> (usize, usize) {
+    if at_end_of_file && start > 0 {
+        (line_start(start) - 1, text_len)
+    } else if at_end_of_file {
+        (0, text_len)
+    } else {
+        (line_start(start), line_start(start + len))
+    }
+}
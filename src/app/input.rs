@@ -0,0 +1,82 @@
+// src/app/input.rs
+
+/// 検索バーなどで使う1行の入力欄の内容とカーソル位置を管理します。
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct InputLine {
+    pub text: String,
+    pub cursor: usize, // カーソル位置 (0-indexed, 文字単位)
+}
+
+impl InputLine {
+    /// 初期テキストを持つ入力欄を作成します。カーソルは末尾に置かれます。
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            cursor: text.chars().count(),
+        }
+    }
+
+    /// カーソル位置に文字を挿入します。
+    pub fn insert_char(&mut self, c: char) {
+        let byte_idx = self.byte_index();
+        self.text.insert(byte_idx, c);
+        self.cursor += 1;
+    }
+
+    /// カーソル位置に文字列を挿入します。入力欄は1行なので、改行を含む場合は最初の行だけを挿入します。
+    pub fn insert_str(&mut self, text: &str) {
+        let line = text.lines().next().unwrap_or_default();
+        let byte_idx = self.byte_index();
+        self.text.insert_str(byte_idx, line);
+        self.cursor += line.chars().count();
+    }
+
+    /// カーソルの前の文字を削除します。削除した場合は`true`を返します。
+    pub fn delete_previous_char(&mut self) -> bool {
+        if self.cursor == 0 {
+            return false;
+        }
+        self.cursor -= 1;
+        let byte_idx = self.byte_index();
+        self.text.remove(byte_idx);
+        true
+    }
+
+    /// カーソル位置の文字を削除します。削除した場合は`true`を返します。
+    pub fn delete_current_char(&mut self) -> bool {
+        let byte_idx = self.byte_index();
+        if byte_idx >= self.text.len() {
+            return false;
+        }
+        self.text.remove(byte_idx);
+        true
+    }
+
+    /// カーソルを1文字左に移動します。
+    pub fn move_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    /// カーソルを1文字右に移動します。
+    pub fn move_right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.text.chars().count());
+    }
+
+    /// カーソルを先頭に移動します。
+    pub fn move_to_start(&mut self) {
+        self.cursor = 0;
+    }
+
+    /// カーソルを末尾に移動します。
+    pub fn move_to_end(&mut self) {
+        self.cursor = self.text.chars().count();
+    }
+
+    /// カーソル位置（文字単位）をテキスト内のバイトオフセットに変換します。
+    fn byte_index(&self) -> usize {
+        self.text
+            .char_indices()
+            .nth(self.cursor)
+            .map_or(self.text.len(), |(i, _)| i)
+    }
+}
@@ -0,0 +1,388 @@
+// src/app/keymap.rs
+
+use super::App;
+use super::actions::{ACTIONS, Action, find_action};
+use super::config::config_dir;
+use super::keys::KeyChord;
+use crate::{emsg, msg};
+use crossterm::event::KeyCode;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// キー割り当ての設定ファイルの名前
+const KEYMAP_FILE_NAME: &str = "keymap.toml";
+
+/// 設定ファイルで、操作の名前の代わりに指定すると割り当てを解除する値
+const UNBIND: &str = "none";
+
+/// 設定ファイルがない場合に作成する、書き方の例を入れた内容
+const KEYMAP_TEMPLATE: &str = r#"# inedit のキー割り当て
+# "キー" = "操作の名前" の形式で、既定の割り当てに追加・上書きします。
+# 続けて押すキーは空白で区切ります（例: "Ctrl+K Ctrl+C"）。
+# 操作の名前の代わりに "none" を指定すると、既定の割り当てを解除します。
+# 操作の名前はキー割り当ての一覧（Ctrl+K Ctrl+S）で確認できます。
+[bindings]
+# "Ctrl+K Ctrl+C" = "edit.copy"
+# "Ctrl+W" = "none"
+"#;
+
+/// 続けて押すキーの組み合わせの並び（例: `Ctrl+K Ctrl+C`）です。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeySequence(pub Vec<KeyChord>);
+
+impl fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, chord) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}", chord)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for KeySequence {
+    type Err = String;
+
+    /// `Ctrl+K Ctrl+C`のように、空白で区切ったキーの組み合わせの並びを解釈します。
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let chords = text
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<KeyChord>, _>>()?;
+        if chords.is_empty() {
+            return Err("キーが指定されていません".to_string());
+        }
+        Ok(Self(chords))
+    }
+}
+
+/// 押されたキーの並びを割り当てと照らし合わせた結果です。
+pub enum KeyLookup {
+    Action(&'static Action), // 割り当てられた操作
+    Pending,                 // より長い並びの途中なので、続きのキーを待つ
+    Unbound,                 // 何も割り当てられていない
+}
+
+/// キーの並びと操作の対応（キー割り当て）です。
+pub struct Keymap {
+    pub bindings: Vec<(KeySequence, &'static Action)>, // 既定の割り当て、設定ファイルの割り当ての順
+}
+
+impl Default for Keymap {
+    /// 各操作の既定のキーを割り当てます。
+    fn default() -> Self {
+        let bindings = ACTIONS
+            .iter()
+            .flat_map(|action| {
+                action
+                    .default_keys
+                    .iter()
+                    .filter_map(|keys| keys.parse().ok())
+                    .map(move |keys| (keys, action))
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// キーの並びに操作を割り当てます。既に別の操作が割り当てられていれば置き換えます。
+    pub fn bind(&mut self, keys: KeySequence, action: &'static Action) {
+        self.unbind(&keys);
+        self.bindings.push((keys, action));
+    }
+
+    /// キーの並びの割り当てを解除します。割り当てがあった場合は`true`を返します。
+    pub fn unbind(&mut self, keys: &KeySequence) -> bool {
+        let len = self.bindings.len();
+        self.bindings.retain(|(bound, _)| bound != keys);
+        self.bindings.len() != len
+    }
+
+    /// 押されたキーの並びに割り当てられた操作を探します。
+    /// より長い並びの途中でもある場合（`Ctrl+K`と`Ctrl+K Ctrl+C`の両方がある場合など）は、続きのキーを待ちます。
+    pub fn lookup(&self, keys: &[KeyChord]) -> KeyLookup {
+        if self
+            .bindings
+            .iter()
+            .any(|(bound, _)| bound.0.len() > keys.len() && bound.0.starts_with(keys))
+        {
+            return KeyLookup::Pending;
+        }
+        match self.bindings.iter().find(|(bound, _)| bound.0 == keys) {
+            Some((_, action)) => KeyLookup::Action(action),
+            None => KeyLookup::Unbound,
+        }
+    }
+
+    /// 操作に割り当てられているキーの並びを返します。
+    pub fn keys_for<'a>(&'a self, action: &'a Action) -> impl Iterator<Item = &'a KeySequence> {
+        self.bindings
+            .iter()
+            .filter(move |(_, bound)| bound.name == action.name)
+            .map(|(keys, _)| keys)
+    }
+
+    /// 操作に割り当てられているキーを`Ctrl+S / F1`のような表記で返します。
+    pub fn describe_keys(&self, action: &Action) -> String {
+        self.keys_for(action)
+            .map(KeySequence::to_string)
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+
+    /// 設定ファイルの内容を解釈し、割り当てを追加・上書き・解除します。
+    /// ファイル全体を解釈できない場合はエラーを返し、割り当ては変更しません。
+    /// 個々の項目の誤りはその項目だけを無視し、内容を一覧で返します。
+    pub fn apply_config(&mut self, text: &str) -> Result<Vec<String>, String> {
+        let file: KeymapFile = toml::from_str(text).map_err(|e| e.message().to_string())?;
+        let mut errors = Vec::new();
+        for (keys_text, action_name) in &file.bindings {
+            let keys: KeySequence = match keys_text.parse() {
+                Ok(keys) => keys,
+                Err(e) => {
+                    errors.push(format!("\"{}\": {}", keys_text, e));
+                    continue;
+                }
+            };
+            if action_name == UNBIND {
+                self.unbind(&keys);
+            } else if let Some(action) = find_action(action_name) {
+                self.bind(keys, action);
+            } else {
+                errors.push(format!(
+                    "\"{}\": 不明なコマンド \"{}\" です",
+                    keys_text, action_name
+                ));
+            }
+        }
+        // 長い並びの途中になっているキーは、続きのキーを待つため使われない
+        for (keys, action) in &self.bindings {
+            if let Some((longer, _)) = self
+                .bindings
+                .iter()
+                .find(|(longer, _)| longer.0.len() > keys.0.len() && longer.0.starts_with(&keys.0))
+            {
+                errors.push(format!(
+                    "\"{}\" ({}) は \"{}\" の途中のため使われません",
+                    keys, action.name, longer
+                ));
+            }
+        }
+        Ok(errors)
+    }
+}
+
+/// キー割り当ての設定ファイルの形式です。
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeymapFile {
+    #[serde(default)]
+    bindings: BTreeMap<String, String>, // キーの並び → 操作の名前
+}
+
+/// キー割り当ての設定ファイルのパスを返します。
+pub fn keymap_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(KEYMAP_FILE_NAME))
+}
+
+/// キー割り当ての一覧のポップアップの状態です。
+pub struct KeybindingsView {
+    pub scroll_offset: usize, // 表示している先頭の行
+    pub height: usize,        // 一覧を表示できる行数（描画時に設定する）
+}
+
+impl KeybindingsView {
+    /// `delta`行だけスクロールします。一覧の末尾が表示領域の下端を越えないようにします。
+    pub fn scroll(&mut self, delta: isize) {
+        let max = ACTIONS.len().saturating_sub(self.height);
+        self.scroll_offset = self.scroll_offset.saturating_add_signed(delta).min(max);
+    }
+
+    /// 一覧を表示できる行数を設定し、スクロール位置を収まる範囲に直します。
+    pub fn set_height(&mut self, height: usize) {
+        self.height = height;
+        self.scroll(0);
+    }
+}
+
+impl App {
+    /// 既定のキー割り当てに設定ファイルの割り当てを反映して、キー割り当てを読み込みます。
+    pub fn load_keymap(&mut self) {
+        let mut keymap = Keymap::default();
+        if let Some(path) = keymap_path() {
+            match fs::read_to_string(&path) {
+                Ok(text) => match keymap.apply_config(&text) {
+                    Ok(errors) => {
+                        for error in errors {
+                            emsg!(self, "キー割り当て {:?} の誤り: {}", path, error);
+                        }
+                        msg!(self, "キー割り当てを {:?} から読み込みました。", path);
+                    }
+                    Err(e) => emsg!(
+                        self,
+                        "キー割り当て {:?} を解釈できません: {}。既定の割り当てを使います。",
+                        path,
+                        e
+                    ),
+                },
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {} // 設定ファイルがなければ既定の割り当てのまま
+                Err(e) => emsg!(
+                    self,
+                    "キー割り当て {:?} の読み込み中にエラーが発生しました: {}",
+                    path,
+                    e
+                ),
+            }
+        }
+        self.keymap = keymap;
+        self.pending_keys.clear();
+    }
+
+    /// キー割り当ての設定ファイルを開きます。ファイルがなければ、書き方の例を入れて作成します。
+    pub fn open_keymap_config(&mut self) {
+        let Some(path) = keymap_path() else {
+            emsg!(
+                self,
+                "設定ディレクトリが分かりません。HOME または XDG_CONFIG_HOME を設定してください。"
+            );
+            return;
+        };
+        if !path.exists() {
+            let created = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(&path, KEYMAP_TEMPLATE));
+            if let Err(e) = created {
+                emsg!(
+                    self,
+                    "キー割り当て {:?} を作成できませんでした: {}",
+                    path,
+                    e
+                );
+                return;
+            }
+        }
+        self.open_file(&path);
+    }
+
+    /// 押されたキーを、続けて押されたキーと合わせて割り当てと照らし合わせ、操作が決まれば実行します。
+    /// 何も割り当てられていない1つのキーの場合は`false`を返します。（呼び出し元で文字の入力などとして扱う）
+    pub fn handle_key_chord(&mut self, chord: KeyChord) -> bool {
+        self.pending_keys.push(chord);
+        match self.keymap.lookup(&self.pending_keys) {
+            KeyLookup::Action(action) => {
+                self.pending_keys.clear();
+                (action.run)(self);
+                true
+            }
+            KeyLookup::Pending => true, // 続きのキーを待つ（入力中のキーはBottom Barに表示する）
+            KeyLookup::Unbound => {
+                let keys = KeySequence(std::mem::take(&mut self.pending_keys));
+                match keys.0.len() {
+                    1 => false,
+                    _ if chord.code == KeyCode::Esc => {
+                        msg!(self, "キーの入力を取り消しました。");
+                        true
+                    }
+                    _ => {
+                        msg!(self, "{} には何も割り当てられていません。", keys);
+                        true
+                    }
+                }
+            }
+        }
+    }
+
+    /// ファインダーなど、キー入力を独自に処理している間に押されたキーを、終了の操作（`app.quit`）のキーと照らし合わせます。
+    /// 続けて押すキーの並び（`Ctrl+K Ctrl+Q`など）に割り当てられている場合も、入力済みのキーと合わせて判定します。
+    /// 終了の操作のキー（またはその並びの途中）として扱った場合は`true`を返します。
+    pub fn handle_quit_key(&mut self, chord: KeyChord) -> bool {
+        let mut keys = std::mem::take(&mut self.pending_keys);
+        keys.push(chord);
+        match self.keymap.lookup(&keys) {
+            KeyLookup::Action(action) if action.name == "app.quit" => {
+                self.request_quit();
+                true
+            }
+            KeyLookup::Pending
+                if self.keymap.bindings.iter().any(|(bound, action)| {
+                    action.name == "app.quit"
+                        && bound.0.len() > keys.len()
+                        && bound.0.starts_with(&keys)
+                }) =>
+            {
+                self.pending_keys = keys; // 続きのキーを待つ
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// キー割り当ての一覧を表示します。
+    pub fn open_keybindings_view(&mut self) {
+        self.keybindings_view = Some(KeybindingsView {
+            scroll_offset: 0,
+            height: 0,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::actions::find_action;
+    use crate::app::tabs::CloseConfirm;
+
+    fn chord(text: &str) -> KeyChord {
+        text.parse().unwrap()
+    }
+
+    /// 続けて押すキーの並びに割り当てた終了の操作も、入力欄などを開いている間に使えることを確かめます。
+    #[test]
+    fn handles_quit_key_sequences() {
+        let mut app = App::default();
+        app.keymap.bind(
+            "Ctrl+K Ctrl+Q".parse().unwrap(),
+            find_action("app.quit").unwrap(),
+        );
+
+        // 終了と関係のないキーは扱わず、入力済みのキーも捨てる
+        assert!(!app.handle_quit_key(chord("Ctrl+F")));
+        assert!(app.handle_quit_key(chord("Ctrl+K")));
+        assert_eq!(app.pending_keys, vec![chord("Ctrl+K")]);
+        assert!(!app.handle_quit_key(chord("Ctrl+X")));
+        assert!(app.pending_keys.is_empty());
+
+        assert!(app.handle_quit_key(chord("Ctrl+K")));
+        assert!(app.handle_quit_key(chord("Ctrl+Q")));
+        assert!(app.quit_requested);
+        assert!(app.pending_keys.is_empty());
+    }
+
+    /// 保存していない変更がある場合は、終了する前に確認することを確かめます。
+    #[test]
+    fn asks_before_quitting_with_unsaved_changes() {
+        let mut app = App::default();
+        app.editor_mut().insert_char('a');
+        assert!(app.handle_quit_key(chord("Ctrl+Q")));
+        assert!(!app.quit_requested);
+        assert_eq!(app.close_confirm, Some(CloseConfirm::Quit));
+
+        // 最後のタブを閉じる場合も、同じく確認する
+        app.close_confirm = None;
+        app.run_action("file.close");
+        assert!(!app.quit_requested);
+        assert_eq!(app.close_confirm, Some(CloseConfirm::Quit));
+
+        app.confirm_close(false);
+        assert!(app.quit_requested);
+    }
+}
@@ -0,0 +1,131 @@
+// src/app/keys.rs
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fmt;
+use std::str::FromStr;
+
+/// 修飾キーとキーの組み合わせ（例: `Ctrl+S`、`Alt+Shift+Left`）です。
+/// 文字キーは小文字で持ち、Shiftは修飾キーとして表します。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// キーと修飾キーから組み合わせを作成します。
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self::from(KeyEvent::new(code, modifiers))
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    /// 端末から受け取ったキーイベントを、比較できる形に揃えます。
+    /// 大文字はShift＋小文字に、Shiftを押して入力した記号はShiftなしの記号にします。
+    fn from(key: KeyEvent) -> Self {
+        let modifiers =
+            key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        match key.code {
+            KeyCode::Char(c) if c.is_uppercase() => Self {
+                code: KeyCode::Char(c.to_lowercase().next().unwrap_or(c)),
+                modifiers: modifiers | KeyModifiers::SHIFT,
+            },
+            KeyCode::Char(c) if !c.is_alphabetic() => Self {
+                code: KeyCode::Char(c),
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            KeyCode::BackTab => Self {
+                code: KeyCode::Tab,
+                modifiers: modifiers | KeyModifiers::SHIFT,
+            },
+            code => Self { code, modifiers },
+        }
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "Ctrl+"),
+            (KeyModifiers::ALT, "Alt+"),
+            (KeyModifiers::SHIFT, "Shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) => write!(f, "{}", c.to_uppercase()),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            code => {
+                let name = NAMED_KEYS
+                    .iter()
+                    .find(|(_, named)| *named == code)
+                    .map_or("?", |(name, _)| name);
+                f.write_str(name)
+            }
+        }
+    }
+}
+
+/// 名前で指定できる特殊キーの一覧です。
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("Esc", KeyCode::Esc),
+    ("Enter", KeyCode::Enter),
+    ("Tab", KeyCode::Tab),
+    ("Backspace", KeyCode::Backspace),
+    ("Delete", KeyCode::Delete),
+    ("Insert", KeyCode::Insert),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+];
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    /// `Ctrl+Shift+P`のような表記を解釈します。修飾キーとキーの名前は大文字と小文字を区別しません。
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        // 最後の`+`より後ろがキー（`Ctrl++`のように`+`そのものを指定した場合も考慮）
+        let (modifier_part, key_part) = match text.strip_suffix("++") {
+            Some(rest) => (rest, "+"),
+            None => text.rsplit_once('+').unwrap_or(("", text)),
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        for name in modifier_part.split('+').filter(|name| !name.is_empty()) {
+            modifiers |= match name.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("不明な修飾キー \"{}\" です", name)),
+            };
+        }
+
+        let lower = key_part.to_ascii_lowercase();
+        let code = if let Some((_, code)) = NAMED_KEYS
+            .iter()
+            .find(|(name, _)| name.to_ascii_lowercase() == lower)
+        {
+            *code
+        } else if lower == "space" {
+            KeyCode::Char(' ')
+        } else if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+            KeyCode::F(n)
+        } else {
+            let mut chars = key_part.chars();
+            match (chars.next(), chars.next()) {
+                // 文字キーは小文字で指定したものとみなす（大文字にはShiftを明示する）
+                (Some(c), None) => KeyCode::Char(c.to_lowercase().next().unwrap_or(c)),
+                _ => return Err(format!("不明なキー \"{}\" です", key_part)),
+            }
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
@@ -0,0 +1,191 @@
+// src/app/language.rs
+
+use regex::Regex;
+use std::path::Path;
+use tree_sitter::Language;
+
+/// 1つの言語についての設定です。
+/// 構文ハイライトの文法に加え、コメントの切り替えや自動インデントなどの機能が参照する情報を持ちます。
+#[derive(Debug, Clone)]
+pub struct LanguageConfig {
+    pub name: &'static str, // 言語名（設定ファイルなどで言語を指定する際に使用）
+    pub extensions: &'static [&'static str], // 対応する拡張子（`.`は含まない）
+    pub file_names: &'static [&'static str], // 拡張子によらず対応するファイル名（`Cargo.lock`など）
+    pub first_line_patterns: &'static [&'static str], // 1行目（shebangなど）にマッチする正規表現
+    pub grammar: Option<fn() -> Language>, // tree-sitter の文法（なければ構文ハイライトなし）
+    pub highlights_query: &'static str, // ハイライト用のクエリ
+    pub line_comment: Option<&'static str>, // 行コメントの開始記号
+    pub block_comment: Option<(&'static str, &'static str)>, // ブロックコメントの開始・終了記号
+    pub indent_unit: &'static str, // インデント1段分の文字列
+}
+
+/// 利用できる言語の一覧を管理し、ファイルに対応する言語を判定します。
+/// 新しい言語は`register`で追加でき、描画やハイライトの処理を変更する必要はありません。
+#[derive(Debug, Clone)]
+pub struct LanguageRegistry {
+    languages: Vec<LanguageConfig>,
+    first_line_regexes: Vec<Vec<Regex>>, // 各言語の1行目のパターンを登録時にコンパイルしたもの（languagesと同じ順）
+}
+
+impl Default for LanguageRegistry {
+    /// 組み込みの言語を登録したレジストリを作成します。
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        for language in builtin_languages() {
+            registry.register(language);
+        }
+        registry
+    }
+}
+
+impl LanguageRegistry {
+    /// 言語が1つも登録されていないレジストリを作成します。
+    pub fn empty() -> Self {
+        Self {
+            languages: Vec::new(),
+            first_line_regexes: Vec::new(),
+        }
+    }
+
+    /// 言語を登録します。同じ名前の言語が既にある場合は置き換えます。
+    /// 1行目のパターンは判定のたびにコンパイルしないよう、ここでコンパイルしておきます。（正しくないパターンは無視します）
+    pub fn register(&mut self, language: LanguageConfig) {
+        let regexes = language
+            .first_line_patterns
+            .iter()
+            .filter_map(|pattern| Regex::new(pattern).ok())
+            .collect();
+        match self.languages.iter().position(|l| l.name == language.name) {
+            Some(index) => {
+                self.languages[index] = language;
+                self.first_line_regexes[index] = regexes;
+            }
+            None => {
+                self.languages.push(language);
+                self.first_line_regexes.push(regexes);
+            }
+        }
+    }
+
+    /// 登録されている全ての言語を返します。
+    pub fn languages(&self) -> &[LanguageConfig] {
+        &self.languages
+    }
+
+    /// 名前で言語を探します。
+    pub fn by_name(&self, name: &str) -> Option<&LanguageConfig> {
+        self.languages.iter().find(|l| l.name == name)
+    }
+
+    /// ファイルのパスと1行目の内容から言語を判定します。
+    /// ファイル名、拡張子、1行目のパターンの順に調べ、最初に見つかった言語を返します。
+    pub fn detect(&self, path: Option<&Path>, first_line: &str) -> Option<&LanguageConfig> {
+        if let Some(path) = path {
+            if let Some(file_name) = path.file_name().and_then(|s| s.to_str())
+                && let Some(language) = self
+                    .languages
+                    .iter()
+                    .find(|l| l.file_names.contains(&file_name))
+            {
+                return Some(language);
+            }
+            if let Some(extension) = path.extension().and_then(|s| s.to_str())
+                && let Some(language) = self
+                    .languages
+                    .iter()
+                    .find(|l| l.extensions.contains(&extension))
+            {
+                return Some(language);
+            }
+        }
+        self.languages
+            .iter()
+            .zip(&self.first_line_regexes)
+            .find(|(_, regexes)| regexes.iter().any(|regex| regex.is_match(first_line)))
+            .map(|(language, _)| language)
+    }
+}
+
+/// 組み込みの言語の一覧です。
+/// 文法のない言語も、コメント記号やインデント幅を参照する機能のために登録しておきます。
+fn builtin_languages() -> Vec<LanguageConfig> {
+    vec![
+        LanguageConfig {
+            name: "rust",
+            extensions: &["rs"],
+            file_names: &[],
+            first_line_patterns: &[r"^#!.*\brust-script\b"],
+            grammar: Some(|| tree_sitter_rust::LANGUAGE.into()),
+            highlights_query: tree_sitter_rust::HIGHLIGHTS_QUERY,
+            line_comment: Some("//"),
+            block_comment: Some(("/*", "*/")),
+            indent_unit: "    ",
+        },
+        LanguageConfig {
+            name: "javascript",
+            extensions: &["js", "mjs", "cjs", "jsx"],
+            file_names: &[],
+            first_line_patterns: &[r"^#!.*\b(node|deno|bun)\b"],
+            grammar: Some(|| tree_sitter_javascript::LANGUAGE.into()),
+            highlights_query: tree_sitter_javascript::HIGHLIGHT_QUERY,
+            line_comment: Some("//"),
+            block_comment: Some(("/*", "*/")),
+            indent_unit: "  ",
+        },
+        LanguageConfig {
+            name: "toml",
+            extensions: &["toml"],
+            file_names: &["Cargo.lock"],
+            first_line_patterns: &[],
+            grammar: None,
+            highlights_query: "",
+            line_comment: Some("#"),
+            block_comment: None,
+            indent_unit: "  ",
+        },
+        LanguageConfig {
+            name: "json",
+            extensions: &["json"],
+            file_names: &[".prettierrc", ".eslintrc"],
+            first_line_patterns: &[],
+            grammar: None,
+            highlights_query: "",
+            line_comment: None,
+            block_comment: None,
+            indent_unit: "  ",
+        },
+        LanguageConfig {
+            name: "markdown",
+            extensions: &["md", "markdown"],
+            file_names: &[],
+            first_line_patterns: &[],
+            grammar: None,
+            highlights_query: "",
+            line_comment: None,
+            block_comment: Some(("<!--", "-->")),
+            indent_unit: "  ",
+        },
+        LanguageConfig {
+            name: "python",
+            extensions: &["py", "pyi"],
+            file_names: &[],
+            first_line_patterns: &[r"^#!.*\bpython[0-9.]*\b"],
+            grammar: None,
+            highlights_query: "",
+            line_comment: Some("#"),
+            block_comment: None,
+            indent_unit: "    ",
+        },
+        LanguageConfig {
+            name: "bash",
+            extensions: &["sh", "bash"],
+            file_names: &[".bashrc", ".bash_profile", ".profile"],
+            first_line_patterns: &[r"^#!.*\b(ba|z)?sh\b"],
+            grammar: None,
+            highlights_query: "",
+            line_comment: Some("#"),
+            block_comment: None,
+            indent_unit: "  ",
+        },
+    ]
+}
@@ -0,0 +1,241 @@
+// src/app/mouse.rs
+
+use super::App;
+use super::window::{PaneRegions, View};
+use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
+use std::time::{Duration, Instant};
+
+/// 続けてクリックしたとみなす間隔（ダブルクリック・トリプルクリックの判定に使用）
+const MULTI_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+/// ホイール1回でスクロールする行数
+const WHEEL_SCROLL_LINES: i32 = 3;
+
+/// マウスのボタンを押したままドラッグしている操作です。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Drag {
+    Text,                           // エディタ本体で選択範囲を広げる
+    Gutter { anchor_line: usize },  // 行番号の欄で行単位の選択範囲を広げる
+    Scrollbar { grab_offset: u16 }, // スクロールバーのつまみを動かす（つかんだ位置のつまみの上端からの行数）
+}
+
+/// マウス操作の状態です。
+#[derive(Debug, Default, Clone)]
+pub struct MouseState {
+    pub last_click: Option<(Instant, u16, u16)>, // 最後にクリックした時刻と位置 (桁, 行)
+    pub click_count: u8,                         // 続けてクリックした回数（1〜3）
+    pub drag: Option<Drag>,                      // ドラッグ中の操作（ボタンを押している間のみSome）
+}
+
+impl MouseState {
+    /// クリックを記録し、同じ位置で続けてクリックした回数（1〜3を繰り返す）を返します。
+    fn register_click(&mut self, column: u16, row: u16) -> u8 {
+        let now = Instant::now();
+        let repeated = self.last_click.is_some_and(|(time, c, r)| {
+            now.duration_since(time) <= MULTI_CLICK_INTERVAL && c == column && r == row
+        });
+        self.click_count = if repeated {
+            self.click_count % 3 + 1
+        } else {
+            1
+        };
+        self.last_click = Some((now, column, row));
+        self.click_count
+    }
+}
+
+impl App {
+    /// マウスのイベントを処理します。
+    /// クリックでカーソルを移動し、ドラッグで選択範囲を広げ、ダブルクリックで単語、トリプルクリックで行を選択します。
+    /// 行番号の欄のクリックとドラッグで行を選択し、スクロールバーのつまみのドラッグとホイールでスクロールします。
+    pub fn handle_mouse(&mut self, event: MouseEvent) {
+        // ポップアップや入力欄、差分表示を開いている間はマウスの操作を受け付けない
+        if self.palette.is_some()
+            || self.finder.is_some()
+            || self.keybindings_view.is_some()
+            || self.open_file_prompt.is_some()
+            || self.close_confirm.is_some()
+            || self.explorer.prompt.is_some()
+            || self.diff_view.is_some()
+        {
+            return;
+        }
+        let position = Position::new(event.column, event.row);
+
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let Some(id) = self.pane_at(position) else {
+                    return;
+                };
+                self.explorer.focused = false;
+                self.focus_pane_id(id);
+                let regions = self.pane_regions(id);
+                let extend = event.modifiers.contains(KeyModifiers::SHIFT);
+                if regions.text.contains(position) {
+                    self.click_text(regions.text, position, extend);
+                } else if regions.gutter.contains(position) {
+                    self.click_gutter(regions.text, position, extend);
+                } else if regions.scrollbar.contains(position) {
+                    self.click_scrollbar(regions.scrollbar, position);
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left) => {
+                let Some(drag) = self.mouse.drag else {
+                    return;
+                };
+                let regions = self.pane_regions(self.windows.focused);
+                match drag {
+                    Drag::Text => {
+                        let (x, y) = self.position_in_text(regions.text, position);
+                        self.editor_mut().set_cursor_position(x, y, true);
+                    }
+                    Drag::Gutter { anchor_line } => {
+                        let (_, y) = self.position_in_text(regions.text, position);
+                        self.editor_mut().select_lines(anchor_line, y);
+                    }
+                    Drag::Scrollbar { grab_offset } => {
+                        self.drag_scrollbar(regions.scrollbar, position.y, grab_offset);
+                    }
+                }
+            }
+            MouseEventKind::Up(MouseButton::Left) => {
+                self.mouse.drag = None;
+            }
+            MouseEventKind::ScrollDown => self.scroll_pane_at(position, WHEEL_SCROLL_LINES),
+            MouseEventKind::ScrollUp => self.scroll_pane_at(position, -WHEEL_SCROLL_LINES),
+            _ => {}
+        }
+    }
+
+    /// 指定された位置にあるペインのidを返します。
+    fn pane_at(&self, position: Position) -> Option<usize> {
+        self.windows
+            .panes
+            .iter()
+            .find(|pane| pane.area.contains(position))
+            .map(|pane| pane.id)
+    }
+
+    /// 指定されたidのペインの各部分の領域を、最後に描画した領域から求めます。
+    fn pane_regions(&self, id: usize) -> PaneRegions {
+        let split = self.windows.panes.len() > 1;
+        PaneRegions::new(
+            self.windows.pane(id).area,
+            split,
+            self.settings.gutter_width,
+        )
+    }
+
+    /// エディタ本体の画面上の位置にある文字の位置 `(x, y)` を返します。
+    /// 描画領域の上下にはみ出した位置では、1行スクロールして端の行の位置を返します。（ドラッグで使用）
+    fn position_in_text(&mut self, text: Rect, position: Position) -> (usize, usize) {
+        let scroll_padding_y = self.language_settings().scroll_padding_y;
+        let row = if position.y < text.top() {
+            self.editor_mut()
+                .scroll_by(-1, text.height, scroll_padding_y);
+            0
+        } else if position.y >= text.bottom() {
+            self.editor_mut()
+                .scroll_by(1, text.height, scroll_padding_y);
+            text.height.saturating_sub(1)
+        } else {
+            position.y - text.y
+        };
+        let column = position.x.saturating_sub(text.x);
+        let wrap = self.word_wrap_enabled;
+        let rows = self.editor().screen_rows(text.width, text.height, wrap);
+        self.editor().position_at(&rows, row, column, wrap)
+    }
+
+    /// エディタ本体のクリックを処理します。
+    /// 1回目でカーソルを移動（Shiftを押している場合は選択範囲を広げる）し、2回目で単語、3回目で行を選択します。
+    fn click_text(&mut self, text: Rect, position: Position, extend: bool) {
+        let (x, y) = self.position_in_text(text, position);
+        match self.mouse.register_click(position.x, position.y) {
+            2 => self.editor_mut().select_word_at(x, y),
+            3 => self.editor_mut().select_lines(y, y),
+            _ => self.editor_mut().set_cursor_position(x, y, extend),
+        }
+        self.mouse.drag = Some(Drag::Text);
+    }
+
+    /// 行番号の欄のクリックを処理します。クリックした行を（Shiftを押している場合はカーソルの行から）選択します。
+    fn click_gutter(&mut self, text: Rect, position: Position, extend: bool) {
+        let (_, y) = self.position_in_text(text, position);
+        let anchor_line = match self.editor().cursor.selection_start {
+            Some((_, start_y)) if extend => start_y,
+            _ if extend => self.editor().cursor.y,
+            _ => y,
+        };
+        self.editor_mut().select_lines(anchor_line, y);
+        self.mouse.drag = Some(Drag::Gutter { anchor_line });
+    }
+
+    /// スクロールバーのクリックを処理します。
+    /// つまみをクリックした場合はそのままドラッグでき、レールをクリックした場合はつまみの中央がその位置に来るようにスクロールします。
+    fn click_scrollbar(&mut self, scrollbar: Rect, position: Position) {
+        let (thumb_start, thumb_height) = self.scrollbar_thumb(scrollbar.height);
+        let row = position.y - scrollbar.y;
+        let grab_offset = if (thumb_start..thumb_start + thumb_height).contains(&row) {
+            row - thumb_start
+        } else {
+            let grab_offset = thumb_height / 2;
+            self.drag_scrollbar(scrollbar, position.y, grab_offset);
+            grab_offset
+        };
+        self.mouse.drag = Some(Drag::Scrollbar { grab_offset });
+    }
+
+    /// つまみの上端が`y`から`grab_offset`行上になるようにスクロールします。
+    fn drag_scrollbar(&mut self, scrollbar: Rect, y: u16, grab_offset: u16) {
+        let total_lines = self.editor().buffer.len_lines() as i64;
+        let thumb_top = y.saturating_sub(scrollbar.y).saturating_sub(grab_offset) as i64;
+        // render_right_block のつまみの位置の計算の逆（つまみの位置 = スクロール位置 * 高さ / 行数）
+        let target = thumb_top * total_lines / scrollbar.height.max(1) as i64;
+        let delta = (target - self.editor().scroll_offset_y as i64) as i32;
+        let scroll_padding_y = self.language_settings().scroll_padding_y;
+        self.editor_mut()
+            .scroll_by(delta, scrollbar.height, scroll_padding_y);
+    }
+
+    /// スクロールバーのつまみの上端の位置と高さを返します。（render_right_block と同じ計算）
+    fn scrollbar_thumb(&self, viewport_height: u16) -> (u16, u16) {
+        let total_lines = self.editor().buffer.len_lines() as f32;
+        let thumb_height =
+            (((viewport_height as f32 / total_lines) * viewport_height as f32) as u16).max(1);
+        let thumb_start =
+            ((self.editor().scroll_offset_y as f32 / total_lines) * viewport_height as f32) as u16;
+        (
+            thumb_start.min(viewport_height.saturating_sub(thumb_height)),
+            thumb_height,
+        )
+    }
+
+    /// 指定された位置にあるペインを`delta`行スクロールします。フォーカスは移しません。
+    fn scroll_pane_at(&mut self, position: Position, delta: i32) {
+        let Some(id) = self.pane_at(position) else {
+            return;
+        };
+        let height = self.pane_regions(id).text.height;
+        if id == self.windows.focused {
+            let scroll_padding_y = self.language_settings().scroll_padding_y;
+            self.editor_mut().scroll_by(delta, height, scroll_padding_y);
+            return;
+        }
+
+        // フォーカスのないペインは、描画時と同じように表示位置を一時的にエディタに設定してスクロールする
+        let pane = self.windows.pane(id).clone();
+        let active_document = self.active_document;
+        self.active_document = pane.document;
+        let focused_view = View::capture(self.editor());
+        pane.view.restore(self.editor_mut());
+
+        let scroll_padding_y = self.language_settings().scroll_padding_y;
+        self.editor_mut().scroll_by(delta, height, scroll_padding_y);
+
+        self.windows.pane_mut(id).view = View::capture(self.editor());
+        focused_view.restore(self.editor_mut());
+        self.active_document = active_document;
+    }
+}
@@ -0,0 +1,99 @@
+// src/app/palette.rs
+
+use super::App;
+use super::actions::{ACTIONS, Action};
+use super::fuzzy::{FuzzyMatch, fuzzy_match};
+use super::input::InputLine;
+use crate::msg;
+
+/// コマンドパレットの候補1件です。
+pub struct PaletteEntry {
+    pub action: &'static Action,
+    pub matched: FuzzyMatch, // クエリにマッチした結果
+    pub matched_name: bool,  // 説明ではなく名前にマッチしたか（強調表示する側）
+}
+
+/// 名前で操作を検索して実行するポップアップ（コマンドパレット）の状態です。
+pub struct CommandPalette {
+    pub input: InputLine,           // 検索クエリの入力欄
+    pub entries: Vec<PaletteEntry>, // クエリにマッチした操作（得点順）
+    pub selected: usize,            // 選択している候補
+    pub scroll_offset: usize,       // 表示している先頭の候補
+}
+
+impl CommandPalette {
+    /// 入力中のクエリで操作を検索し直します。説明と名前の両方を検索し、得点の高い方を使います。
+    pub fn update_entries(&mut self) {
+        let query = self.input.text.as_str();
+        self.entries = ACTIONS
+            .iter()
+            .filter_map(|action| {
+                let by_description = fuzzy_match(query, action.description);
+                let by_name = fuzzy_match(query, action.name);
+                match (by_description, by_name) {
+                    (Some(d), Some(n)) if n.score > d.score => Some((n, true)),
+                    (Some(d), _) => Some((d, false)),
+                    (None, Some(n)) => Some((n, true)),
+                    (None, None) => None,
+                }
+                .map(|(matched, matched_name)| PaletteEntry {
+                    action,
+                    matched,
+                    matched_name,
+                })
+            })
+            .collect();
+        // 同点なら登録順のまま並べる
+        self.entries
+            .sort_by_key(|entry| std::cmp::Reverse(entry.matched.score));
+        self.selected = 0;
+        self.scroll_offset = 0;
+    }
+
+    /// 選択を`delta`件だけ移動します。
+    pub fn move_selection(&mut self, delta: isize) {
+        let last = self.entries.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    /// 選択している候補が表示されるように、スクロール位置を調整します。
+    pub fn adjust_scroll(&mut self, height: usize) {
+        if self.selected < self.scroll_offset {
+            self.scroll_offset = self.selected;
+        } else if height > 0 && self.selected >= self.scroll_offset + height {
+            self.scroll_offset = self.selected + 1 - height;
+        }
+    }
+}
+
+impl App {
+    /// コマンドパレットを開きます。
+    pub fn open_palette(&mut self) {
+        let mut palette = CommandPalette {
+            input: InputLine::default(),
+            entries: Vec::new(),
+            selected: 0,
+            scroll_offset: 0,
+        };
+        palette.update_entries();
+        self.palette = Some(palette);
+    }
+
+    /// コマンドパレットの選択を移動します。
+    pub fn palette_move_selection(&mut self, delta: isize) {
+        if let Some(palette) = &mut self.palette {
+            palette.move_selection(delta);
+        }
+    }
+
+    /// 選択している操作を実行し、コマンドパレットを閉じます。
+    pub fn confirm_palette(&mut self) {
+        let Some(palette) = self.palette.take() else {
+            return;
+        };
+        match palette.entries.get(palette.selected) {
+            Some(entry) => (entry.action.run)(self),
+            None => msg!(self, "一致するコマンドがありません。"),
+        }
+    }
+}
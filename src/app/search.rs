@@ -0,0 +1,347 @@
+// src/app/search.rs
+
+use super::App;
+use super::cursor::Cursor;
+use super::input::InputLine;
+use crate::msg;
+use regex::{Regex, RegexBuilder};
+
+/// 検索結果1件の位置を表します。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchMatch {
+    pub y: usize,     // マッチ開始位置の行 (0-indexed)
+    pub x: usize,     // マッチ開始位置の列 (0-indexed, 文字単位)
+    pub start: usize, // マッチ開始位置のバイトオフセット
+    pub end: usize,   // マッチ終了位置のバイトオフセット
+}
+
+/// 検索の動作を切り替えるオプションです。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub match_case: bool,   // 大文字と小文字を区別する
+    pub whole_word: bool,   // 単語全体に一致する場合のみマッチとする
+    pub regex: bool,        // クエリを正規表現として扱う
+    pub in_selection: bool, // 検索バーを開いた時点の選択範囲内だけを検索する
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            match_case: true,
+            whole_word: false,
+            regex: false,
+            in_selection: false,
+        }
+    }
+}
+
+impl SearchOptions {
+    /// オプションに従って、クエリから検索用の正規表現を組み立てます。
+    /// 正規表現モードでない場合、クエリはエスケープされ文字列としてそのまま検索されます。
+    /// `^`と`$`は各行の先頭・末尾にマッチし、`\n`を含むパターンは行をまたいでマッチします。
+    pub fn build_regex(&self, query: &str) -> Result<Regex, regex::Error> {
+        let mut pattern = if self.regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+        if self.whole_word {
+            let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+            if self.regex {
+                pattern = format!(r"\b(?:{})\b", pattern);
+            } else {
+                // 文字列として検索する場合、単語の文字で始まる（終わる）側にだけ単語境界を置く
+                // （`\b`を常に置くと、記号で始まるクエリが一切マッチしなくなるため）
+                if query.starts_with(is_word_char) {
+                    pattern = format!(r"\b{}", pattern);
+                }
+                if query.ends_with(is_word_char) {
+                    pattern = format!(r"{}\b", pattern);
+                }
+            }
+        }
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.match_case)
+            .multi_line(true)
+            .build()
+    }
+}
+
+/// 検索バーで入力中の欄です。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchField {
+    Query,       // 検索クエリ
+    Replacement, // 置換後のテキスト
+}
+
+/// 検索バー（インクリメンタル検索・置換）の状態を管理します。
+#[derive(Debug, Clone)]
+pub struct SearchPrompt {
+    pub input: InputLine,               // 検索クエリの入力欄
+    pub replacement: Option<InputLine>, // 置換テキストの入力欄（置換モードの場合のみSome）
+    pub focus: SearchField,             // 入力中の欄
+    pub origin_cursor: Cursor,          // 検索バーを開いた時点のカーソル（検索の起点）
+    pub error: Option<String>,          // 正規表現の構文エラーなど、検索できなかった理由
+}
+
+impl SearchPrompt {
+    /// 入力中の欄を返します。
+    pub fn focused_input_mut(&mut self) -> &mut InputLine {
+        match (self.focus, &mut self.replacement) {
+            (SearchField::Replacement, Some(replacement)) => replacement,
+            _ => &mut self.input,
+        }
+    }
+}
+
+impl App {
+    /// 検索バーを開きます。
+    /// 1行以内の選択範囲があればそれを、なければ前回の検索クエリを初期値にします。
+    /// 複数行にわたる選択範囲がある場合は、選択範囲内検索を有効にします。
+    pub fn open_search(&mut self) {
+        if let Some(prompt) = &mut self.search {
+            // 既に開いている場合は検索クエリの欄に移動するだけ
+            prompt.focus = SearchField::Query;
+            return;
+        }
+        let selected = self.editor().copy_selection();
+        let multi_line_selection = selected.as_ref().is_some_and(|text| text.contains('\n'));
+        let initial_query = match selected {
+            Some(selected) if !multi_line_selection => selected,
+            _ => self.editor().search_query.clone(),
+        };
+        // 選択範囲を選択範囲内検索の対象として記憶し、その先頭から検索を始める
+        let editor = self.editor_mut();
+        editor.search_scope = editor.get_selection_range();
+        editor.search_options.in_selection = multi_line_selection;
+        if let Some((start, _)) = editor.search_scope {
+            let (y, x) = editor.buffer.byte_to_pos(start);
+            editor.set_cursor_position(x, y, false);
+        }
+        self.search = Some(SearchPrompt {
+            input: InputLine::new(&initial_query),
+            replacement: None,
+            focus: SearchField::Query,
+            origin_cursor: self.editor().cursor,
+            error: None,
+        });
+        self.update_search();
+    }
+
+    /// 置換モードで検索バーを開きます。既に検索バーが開いている場合は置換テキストの欄を追加します。
+    pub fn open_replace(&mut self) {
+        if self.search.is_none() {
+            self.open_search();
+        }
+        if let Some(prompt) = &mut self.search {
+            prompt.replacement.get_or_insert_with(InputLine::default);
+            prompt.focus = SearchField::Replacement;
+        }
+    }
+
+    /// 検索バーの入力内容で検索をやり直します。（入力が変わるたびに呼び出します）
+    /// 検索は常に検索バーを開いた位置から行い、マッチがなければカーソルを元の位置に戻します。
+    pub fn update_search(&mut self) {
+        let Some(prompt) = &self.search else {
+            return;
+        };
+        let query = prompt.input.text.clone();
+        self.editor_mut().cursor = prompt.origin_cursor;
+        let result = self.editor_mut().search(&query);
+        if let Some(prompt) = &mut self.search {
+            prompt.error = result.err().map(|e| e.to_string());
+        }
+    }
+
+    /// 検索バーを閉じ、検索結果のハイライトを消します。カーソルは現在のマッチ位置に残ります。
+    pub fn close_search(&mut self) {
+        self.search = None;
+        self.editor_mut().clear_search();
+    }
+
+    /// 大文字と小文字の区別を切り替え、検索し直します。
+    pub fn toggle_search_match_case(&mut self) {
+        let options = &mut self.editor_mut().search_options;
+        options.match_case = !options.match_case;
+        self.update_search();
+    }
+
+    /// 単語単位の検索を切り替え、検索し直します。
+    pub fn toggle_search_whole_word(&mut self) {
+        let options = &mut self.editor_mut().search_options;
+        options.whole_word = !options.whole_word;
+        self.update_search();
+    }
+
+    /// 正規表現モードを切り替え、検索し直します。
+    pub fn toggle_search_regex(&mut self) {
+        let options = &mut self.editor_mut().search_options;
+        options.regex = !options.regex;
+        self.update_search();
+    }
+
+    /// 選択範囲内検索を切り替え、検索し直します。
+    /// 検索バーを開いた時点で選択範囲がなかった場合は有効にできません。
+    pub fn toggle_search_in_selection(&mut self) {
+        if self.editor().search_scope.is_none() {
+            msg!(self, "選択範囲がありません。");
+            return;
+        }
+        let options = &mut self.editor_mut().search_options;
+        options.in_selection = !options.in_selection;
+        self.update_search();
+    }
+
+    /// 現在の検索結果を置換テキストで置き換え、次の検索結果に移動します。
+    pub fn replace_current(&mut self) {
+        let Some(replacement) = self.replacement_text() else {
+            return;
+        };
+        if !self.editor_mut().replace_current_match(&replacement) {
+            msg!(self, "置換する検索結果がありません。");
+        }
+    }
+
+    /// 全ての検索結果を置換テキストで置き換えます。1回のUndoで元に戻せます。
+    pub fn replace_all(&mut self) {
+        let Some(replacement) = self.replacement_text() else {
+            return;
+        };
+        let count = self.editor_mut().replace_all_matches(&replacement);
+        if count > 0 {
+            msg!(self, "{}件を置換しました。", count);
+        } else {
+            msg!(self, "置換する検索結果がありません。");
+        }
+    }
+
+    /// 置換モードの場合、置換テキストの欄の内容を返します。
+    fn replacement_text(&self) -> Option<String> {
+        self.search
+            .as_ref()
+            .and_then(|prompt| prompt.replacement.as_ref())
+            .map(|replacement| replacement.text.clone())
+    }
+
+    /// 次の検索結果に移動します。
+    /// 検索バーが閉じている場合は、前回のクエリでカーソル位置から検索します。
+    pub fn search_next(&mut self) {
+        if self.search.is_some() {
+            self.editor_mut().next_search_match();
+        } else {
+            self.search_from_cursor(true);
+        }
+    }
+
+    /// 前の検索結果に移動します。
+    /// 検索バーが閉じている場合は、前回のクエリでカーソル位置から検索します。
+    pub fn search_previous(&mut self) {
+        if self.search.is_some() {
+            self.editor_mut().previous_search_match();
+        } else {
+            self.search_from_cursor(false);
+        }
+    }
+
+    /// 検索バーを開かずに、前回のクエリでカーソル位置から前後のマッチに移動します。
+    /// バッファを編集するとマッチ位置が古くなるため、移動後は検索結果をクリアします。
+    fn search_from_cursor(&mut self, forward: bool) {
+        if self.editor().search_query.is_empty() {
+            return;
+        }
+        let origin = self.editor().cursor;
+        let query = self.editor().search_query.clone();
+        if self.editor_mut().search(&query).is_err() {
+            return;
+        }
+        self.editor_mut().cursor = origin;
+        if forward {
+            self.editor_mut().next_search_match();
+        } else {
+            self.editor_mut().previous_search_match();
+        }
+        self.editor_mut().clear_search();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::editor::Editor;
+
+    fn options(match_case: bool, whole_word: bool, regex: bool) -> SearchOptions {
+        SearchOptions {
+            match_case,
+            whole_word,
+            regex,
+            in_selection: false,
+        }
+    }
+
+    fn find_all(options: SearchOptions, query: &str, text: &str) -> Vec<String> {
+        let regex = options.build_regex(query).unwrap();
+        regex
+            .find_iter(text)
+            .map(|found| found.as_str().to_string())
+            .collect()
+    }
+
+    /// 大文字と小文字の区別・単語単位・正規表現の組み合わせごとに、マッチする文字列を確かめます。
+    #[test]
+    fn build_regex_combines_options() {
+        let text = "Foo foo food (foo) a.b axb";
+        assert_eq!(find_all(options(true, false, false), "foo", text).len(), 3);
+        assert_eq!(find_all(options(false, false, false), "foo", text).len(), 4);
+        assert_eq!(find_all(options(true, true, false), "foo", text).len(), 2);
+        assert_eq!(find_all(options(false, true, false), "FOO", text).len(), 3);
+        // 文字列として検索する場合、記号は正規表現として解釈されない
+        assert_eq!(find_all(options(true, false, false), "a.b", text), ["a.b"]);
+        assert_eq!(
+            find_all(options(true, false, true), "a.b", text),
+            ["a.b", "axb"]
+        );
+        // 記号で始まる・終わるクエリも単語単位で検索できる
+        assert_eq!(
+            find_all(options(true, true, false), "(foo)", text),
+            ["(foo)"]
+        );
+        assert_eq!(
+            find_all(options(false, true, true), "fo+d?", text),
+            ["Foo", "foo", "food", "foo"]
+        );
+        assert!(options(true, false, true).build_regex("(").is_err());
+    }
+
+    /// 正規表現モードの`^`と`$`は各行に、`\n`を含むパターンは行をまたいでマッチすることを確かめます。
+    #[test]
+    fn build_regex_matches_lines_and_across_lines() {
+        let text = "one\ntwo\n";
+        assert_eq!(find_all(options(true, false, true), "^t.*$", text), ["two"]);
+        assert_eq!(
+            find_all(options(true, false, true), r"e\nt", text),
+            ["e\nt"]
+        );
+    }
+
+    /// 選択範囲内検索では、範囲内のマッチだけが検索結果になることを確かめます。
+    #[test]
+    fn in_selection_filters_matches() {
+        let mut editor = Editor::new("ab ab\nab ab\nab\n".to_string());
+        editor.set_cursor_position(2, 0, false);
+        editor.set_cursor_position(4, 1, true);
+        editor.search_scope = editor.get_selection_range();
+        editor.search_options.in_selection = true;
+        editor.search("ab").unwrap();
+        let positions: Vec<(usize, usize)> = editor
+            .search_matches
+            .iter()
+            .map(|found| (found.y, found.x))
+            .collect();
+        // 範囲の終端をまたぐマッチ（2行目の2つ目）は含まない
+        assert_eq!(positions, [(0, 3), (1, 0)]);
+
+        editor.search_options.in_selection = false;
+        editor.search("ab").unwrap();
+        assert_eq!(editor.search_matches.len(), 5);
+    }
+}
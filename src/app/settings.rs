@@ -0,0 +1,522 @@
+// src/app/settings.rs
+
+use super::App;
+use super::clipboard::{CLIPBOARD_PROVIDERS, Clipboard};
+use super::color::ColorDepth;
+use super::config::config_dir;
+use super::language::LanguageRegistry;
+use crate::{emsg, msg};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// 全体の設定ファイルの名前（設定ディレクトリに置く）
+const SETTINGS_FILE_NAME: &str = "settings.toml";
+
+/// プロジェクトごとの設定ファイルの名前（プロジェクトのルートに置く）
+const PROJECT_SETTINGS_FILE_NAME: &str = ".inedit.toml";
+
+/// 言語ごとの設定を書く表の名前（`[language.rust]`のように書く）
+const LANGUAGE_TABLE: &str = "language";
+
+/// 設定ファイルがない場合に作成する、書き方の例を入れた内容
+const SETTINGS_TEMPLATE: &str = r##"# inedit の設定
+# 全体の設定は設定ディレクトリの settings.toml に、プロジェクトごとの設定はプロジェクトのルートの .inedit.toml に書きます。
+# プロジェクトの設定は全体の設定より優先されます。
+# [language.<言語名>] の設定はその言語のファイルにだけ適用され、言語を指定しない設定より優先されます。
+
+# word_wrap = true           # 折り返し表示を有効にして開始する
+# tab_size = 4               # Tab キーで挿入するスペースの数（1〜16、言語ごとに指定可。指定がなければ言語ごとの既定の幅）
+# message_lifetime_secs = 3  # メッセージの表示期間（秒、1〜60）
+# gutter_width = 7           # 行番号の列の幅（4〜12）
+# scroll_padding_x = 5       # カーソルと左右の端の間に空ける桁数（0〜20、言語ごとに指定可）
+# scroll_padding_y = 3       # カーソルと上下の端の間に空ける行数（0〜20、言語ごとに指定可）
+# theme = "dark"             # テーマ（dark / light / high-contrast、または設定ディレクトリの themes/<名前>.toml）
+# color_mode = "auto"        # 使う色の数（auto / truecolor / 256 / 16 / monochrome、auto は COLORTERM と TERM から判定）
+# clipboard = "auto"         # クリップボード（auto / internal / osc52 / wl-copy / xclip / xsel / pbcopy、auto は SSH 越しなら osc52）
+
+# テーマファイルには、組み込みのテーマに重ねる色を書きます。
+#   extends = "dark"
+#   [colors]
+#   selection_background = "#3a3d6e"
+#   [syntax]
+#   keyword = "magenta"
+
+# [language.javascript]
+# tab_size = 2
+"##;
+
+/// エディタの設定です。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    pub word_wrap: bool,                 // 折り返し表示を有効にして開始するか
+    pub tab_size: usize,                 // Tabキーで挿入するスペースの数
+    pub message_lifetime_secs: u64,      // メッセージの表示期間（秒）
+    pub gutter_width: u16,               // 行番号と差分の列（Left Block）の幅
+    pub scroll_padding_x: u16,           // カーソルと左右の端の間に空ける桁数
+    pub scroll_padding_y: u16,           // カーソルと上下の端の間に空ける行数
+    pub theme: String,                   // テーマの名前
+    pub color_depth: Option<ColorDepth>, // 使う色の数（Noneは端末から判定する）
+    pub clipboard: String,               // クリップボードの読み書きの方法（autoは環境から選ぶ）
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            word_wrap: true,
+            tab_size: 4,
+            message_lifetime_secs: 3,
+            gutter_width: 7,
+            scroll_padding_x: 5,
+            scroll_padding_y: 3,
+            theme: "dark".to_string(),
+            color_depth: None,
+            clipboard: "auto".to_string(),
+        }
+    }
+}
+
+/// 設定項目の定義です。
+struct SettingDef {
+    key: &'static str,                                      // 設定ファイルでの名前
+    per_language: bool,                                     // 言語ごとに指定できるか
+    apply: fn(&mut Settings, &Value) -> Result<(), String>, // 値を検証して反映する
+}
+
+/// 設定できる全ての項目です。
+static SETTING_DEFS: &[SettingDef] = &[
+    SettingDef {
+        key: "word_wrap",
+        per_language: false,
+        apply: |settings, value| {
+            settings.word_wrap = bool_value(value)?;
+            Ok(())
+        },
+    },
+    SettingDef {
+        key: "tab_size",
+        per_language: true,
+        apply: |settings, value| {
+            settings.tab_size = int_value(value, 1..=16)? as usize;
+            Ok(())
+        },
+    },
+    SettingDef {
+        key: "message_lifetime_secs",
+        per_language: false,
+        apply: |settings, value| {
+            settings.message_lifetime_secs = int_value(value, 1..=60)? as u64;
+            Ok(())
+        },
+    },
+    SettingDef {
+        key: "gutter_width",
+        per_language: false,
+        apply: |settings, value| {
+            settings.gutter_width = int_value(value, 4..=12)? as u16;
+            Ok(())
+        },
+    },
+    SettingDef {
+        key: "scroll_padding_x",
+        per_language: true,
+        apply: |settings, value| {
+            settings.scroll_padding_x = int_value(value, 0..=20)? as u16;
+            Ok(())
+        },
+    },
+    SettingDef {
+        key: "scroll_padding_y",
+        per_language: true,
+        apply: |settings, value| {
+            settings.scroll_padding_y = int_value(value, 0..=20)? as u16;
+            Ok(())
+        },
+    },
+    SettingDef {
+        key: "theme",
+        per_language: false,
+        apply: |settings, value| {
+            settings.theme = value
+                .as_str()
+                .ok_or_else(|| "テーマの名前を文字列で指定してください".to_string())?
+                .to_string();
+            Ok(())
+        },
+    },
+    SettingDef {
+        key: "color_mode",
+        per_language: false,
+        apply: |settings, value| {
+            settings.color_depth = match value.as_str() {
+                Some("auto") => None,
+                Some(mode) => Some(mode.parse()?),
+                None => return Err("文字列で指定してください".to_string()),
+            };
+            Ok(())
+        },
+    },
+    SettingDef {
+        key: "clipboard",
+        per_language: false,
+        apply: |settings, value| {
+            settings.clipboard = value
+                .as_str()
+                .filter(|name| CLIPBOARD_PROVIDERS.contains(name))
+                .ok_or_else(|| {
+                    format!(
+                        "{} のいずれかを指定してください",
+                        CLIPBOARD_PROVIDERS.join(" / ")
+                    )
+                })?
+                .to_string();
+            Ok(())
+        },
+    },
+];
+
+/// 真偽値の設定値を取り出します。
+fn bool_value(value: &Value) -> Result<bool, String> {
+    value
+        .as_bool()
+        .ok_or_else(|| "true か false で指定してください".to_string())
+}
+
+/// 範囲内の整数の設定値を取り出します。
+fn int_value(value: &Value, range: RangeInclusive<i64>) -> Result<i64, String> {
+    value
+        .as_integer()
+        .filter(|n| range.contains(n))
+        .ok_or_else(|| {
+            format!(
+                "{} から {} までの整数で指定してください",
+                range.start(),
+                range.end()
+            )
+        })
+}
+
+/// 読み込んだ1つの設定ファイルの内容です。値は検証済みで、誤りのある項目は含みません。
+#[derive(Debug, Clone)]
+pub struct SettingsFile {
+    pub path: PathBuf,
+    base: Table,                        // 言語を指定しない設定
+    languages: BTreeMap<String, Table>, // 言語ごとの設定（言語名 → 設定）
+}
+
+impl SettingsFile {
+    /// 設定ファイルの内容を解釈し、各項目を検証します。
+    /// ファイル全体を解釈できない場合はエラーを返します。
+    /// 誤りのある項目はその項目だけを除き、内容を一覧で返します。
+    pub fn parse(
+        path: &Path,
+        text: &str,
+        languages: &LanguageRegistry,
+    ) -> Result<(Self, Vec<String>), String> {
+        let mut table: Table = toml::from_str(text).map_err(|e| e.message().to_string())?;
+        let mut errors = Vec::new();
+
+        let mut language_tables = BTreeMap::new();
+        match table.remove(LANGUAGE_TABLE) {
+            Some(Value::Table(sections)) => {
+                for (name, section) in sections {
+                    if languages.by_name(&name).is_none() {
+                        errors.push(format!("不明な言語 \"{}\" です", name));
+                        continue;
+                    }
+                    let Value::Table(section) = section else {
+                        errors.push(format!(
+                            "[{}.{}] は表で指定してください",
+                            LANGUAGE_TABLE, name
+                        ));
+                        continue;
+                    };
+                    let prefix = format!("{}.{}.", LANGUAGE_TABLE, name);
+                    language_tables.insert(name, validate(section, true, &prefix, &mut errors));
+                }
+            }
+            Some(_) => errors.push(format!("{} は表で指定してください", LANGUAGE_TABLE)),
+            None => {}
+        }
+        let base = validate(table, false, "", &mut errors);
+
+        Ok((
+            Self {
+                path: path.to_path_buf(),
+                base,
+                languages: language_tables,
+            },
+            errors,
+        ))
+    }
+}
+
+/// 設定の表の各項目を検証し、正しい項目だけを残した表を返します。
+/// `prefix`はエラーの内容に表示する、項目名の前に付ける表の名前です。
+fn validate(table: Table, per_language: bool, prefix: &str, errors: &mut Vec<String>) -> Table {
+    let mut valid = Table::new();
+    for (key, value) in table {
+        let Some(def) = SETTING_DEFS.iter().find(|def| def.key == key) else {
+            errors.push(format!("不明な設定 \"{}{}\" です", prefix, key));
+            continue;
+        };
+        if per_language && !def.per_language {
+            errors.push(format!("{}{} は言語ごとには設定できません", prefix, key));
+            continue;
+        }
+        match (def.apply)(&mut Settings::default(), &value) {
+            Ok(()) => {
+                valid.insert(key, value);
+            }
+            Err(e) => errors.push(format!("{}{}: {}", prefix, key, e)),
+        }
+    }
+    valid
+}
+
+/// 設定ファイルの内容を既定の設定に重ねて、最終的な設定を求めます。
+/// `files`は優先度の低い順（全体の設定、プロジェクトの設定の順）に並べます。
+/// 言語ごとの設定は、言語を指定しない設定よりも優先します。
+pub fn resolve_settings(files: &[SettingsFile], language: Option<&str>) -> Settings {
+    let mut settings = Settings::default();
+    let language_tables = files
+        .iter()
+        .filter_map(|file| language.and_then(|name| file.languages.get(name)));
+    for table in files.iter().map(|file| &file.base).chain(language_tables) {
+        for (key, value) in table {
+            if let Some(def) = SETTING_DEFS.iter().find(|def| def.key == key) {
+                // 読み込み時に検証済みなので失敗しない
+                let _ = (def.apply)(&mut settings, value);
+            }
+        }
+    }
+    settings
+}
+
+/// 設定ファイルのいずれかで`key`の項目が指定されているかどうかを返します。（`language`の言語ごとの設定も含みます）
+/// 指定がなければ言語の既定値を使う項目（インデントなど）の判定に使います。
+pub fn is_specified(files: &[SettingsFile], language: Option<&str>, key: &str) -> bool {
+    files.iter().any(|file| {
+        file.base.contains_key(key)
+            || language
+                .and_then(|name| file.languages.get(name))
+                .is_some_and(|table| table.contains_key(key))
+    })
+}
+
+/// 全体の設定ファイルのパスを返します。
+pub fn settings_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(SETTINGS_FILE_NAME))
+}
+
+impl App {
+    /// 全体の設定ファイルとプロジェクトの設定ファイルを読み込み、設定を反映します。
+    /// 設定ファイルがなければ既定の設定を使います。
+    pub fn load_settings(&mut self) {
+        let project_path = self.explorer.root.join(PROJECT_SETTINGS_FILE_NAME);
+        let mut files = Vec::new();
+        for path in settings_path().into_iter().chain([project_path]) {
+            let text = match fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => {
+                    emsg!(
+                        self,
+                        "設定 {:?} の読み込み中にエラーが発生しました: {}",
+                        path,
+                        e
+                    );
+                    continue;
+                }
+            };
+            match SettingsFile::parse(&path, &text, &self.languages) {
+                Ok((file, errors)) => {
+                    for error in errors {
+                        emsg!(self, "設定 {:?} の誤り: {}", path, error);
+                    }
+                    msg!(self, "設定を {:?} から読み込みました。", path);
+                    files.push(file);
+                }
+                Err(e) => emsg!(
+                    self,
+                    "設定 {:?} を解釈できません: {}。このファイルの設定は使いません。",
+                    path,
+                    e
+                ),
+            }
+        }
+        self.settings_files = files;
+        self.settings = resolve_settings(&self.settings_files, None);
+        self.word_wrap_enabled = self.settings.word_wrap;
+        self.color_depth = self.settings.color_depth.unwrap_or_else(ColorDepth::detect);
+        let theme = self.settings.theme.clone();
+        self.set_theme(&theme); // 読み込めなければ今のテーマのまま
+        self.theme.monochrome = self.color_depth == ColorDepth::Monochrome;
+        self.clipboard
+            .set_backend(Clipboard::backend_for(&self.settings.clipboard));
+        // タブの表示幅は設定と .editorconfig から決まるので、開いている全てのファイルについて読み直す
+        for index in 0..self.documents.len() {
+            self.load_editorconfig(index);
+        }
+    }
+
+    /// 編集中のファイルの言語に対する設定を返します。
+    pub fn language_settings(&self) -> Settings {
+        let language = self.editor().language.as_ref().map(|l| l.name);
+        resolve_settings(&self.settings_files, language)
+    }
+
+    /// 全体の設定ファイルを開きます。ファイルがなければ、書き方の例を入れて作成します。
+    pub fn open_settings(&mut self) {
+        match settings_path() {
+            Some(path) => self.open_settings_file(path),
+            None => emsg!(
+                self,
+                "設定ディレクトリが分かりません。HOME または XDG_CONFIG_HOME を設定してください。"
+            ),
+        }
+    }
+
+    /// プロジェクトの設定ファイルを開きます。ファイルがなければ、書き方の例を入れて作成します。
+    pub fn open_project_settings(&mut self) {
+        let path = self.explorer.root.join(PROJECT_SETTINGS_FILE_NAME);
+        self.open_settings_file(path);
+    }
+
+    /// 設定ファイルを開きます。ファイルがなければ、書き方の例を入れて作成します。
+    fn open_settings_file(&mut self, path: PathBuf) {
+        if !path.exists() {
+            let created = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(&path, SETTINGS_TEMPLATE));
+            if let Err(e) = created {
+                emsg!(self, "設定 {:?} を作成できませんでした: {}", path, e);
+                return;
+            }
+        }
+        self.open_file(&path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> (SettingsFile, Vec<String>) {
+        SettingsFile::parse(
+            Path::new("settings.toml"),
+            text,
+            &LanguageRegistry::default(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let (file, errors) = parse(
+            r#"
+            tab_size = 0
+            gutter_width = 20
+            word_wrap = "yes"
+            color_mode = "millions"
+            clipboard = "nope"
+            unknown = 1
+            message_lifetime_secs = 10
+
+            [language.rust]
+            tab_size = 2
+            theme = "light"
+
+            [language.klingon]
+            tab_size = 3
+            "#,
+        );
+        for expected in [
+            "tab_size: 1 から 16 までの整数で指定してください",
+            "gutter_width: 4 から 12 までの整数で指定してください",
+            "word_wrap: true か false で指定してください",
+            "color_mode: auto / truecolor / 256 / 16 / monochrome のいずれかを指定してください",
+            "不明な設定 \"unknown\" です",
+            "language.rust.theme は言語ごとには設定できません",
+            "不明な言語 \"klingon\" です",
+        ] {
+            assert!(
+                errors.iter().any(|e| e == expected),
+                "{:?} がありません: {:?}",
+                expected,
+                errors
+            );
+        }
+        assert!(errors.iter().any(|e| e.starts_with("clipboard: ")));
+        assert_eq!(errors.len(), 8);
+
+        // 誤りのある項目だけを除き、正しい項目は反映する
+        let settings = resolve_settings(std::slice::from_ref(&file), Some("rust"));
+        assert_eq!(
+            settings,
+            Settings {
+                message_lifetime_secs: 10,
+                tab_size: 2,
+                ..Settings::default()
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_tables() {
+        assert!(
+            SettingsFile::parse(Path::new("x"), "tab_size = ", &LanguageRegistry::default())
+                .is_err()
+        );
+        let (_, errors) = parse("language = 1");
+        assert_eq!(errors, vec!["language は表で指定してください"]);
+        let (_, errors) = parse("[language]\nrust = 1");
+        assert_eq!(errors, vec!["[language.rust] は表で指定してください"]);
+    }
+
+    #[test]
+    fn language_settings_take_precedence() {
+        let (global, _) = parse(
+            r#"
+            tab_size = 8
+            scroll_padding_x = 2
+            [language.rust]
+            tab_size = 6
+            "#,
+        );
+        let (project, _) = parse(
+            r#"
+            tab_size = 3
+            [language.rust]
+            scroll_padding_y = 1
+            [language.javascript]
+            tab_size = 2
+            "#,
+        );
+        let files = [global, project];
+
+        // プロジェクトの設定は全体の設定より優先する
+        let settings = resolve_settings(&files, None);
+        assert_eq!((settings.tab_size, settings.scroll_padding_x), (3, 2));
+        // 言語ごとの設定は、プロジェクトの言語を指定しない設定よりも優先する
+        let settings = resolve_settings(&files, Some("rust"));
+        assert_eq!(
+            (
+                settings.tab_size,
+                settings.scroll_padding_x,
+                settings.scroll_padding_y
+            ),
+            (6, 2, 1)
+        );
+        let settings = resolve_settings(&files, Some("javascript"));
+        assert_eq!((settings.tab_size, settings.scroll_padding_y), (2, 3));
+
+        assert!(is_specified(&files, Some("rust"), "scroll_padding_y"));
+        assert!(!is_specified(&files, None, "scroll_padding_y"));
+        assert!(!is_specified(&files, Some("python"), "gutter_width"));
+    }
+}
@@ -0,0 +1,163 @@
+// src/app/syntax.rs
+
+use super::text_buffer::TextBuffer;
+use ropey::Rope;
+use std::cmp::Reverse;
+use std::ops::Range;
+use tree_sitter::{
+    InputEdit, Language, Node, Parser, Point, Query, QueryCursor, StreamingIterator, TextProvider,
+    Tree,
+};
+
+/// ハイライトの対象となる範囲1つ分です。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HighlightSpan {
+    pub start: usize,   // 開始位置のバイトオフセット
+    pub end: usize,     // 終了位置のバイトオフセット
+    pub capture: usize, // クエリのキャプチャ番号（`Syntax::capture_name`で名前を取得）
+}
+
+/// tree-sitter による構文木を保持し、バッファの編集に合わせて増分的に再解析します。
+pub struct Syntax {
+    parser: Parser,
+    query: Query,
+    tree: Option<Tree>, // 最後に解析した構文木（編集済みで再解析が必要な場合もある）
+    stale: bool,        // 最後の解析以降にバッファが編集されたかどうか
+}
+
+impl Syntax {
+    /// 文法とハイライト用のクエリから構文解析器を作成します。
+    /// 文法やクエリを読み込めなかった場合はエラーの内容を返します。
+    pub fn new(grammar: &Language, highlights_query: &str) -> Result<Self, String> {
+        let mut parser = Parser::new();
+        parser.set_language(grammar).map_err(|e| e.to_string())?;
+        let query = Query::new(grammar, highlights_query).map_err(|e| e.to_string())?;
+        Ok(Self {
+            parser,
+            query,
+            tree: None,
+            stale: true,
+        })
+    }
+
+    /// キャプチャ番号に対応するキャプチャ名（`keyword`や`function.method`など）を返します。
+    pub fn capture_name(&self, capture: usize) -> &str {
+        self.query.capture_names()[capture]
+    }
+
+    /// バッファ全体が置き換えられた場合に呼び出し、次回は最初から解析し直すようにします。
+    pub fn reset(&mut self) {
+        self.tree = None;
+        self.stale = true;
+    }
+
+    /// バッファの編集内容を構文木に反映します。再解析は次の`update`まで遅延されます。
+    pub fn edit(&mut self, edit: &InputEdit) {
+        if let Some(tree) = &mut self.tree {
+            tree.edit(edit);
+        }
+        self.stale = true;
+    }
+
+    /// 編集後に構文木が古くなっていれば、前回の構文木を再利用して増分的に再解析します。
+    pub fn update(&mut self, buffer: &TextBuffer) {
+        if !self.stale {
+            return;
+        }
+        let rope = buffer.rope();
+        let mut read_chunk = |byte_offset: usize, _: Point| -> &[u8] {
+            if byte_offset >= rope.len_bytes() {
+                return &[];
+            }
+            let (chunk, chunk_start, _, _) = rope.chunk_at_byte(byte_offset);
+            &chunk.as_bytes()[byte_offset - chunk_start..]
+        };
+        self.tree = self
+            .parser
+            .parse_with_options(&mut read_chunk, self.tree.as_ref(), None);
+        self.stale = false;
+    }
+
+    /// 指定されたバイト範囲に含まれるハイライトを、開始位置の順に重なりなく返します。
+    /// 入れ子になったキャプチャは内側のものを優先し、同じノードに複数のキャプチャがある
+    /// 場合はクエリ内で後に書かれたものを優先します。（各文法のクエリはこの規則を前提に、
+    /// `(identifier) @variable`のような汎用的なパターンを先に書いています）
+    pub fn highlights(&self, buffer: &TextBuffer, range: Range<usize>) -> Vec<HighlightSpan> {
+        let Some(tree) = &self.tree else {
+            return Vec::new();
+        };
+        if range.is_empty() {
+            return Vec::new();
+        }
+
+        let mut cursor = QueryCursor::new();
+        cursor.set_byte_range(range.clone());
+        let mut captures = cursor.captures(
+            &self.query,
+            tree.root_node(),
+            RopeTextProvider(buffer.rope()),
+        );
+        let mut captured: Vec<(Range<usize>, usize, usize)> = Vec::new(); // (ノードの範囲, パターン番号, キャプチャ番号)
+        while let Some((query_match, capture_idx)) = captures.next() {
+            let capture = query_match.captures[*capture_idx];
+            captured.push((
+                capture.node.byte_range(),
+                query_match.pattern_index,
+                capture.index as usize,
+            ));
+        }
+        // 外側のノードから内側のノードへ、同じノードではパターンの順に塗り重ねる
+        captured.sort_by_key(|(node_range, pattern, _)| {
+            (node_range.start, Reverse(node_range.end), *pattern)
+        });
+
+        // 範囲内の各バイトに、そこを覆う最も優先度の高いキャプチャを割り当てる
+        let mut painted: Vec<Option<usize>> = vec![None; range.len()];
+        for (node_range, _, capture) in captured {
+            let start = node_range.start.max(range.start) - range.start;
+            let end = node_range.end.min(range.end).saturating_sub(range.start);
+            if start < end {
+                painted[start..end].fill(Some(capture));
+            }
+        }
+
+        // 同じキャプチャが続く範囲をまとめる
+        let mut spans: Vec<HighlightSpan> = Vec::new();
+        for (i, capture) in painted.into_iter().enumerate() {
+            let Some(capture) = capture else {
+                continue;
+            };
+            let offset = range.start + i;
+            match spans.last_mut() {
+                Some(last) if last.end == offset && last.capture == capture => last.end += 1,
+                _ => spans.push(HighlightSpan {
+                    start: offset,
+                    end: offset + 1,
+                    capture,
+                }),
+            }
+        }
+        spans
+    }
+}
+
+/// バイトオフセットを tree-sitter の位置（行とバイト単位の列）に変換します。
+pub fn byte_to_point(buffer: &TextBuffer, byte_offset: usize) -> Point {
+    let row = buffer.byte_to_line(byte_offset);
+    Point::new(
+        row,
+        byte_offset.min(buffer.len_bytes()) - buffer.line_to_byte(row),
+    )
+}
+
+/// クエリの述語（`#match?`など）の評価のために、ノードのテキストをロープから取り出します。
+struct RopeTextProvider<'a>(&'a Rope);
+
+impl<'a> TextProvider<&'a [u8]> for RopeTextProvider<'a> {
+    type I = Box<dyn Iterator<Item = &'a [u8]> + 'a>;
+
+    fn text(&mut self, node: Node) -> Self::I {
+        let slice = self.0.byte_slice(node.byte_range());
+        Box::new(slice.chunks().map(str::as_bytes))
+    }
+}
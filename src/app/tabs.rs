@@ -0,0 +1,259 @@
+// src/app/tabs.rs
+
+use super::App;
+use super::document::Document;
+use super::input::InputLine;
+use crate::{emsg, msg};
+use std::fs;
+use std::path::Path;
+
+/// 保存していない変更を破棄する前に確認している操作です。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseConfirm {
+    Tab,  // 編集中のファイルのタブを閉じる
+    Quit, // アプリケーションを終了する
+}
+
+impl App {
+    /// ファイルを開いて、編集中のファイルにします。既に開いている場合はそのタブに切り替えます。
+    pub fn open_file(&mut self, path: &Path) {
+        if let Some(index) = self.find_document(path) {
+            self.switch_document(index);
+            return;
+        }
+        self.close_document_views();
+        // 起動時に開いた空のバッファが手付かずのままなら、それを置き換える
+        if self.documents.len() == 1 && is_blank(&self.documents[0]) {
+            self.documents.clear();
+        }
+        self.load_document(path.to_path_buf());
+    }
+
+    /// 指定されたパスのファイルを開いていれば、そのインデックスを返します。
+    fn find_document(&self, path: &Path) -> Option<usize> {
+        let canonical = fs::canonicalize(path).ok();
+        self.documents.iter().position(|document| {
+            document.target_path.as_deref().is_some_and(|target| {
+                target == path || canonical.is_some() && fs::canonicalize(target).ok() == canonical
+            })
+        })
+    }
+
+    /// 指定されたインデックスのファイルを編集中のファイルにします。
+    /// 検索バーやプレビューなど、編集中のファイルに結び付いた表示は閉じます。
+    pub fn switch_document(&mut self, index: usize) {
+        if index >= self.documents.len() {
+            return;
+        }
+        if index != self.active_document {
+            self.close_document_views();
+        }
+        self.active_document = index;
+        self.windows.focused_pane_mut().document = index; // フォーカスのあるペインに表示する
+    }
+
+    /// 次のタブに切り替えます。最後のタブからは最初のタブに戻ります。
+    pub fn next_document(&mut self) {
+        self.switch_document((self.active_document + 1) % self.documents.len());
+    }
+
+    /// 前のタブに切り替えます。最初のタブからは最後のタブに戻ります。
+    pub fn previous_document(&mut self) {
+        let count = self.documents.len();
+        self.switch_document((self.active_document + count - 1) % count);
+    }
+
+    /// 編集中のファイルのタブを閉じます。Undo履歴は保存し、一時ファイルは削除します。
+    /// 保存していない変更がある場合は、閉じる前に確認します。
+    /// 最後のタブを閉じた場合は`true`を返します（アプリケーションを終了する）。
+    pub fn close_current_document(&mut self) -> bool {
+        if self.documents.len() == 1 {
+            return true;
+        }
+        if self.document().is_modified() {
+            msg!(
+                self,
+                "{} は保存されていません。y: 保存せずに閉じる / s: 保存して閉じる / Esc: キャンセル",
+                self.document().name()
+            );
+            self.close_confirm = Some(CloseConfirm::Tab);
+            return false;
+        }
+        self.discard_current_document();
+        false
+    }
+
+    /// 確認中の操作を実行します。`save`が`true`なら、先に閉じるファイル（終了する場合は全てのファイル）を保存します。
+    /// 保存できなかった場合は、変更を失わないよう操作を取り消します。
+    pub fn confirm_close(&mut self, save: bool) {
+        let Some(confirm) = self.close_confirm.take() else {
+            return;
+        };
+        match confirm {
+            CloseConfirm::Tab => {
+                if save && !self.save_document(self.active_document) {
+                    return;
+                }
+                self.discard_current_document();
+            }
+            CloseConfirm::Quit => {
+                if save
+                    && !(0..self.documents.len()).all(|index| {
+                        !self.documents[index].is_modified() || self.save_document(index)
+                    })
+                {
+                    return;
+                }
+                self.quit();
+            }
+        }
+    }
+
+    /// 指定されたインデックスのファイルを保存します。
+    /// 保存できなかった場合はエラーを表示して`false`を返します。
+    fn save_document(&mut self, index: usize) -> bool {
+        let active_document = self.active_document;
+        // 差分表示は編集中のファイルのものなので、他のファイルを保存する間は外しておく
+        let diff_view = if index != active_document {
+            self.diff_view.take()
+        } else {
+            None
+        };
+        self.active_document = index;
+        let result = self.save_current_file();
+        self.active_document = active_document;
+        if diff_view.is_some() {
+            self.diff_view = diff_view;
+        }
+        if let Err(e) = result {
+            emsg!(
+                self,
+                "{} の保存に失敗しました: {}",
+                self.documents[index].name(),
+                e
+            );
+            return false;
+        }
+        true
+    }
+
+    /// 編集中のファイルのタブを、保存していない変更があっても確認せずに閉じます。
+    fn discard_current_document(&mut self) {
+        let index = self.active_document;
+        self.close_document_views();
+
+        let document = self.documents.remove(index);
+        if let Some(path) = &document.history_path
+            && let Err(e) = document.write_history_for_disk(path)
+        {
+            emsg!(
+                self,
+                "警告: Undo履歴 {:?} を保存できませんでした: {}",
+                path,
+                e
+            );
+        }
+        if let Some(path) = &document.temp_path
+            && path.exists()
+            && let Err(e) = fs::remove_file(path)
+        {
+            emsg!(
+                self,
+                "警告: 一時ファイル {:?} を削除できませんでした: {}",
+                path,
+                e
+            );
+        }
+        if document.is_modified() {
+            msg!(self, "{} を保存せずに閉じました。", document.name());
+        } else {
+            msg!(self, "{} を閉じました。", document.name());
+        }
+        self.active_document = index.min(self.documents.len() - 1);
+        // 閉じたファイルを表示していたペインには、代わりに編集中のファイルを表示する
+        self.windows.remove_document(index, self.active_document);
+    }
+
+    /// 検索バーや差分表示など、編集中のファイルに結び付いた表示を閉じます。
+    fn close_document_views(&mut self) {
+        if self.search.is_some() {
+            self.close_search();
+        }
+        self.diff_view = None;
+        self.hunk_preview_visible = false;
+    }
+
+    /// 「ファイルを開く」のパスの入力欄を開きます。
+    /// 初期値は編集中のファイルのあるディレクトリです。
+    pub fn open_file_prompt(&mut self) {
+        let directory = self
+            .document()
+            .target_path
+            .as_deref()
+            .and_then(Path::parent)
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .map(|parent| format!("{}/", parent.display()))
+            .unwrap_or_default();
+        self.open_file_prompt = Some(InputLine::new(&directory));
+    }
+
+    /// 「ファイルを開く」の入力欄に入力されたパスのファイルを開きます。
+    pub fn confirm_open_file_prompt(&mut self) {
+        let Some(input) = self.open_file_prompt.take() else {
+            return;
+        };
+        let path = input.text.trim();
+        if path.is_empty() {
+            return;
+        }
+        if Path::new(path).is_dir() {
+            msg!(self, "{} はディレクトリです。", path);
+            self.open_file_prompt = Some(input);
+            return;
+        }
+        self.open_file(Path::new(path));
+    }
+}
+
+/// 起動時に作られる、ファイルに結び付いていない空のバッファかどうかを返します。
+fn is_blank(document: &Document) -> bool {
+    document.target_path.is_none()
+        && !document.is_modified()
+        && document.editor.buffer.len_bytes() == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 保存していない変更のあるタブは、確認してから閉じることを確かめます。
+    #[test]
+    fn asks_before_closing_modified_tab() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("first.txt");
+        let second = dir.path().join("second.txt");
+        fs::write(&first, "first\n").unwrap();
+        fs::write(&second, "second\n").unwrap();
+
+        let mut app = App::default();
+        app.open_file(&first);
+        app.open_file(&second);
+        app.editor_mut().insert_char('!');
+
+        assert!(!app.close_current_document());
+        assert_eq!(app.documents.len(), 2);
+        assert_eq!(app.close_confirm, Some(CloseConfirm::Tab));
+
+        // 保存して閉じる
+        app.confirm_close(true);
+        assert_eq!(app.close_confirm, None);
+        assert_eq!(app.documents.len(), 1);
+        assert_eq!(fs::read_to_string(&second).unwrap(), "!second\n");
+
+        // 変更がなければ確認せずに閉じる
+        app.open_file(&second);
+        assert!(!app.close_current_document());
+        assert_eq!(app.close_confirm, None);
+        assert_eq!(app.documents.len(), 1);
+    }
+}
@@ -0,0 +1,210 @@
+// src/app/text_buffer.rs
+
+use ropey::{Rope, RopeSlice};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::ops::Range;
+use std::path::Path;
+
+/// ロープ（平衡木）でテキストを保持するストレージです。
+/// 挿入・削除、および行番号とオフセットの相互変換を O(log n) で行えるため、
+/// 編集のたびにバッファ全体を走査する必要がありません。
+///
+/// 行の区切りは LF (`\n`) のみです。テキストが改行で終わる場合、その後ろに
+/// 空の最終行が1行あるものとして扱います（`"a\n"` は2行）。
+/// オフセットは特に断りがない限りバイト単位、行内の列 (x) は文字単位です。
+#[derive(Debug, Clone, Default)]
+pub struct TextBuffer {
+    rope: Rope,
+}
+
+impl TextBuffer {
+    /// 文字列から新しいバッファを作成します。
+    pub fn new(text: &str) -> Self {
+        Self {
+            rope: Rope::from_str(text),
+        }
+    }
+
+    /// ファイルの内容を読み込んでバッファを作成します。
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let rope = Rope::from_reader(BufReader::new(File::open(path)?))?;
+        Ok(Self { rope })
+    }
+
+    /// バッファの内容をファイルに書き込みます。
+    pub fn write_to_file(&self, path: &Path) -> io::Result<()> {
+        self.rope.write_to(BufWriter::new(File::create(path)?))
+    }
+
+    /// 内部のロープへの参照を返します。（構文解析など、チャンク単位の読み取り用）
+    pub fn rope(&self) -> &Rope {
+        &self.rope
+    }
+
+    /// バッファの総バイト数を返します。
+    pub fn len_bytes(&self) -> usize {
+        self.rope.len_bytes()
+    }
+
+    /// バッファの総文字数を返します。
+    pub fn len_chars(&self) -> usize {
+        self.rope.len_chars()
+    }
+
+    /// バッファの行数を返します。空のバッファでも1行と数えます。
+    pub fn len_lines(&self) -> usize {
+        self.rope.len_lines()
+    }
+
+    /// バッファが空かどうかを返します。
+    pub fn is_empty(&self) -> bool {
+        self.rope.len_bytes() == 0
+    }
+
+    /// 指定された行の内容を、末尾の改行を除いて返します。
+    /// 範囲外の行が指定された場合は空のスライスを返します。
+    pub fn line(&self, y: usize) -> RopeSlice<'_> {
+        match self.rope.get_line(y) {
+            Some(line) => {
+                let len = line.len_chars();
+                if len > 0 && line.char(len - 1) == '\n' {
+                    line.slice(..len - 1)
+                } else {
+                    line
+                }
+            }
+            None => self.rope.slice(0..0),
+        }
+    }
+
+    /// 指定された行の文字数（改行を除く）を返します。
+    pub fn line_len_chars(&self, y: usize) -> usize {
+        self.line(y).len_chars()
+    }
+
+    /// 全ての行を、末尾の改行を除いた状態で順に返します。
+    pub fn lines(&self) -> impl Iterator<Item = RopeSlice<'_>> {
+        (0..self.len_lines()).map(move |y| self.line(y))
+    }
+
+    /// 指定された行の先頭のバイトオフセットを返します。
+    /// 行数を超える場合はバッファの末尾を返します。
+    pub fn line_to_byte(&self, y: usize) -> usize {
+        if y >= self.len_lines() {
+            self.len_bytes()
+        } else {
+            self.rope.line_to_byte(y)
+        }
+    }
+
+    /// バイトオフセットが含まれる行番号を返します。
+    pub fn byte_to_line(&self, byte_offset: usize) -> usize {
+        self.rope.byte_to_line(byte_offset.min(self.len_bytes()))
+    }
+
+    /// バイトオフセットを文字オフセットに変換します。
+    pub fn byte_to_char(&self, byte_offset: usize) -> usize {
+        self.rope.byte_to_char(byte_offset.min(self.len_bytes()))
+    }
+
+    /// 文字オフセットをバイトオフセットに変換します。
+    pub fn char_to_byte(&self, char_offset: usize) -> usize {
+        self.rope.char_to_byte(char_offset.min(self.len_chars()))
+    }
+
+    /// 論理位置 (y, x) をバイトオフセットに変換します。
+    /// 行や列が範囲外の場合は、それぞれバッファ末尾・行末にクランプします。
+    pub fn pos_to_byte(&self, y: usize, x: usize) -> usize {
+        if y >= self.len_lines() {
+            return self.len_bytes();
+        }
+        let line_start_char = self.rope.line_to_char(y);
+        let x = x.min(self.line_len_chars(y));
+        self.rope.char_to_byte(line_start_char + x)
+    }
+
+    /// バイトオフセットを論理位置 (y, x) に変換します。
+    /// 文字の途中を指すオフセットは、その文字の先頭として扱います。
+    pub fn byte_to_pos(&self, byte_offset: usize) -> (usize, usize) {
+        let char_offset = self.byte_to_char(byte_offset);
+        let y = self.rope.char_to_line(char_offset);
+        (y, char_offset - self.rope.line_to_char(y))
+    }
+
+    /// 指定されたバイトオフセットの直前にある文字の先頭オフセットを返します。
+    pub fn prev_char_boundary(&self, byte_offset: usize) -> usize {
+        let char_offset = self.byte_to_char(byte_offset);
+        if self.rope.char_to_byte(char_offset) < byte_offset.min(self.len_bytes()) {
+            // 文字の途中を指していた場合は、その文字の先頭が直前の境界
+            return self.rope.char_to_byte(char_offset);
+        }
+        self.rope.char_to_byte(char_offset.saturating_sub(1))
+    }
+
+    /// 指定されたバイトオフセットの直後にある文字の終端オフセットを返します。
+    pub fn next_char_boundary(&self, byte_offset: usize) -> usize {
+        let char_offset = self.byte_to_char(byte_offset);
+        self.char_to_byte(char_offset + 1)
+    }
+
+    /// 指定されたバイト範囲のテキストを文字列として取り出します。
+    pub fn slice_to_string(&self, range: Range<usize>) -> String {
+        let start = self.byte_to_char(range.start);
+        let end = self.byte_to_char(range.end);
+        self.rope.slice(start..end).to_string()
+    }
+
+    /// バッファ内容のハッシュ値 (64ビット FNV-1a) を返します。
+    /// 保存した履歴が現在の内容に対応しているかどうかの確認に使います。
+    pub fn content_hash(&self) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+        const FNV_PRIME: u64 = 0x100000001b3;
+        let mut hash = FNV_OFFSET_BASIS;
+        for chunk in self.rope.chunks() {
+            for byte in chunk.bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        }
+        hash
+    }
+
+    /// 指定されたバイトオフセットにテキストを挿入します。
+    pub fn insert(&mut self, byte_offset: usize, text: &str) {
+        let char_offset = self.byte_to_char(byte_offset);
+        self.rope.insert(char_offset, text);
+    }
+
+    /// 指定されたバイト範囲のテキストを削除します。
+    pub fn remove(&mut self, range: Range<usize>) {
+        let start = self.byte_to_char(range.start);
+        let end = self.byte_to_char(range.end);
+        if start < end {
+            self.rope.remove(start..end);
+        }
+    }
+
+    /// 指定されたバイト範囲のテキストを新しいテキストで置き換えます。
+    pub fn replace(&mut self, range: Range<usize>, text: &str) {
+        let start = range.start;
+        self.remove(range);
+        self.insert(start, text);
+    }
+}
+
+impl fmt::Display for TextBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for chunk in self.rope.chunks() {
+            f.write_str(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&str> for TextBuffer {
+    fn from(text: &str) -> Self {
+        Self::new(text)
+    }
+}
@@ -0,0 +1,536 @@
+// src/app/theme.rs
+
+use super::App;
+use super::color::ColorDepth;
+use super::config::config_dir;
+use crate::{emsg, msg};
+use ratatui::style::{Color, Modifier, Style};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use toml::{Table, Value};
+
+/// ユーザー定義のテーマを置くディレクトリの名前（設定ディレクトリの中に置く）
+const THEMES_DIR_NAME: &str = "themes";
+
+/// 組み込みのテーマの名前（切り替える順）
+pub const BUILTIN_THEMES: &[&str] = &["dark", "light", "high-contrast"];
+
+/// 画面の各部分の色の組み合わせ（テーマ）です。全てのコンポーネントはここから色を取ります。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub background: Color, // エディタ本体とポップアップの背景（Resetは端末の既定の色）
+    pub foreground: Color, // 通常の文字
+    pub panel_background: Color, // 行番号・スクロールバー・検索バー・差分表示の背景
+    pub muted: Color,      // 補足の文字（ヒント、変更のない行の記号など）
+    pub inactive: Color,   // 選択されていないタブやペインの見出しの文字
+    pub inactive_background: Color, // フォーカスのないペインの見出しや選択行、差分表示の空き行の背景
+    pub emphasis: Color,            // フォーカスのあるサイドバーの見出し
+    pub accent: Color,              // ポップアップの枠線、入力欄のプロンプト、キーの表記
+    pub accent_foreground: Color,   // accentを背景にした文字
+    pub highlight_background: Color, // 選択している候補・タブ・フォーカスのあるペインの見出しの背景
+    pub line_number: Color,         // 行番号
+    pub selection_background: Color, // 選択範囲
+    pub search_scope_background: Color, // 選択範囲内検索の対象範囲
+    pub search_match_background: Color, // 検索結果
+    pub current_match_background: Color, // 現在の検索結果・有効な検索オプション
+    pub current_match_foreground: Color,
+    pub fuzzy_match: Color,           // あいまい検索でマッチした文字
+    pub ruler_background: Color,      // max_line_length のルーラー
+    pub status_bar_foreground: Color, // Bottom Bar
+    pub status_bar_background: Color,
+    pub message_background: Color, // メッセージ通知
+    pub message_info: Color,
+    pub message_error: Color, // エラーメッセージや正規表現の誤り
+    pub added: Color,         // 差分の記号
+    pub modified: Color,
+    pub deleted: Color,
+    pub added_background: Color, // 差分表示の変更行
+    pub deleted_background: Color,
+    pub preview_background: Color,       // 変更前の内容のプレビュー
+    pub directory: Color,                // ファイルツリーのディレクトリ
+    pub active_file: Color,              // ファイルツリーで編集中のファイル
+    pub syntax: BTreeMap<String, Color>, // 構文ハイライトのキャプチャ名 → 文字色
+    pub monochrome: bool, // 色を使えない端末か（選択範囲などを太字・反転・下線で区別する）
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    /// 暗い背景のテーマ（既定）です。
+    pub fn dark() -> Self {
+        Self {
+            name: "dark".to_string(),
+            background: Color::Reset,
+            foreground: Color::Reset,
+            panel_background: Color::Rgb(30, 30, 30),
+            muted: Color::DarkGray,
+            inactive: Color::Gray,
+            inactive_background: Color::Rgb(45, 45, 45),
+            emphasis: Color::White,
+            accent: Color::Cyan,
+            accent_foreground: Color::Black,
+            highlight_background: Color::Rgb(60, 60, 90),
+            line_number: Color::Blue,
+            selection_background: Color::Rgb(50, 50, 100),
+            search_scope_background: Color::Rgb(35, 35, 60),
+            search_match_background: Color::Rgb(90, 80, 30),
+            current_match_background: Color::Rgb(230, 160, 30),
+            current_match_foreground: Color::Black,
+            fuzzy_match: Color::Yellow,
+            ruler_background: Color::Rgb(45, 45, 45),
+            status_bar_foreground: Color::Black,
+            status_bar_background: Color::White,
+            message_background: Color::DarkGray,
+            message_info: Color::Yellow,
+            message_error: Color::Red,
+            added: Color::Green,
+            modified: Color::Yellow,
+            deleted: Color::Red,
+            added_background: Color::Rgb(30, 60, 30),
+            deleted_background: Color::Rgb(70, 30, 30),
+            preview_background: Color::Rgb(40, 30, 30),
+            directory: Color::LightBlue,
+            active_file: Color::Yellow,
+            syntax: syntax_colors(&[
+                ("keyword", Color::Rgb(197, 134, 192)),
+                ("function", Color::Rgb(220, 220, 170)),
+                ("type", Color::Rgb(78, 201, 176)),
+                ("constructor", Color::Rgb(78, 201, 176)),
+                ("attribute", Color::Rgb(78, 201, 176)),
+                ("type.builtin", Color::Rgb(86, 156, 214)),
+                ("variable.builtin", Color::Rgb(86, 156, 214)),
+                ("constant.builtin", Color::Rgb(86, 156, 214)),
+                ("string", Color::Rgb(206, 145, 120)),
+                ("escape", Color::Rgb(215, 186, 125)),
+                ("label", Color::Rgb(215, 186, 125)),
+                ("number", Color::Rgb(181, 206, 168)),
+                ("comment", Color::Rgb(106, 153, 85)),
+                ("constant", Color::Rgb(79, 193, 255)),
+                ("property", Color::Rgb(156, 220, 254)),
+                ("variable.parameter", Color::Rgb(156, 220, 254)),
+            ]),
+            monochrome: false,
+        }
+    }
+
+    /// 明るい背景のテーマです。
+    pub fn light() -> Self {
+        Self {
+            name: "light".to_string(),
+            background: Color::Rgb(250, 250, 250),
+            foreground: Color::Rgb(30, 30, 30),
+            panel_background: Color::Rgb(235, 235, 235),
+            muted: Color::Rgb(140, 140, 140),
+            inactive: Color::Rgb(90, 90, 90),
+            inactive_background: Color::Rgb(222, 222, 222),
+            emphasis: Color::Black,
+            accent: Color::Rgb(0, 120, 170),
+            accent_foreground: Color::White,
+            highlight_background: Color::Rgb(200, 212, 240),
+            line_number: Color::Rgb(40, 90, 180),
+            selection_background: Color::Rgb(180, 200, 240),
+            search_scope_background: Color::Rgb(225, 230, 245),
+            search_match_background: Color::Rgb(250, 230, 150),
+            current_match_background: Color::Rgb(240, 150, 20),
+            current_match_foreground: Color::Black,
+            fuzzy_match: Color::Rgb(200, 100, 0),
+            ruler_background: Color::Rgb(230, 230, 230),
+            status_bar_foreground: Color::White,
+            status_bar_background: Color::Rgb(60, 60, 60),
+            message_background: Color::Rgb(220, 220, 220),
+            message_info: Color::Rgb(150, 100, 0),
+            message_error: Color::Rgb(200, 0, 0),
+            added: Color::Rgb(0, 140, 0),
+            modified: Color::Rgb(180, 130, 0),
+            deleted: Color::Rgb(200, 0, 0),
+            added_background: Color::Rgb(215, 245, 215),
+            deleted_background: Color::Rgb(250, 220, 220),
+            preview_background: Color::Rgb(250, 235, 235),
+            directory: Color::Rgb(0, 90, 180),
+            active_file: Color::Rgb(180, 100, 0),
+            syntax: syntax_colors(&[
+                ("keyword", Color::Rgb(175, 0, 219)),
+                ("function", Color::Rgb(121, 94, 38)),
+                ("type", Color::Rgb(38, 127, 153)),
+                ("constructor", Color::Rgb(38, 127, 153)),
+                ("attribute", Color::Rgb(38, 127, 153)),
+                ("type.builtin", Color::Rgb(0, 0, 255)),
+                ("variable.builtin", Color::Rgb(0, 0, 255)),
+                ("constant.builtin", Color::Rgb(0, 0, 255)),
+                ("string", Color::Rgb(163, 21, 21)),
+                ("escape", Color::Rgb(238, 0, 0)),
+                ("label", Color::Rgb(238, 0, 0)),
+                ("number", Color::Rgb(9, 134, 88)),
+                ("comment", Color::Rgb(0, 128, 0)),
+                ("constant", Color::Rgb(0, 112, 193)),
+                ("property", Color::Rgb(0, 16, 128)),
+                ("variable.parameter", Color::Rgb(0, 16, 128)),
+            ]),
+            monochrome: false,
+        }
+    }
+
+    /// 黒い背景にはっきりした色を使う、コントラストの高いテーマです。
+    pub fn high_contrast() -> Self {
+        Self {
+            name: "high-contrast".to_string(),
+            background: Color::Black,
+            foreground: Color::White,
+            panel_background: Color::Black,
+            muted: Color::Gray,
+            inactive: Color::White,
+            inactive_background: Color::DarkGray,
+            emphasis: Color::White,
+            accent: Color::LightCyan,
+            accent_foreground: Color::Black,
+            highlight_background: Color::Blue,
+            line_number: Color::LightCyan,
+            selection_background: Color::Blue,
+            search_scope_background: Color::DarkGray,
+            search_match_background: Color::Magenta,
+            current_match_background: Color::Yellow,
+            current_match_foreground: Color::Black,
+            fuzzy_match: Color::LightYellow,
+            ruler_background: Color::DarkGray,
+            status_bar_foreground: Color::Black,
+            status_bar_background: Color::White,
+            message_background: Color::Black,
+            message_info: Color::LightYellow,
+            message_error: Color::LightRed,
+            added: Color::LightGreen,
+            modified: Color::LightYellow,
+            deleted: Color::LightRed,
+            added_background: Color::Green,
+            deleted_background: Color::Red,
+            preview_background: Color::Black,
+            directory: Color::LightCyan,
+            active_file: Color::LightYellow,
+            syntax: syntax_colors(&[
+                ("keyword", Color::LightMagenta),
+                ("function", Color::LightYellow),
+                ("type", Color::LightCyan),
+                ("constructor", Color::LightCyan),
+                ("attribute", Color::LightCyan),
+                ("type.builtin", Color::LightBlue),
+                ("variable.builtin", Color::LightBlue),
+                ("constant.builtin", Color::LightBlue),
+                ("string", Color::LightGreen),
+                ("escape", Color::Yellow),
+                ("label", Color::Yellow),
+                ("number", Color::LightRed),
+                ("comment", Color::Gray),
+                ("constant", Color::LightBlue),
+                ("property", Color::White),
+                ("variable.parameter", Color::White),
+            ]),
+            monochrome: false,
+        }
+    }
+
+    /// 組み込みのテーマを名前で探します。
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "high-contrast" => Some(Self::high_contrast()),
+            _ => None,
+        }
+    }
+
+    /// エディタ本体とポップアップの背景・文字の色です。
+    pub fn base_style(&self) -> Style {
+        Style::default().bg(self.background).fg(self.foreground)
+    }
+
+    /// 色を使えない端末では、スタイルに`modifier`を加えて色の代わりに区別できるようにします。
+    fn or_modifier(&self, style: Style, modifier: Modifier) -> Style {
+        if self.monochrome {
+            style.add_modifier(modifier)
+        } else {
+            style
+        }
+    }
+
+    /// 選択範囲のスタイルです。（色を使えない場合は反転）
+    pub fn selection(&self) -> Style {
+        self.or_modifier(
+            Style::default().bg(self.selection_background),
+            Modifier::REVERSED,
+        )
+    }
+
+    /// 検索結果のスタイルです。（色を使えない場合は下線）
+    pub fn search_match(&self) -> Style {
+        self.or_modifier(
+            Style::default().bg(self.search_match_background),
+            Modifier::UNDERLINED,
+        )
+    }
+
+    /// 現在の検索結果と有効な検索オプションのスタイルです。（色を使えない場合は反転・太字）
+    pub fn current_match(&self) -> Style {
+        self.or_modifier(
+            Style::default()
+                .bg(self.current_match_background)
+                .fg(self.current_match_foreground),
+            Modifier::REVERSED | Modifier::BOLD,
+        )
+    }
+
+    /// 選択している候補・タブ・フォーカスのあるペインの見出しのスタイルです。（色を使えない場合は反転）
+    pub fn highlight(&self) -> Style {
+        self.or_modifier(
+            Style::default().bg(self.highlight_background),
+            Modifier::REVERSED,
+        )
+    }
+
+    /// Bottom Bar のスタイルです。（色を使えない場合は反転）
+    pub fn status_bar(&self) -> Style {
+        self.or_modifier(
+            Style::default()
+                .fg(self.status_bar_foreground)
+                .bg(self.status_bar_background),
+            Modifier::REVERSED,
+        )
+    }
+
+    /// 差分の記号のスタイルです。`color`は added / modified / deleted のいずれかです。（色を使えない場合は太字）
+    pub fn diff_marker(&self, color: Color) -> Style {
+        self.or_modifier(Style::default().fg(color), Modifier::BOLD)
+    }
+
+    /// 差分表示の変更行のスタイルです。`background`は added_background か deleted_background です。
+    /// （色を使えない場合は下線）
+    pub fn changed_line(&self, background: Color) -> Style {
+        self.or_modifier(Style::default().bg(background), Modifier::UNDERLINED)
+    }
+
+    /// 構文ハイライトのキャプチャ名に対応する文字色を返します。
+    /// `function.method`のような名前に対応する色がなければ、`function`のように末尾を省いて探します。
+    pub fn syntax_color(&self, capture_name: &str) -> Option<Color> {
+        let mut name = capture_name;
+        loop {
+            if let Some(color) = self.syntax.get(name) {
+                return Some(*color);
+            }
+            name = name.rsplit_once('.')?.0;
+        }
+    }
+
+    /// テーマファイルで指定できる名前の色を返します。
+    fn color_mut(&mut self, key: &str) -> Option<&mut Color> {
+        let color = match key {
+            "background" => &mut self.background,
+            "foreground" => &mut self.foreground,
+            "panel_background" => &mut self.panel_background,
+            "muted" => &mut self.muted,
+            "inactive" => &mut self.inactive,
+            "inactive_background" => &mut self.inactive_background,
+            "emphasis" => &mut self.emphasis,
+            "accent" => &mut self.accent,
+            "accent_foreground" => &mut self.accent_foreground,
+            "highlight_background" => &mut self.highlight_background,
+            "line_number" => &mut self.line_number,
+            "selection_background" => &mut self.selection_background,
+            "search_scope_background" => &mut self.search_scope_background,
+            "search_match_background" => &mut self.search_match_background,
+            "current_match_background" => &mut self.current_match_background,
+            "current_match_foreground" => &mut self.current_match_foreground,
+            "fuzzy_match" => &mut self.fuzzy_match,
+            "ruler_background" => &mut self.ruler_background,
+            "status_bar_foreground" => &mut self.status_bar_foreground,
+            "status_bar_background" => &mut self.status_bar_background,
+            "message_background" => &mut self.message_background,
+            "message_info" => &mut self.message_info,
+            "message_error" => &mut self.message_error,
+            "added" => &mut self.added,
+            "modified" => &mut self.modified,
+            "deleted" => &mut self.deleted,
+            "added_background" => &mut self.added_background,
+            "deleted_background" => &mut self.deleted_background,
+            "preview_background" => &mut self.preview_background,
+            "directory" => &mut self.directory,
+            "active_file" => &mut self.active_file,
+            _ => return None,
+        };
+        Some(color)
+    }
+
+    /// テーマファイルの内容を解釈します。
+    /// `extends`で指定した組み込みのテーマ（省略時は dark）に、`[colors]`と`[syntax]`の色を重ねます。
+    /// ファイル全体を解釈できない場合はエラーを返します。
+    /// 誤りのある項目はその項目だけを無視し、内容を一覧で返します。
+    pub fn parse(name: &str, text: &str) -> Result<(Self, Vec<String>), String> {
+        let mut table: Table = toml::from_str(text).map_err(|e| e.message().to_string())?;
+        let mut errors = Vec::new();
+
+        let mut theme = match table.remove("extends") {
+            None => Self::dark(),
+            Some(Value::String(base)) => Self::builtin(&base).ok_or_else(|| {
+                format!(
+                    "extends には組み込みのテーマ（{}）を指定してください",
+                    BUILTIN_THEMES.join(" / ")
+                )
+            })?,
+            Some(_) => return Err("extends は文字列で指定してください".to_string()),
+        };
+        theme.name = name.to_string();
+
+        for (section, value) in table {
+            let Value::Table(colors) = value else {
+                errors.push(format!("不明な項目 \"{}\" です", section));
+                continue;
+            };
+            for (key, value) in colors {
+                let color = match value.as_str().map(str::parse::<Color>) {
+                    Some(Ok(color)) => color,
+                    _ => {
+                        errors.push(format!(
+                            "{}.{}: \"#rrggbb\" か色の名前（red など）で指定してください",
+                            section, key
+                        ));
+                        continue;
+                    }
+                };
+                match section.as_str() {
+                    "colors" => match theme.color_mut(&key) {
+                        Some(slot) => *slot = color,
+                        None => errors.push(format!("不明な色の名前 \"colors.{}\" です", key)),
+                    },
+                    "syntax" => {
+                        theme.syntax.insert(key, color);
+                    }
+                    _ => {
+                        errors.push(format!("不明な項目 \"{}\" です", section));
+                        break;
+                    }
+                }
+            }
+        }
+        Ok((theme, errors))
+    }
+}
+
+/// キャプチャ名と色の組から、構文ハイライトの色の表を作成します。
+fn syntax_colors(colors: &[(&str, Color)]) -> BTreeMap<String, Color> {
+    colors
+        .iter()
+        .map(|(name, color)| (name.to_string(), *color))
+        .collect()
+}
+
+/// ユーザー定義のテーマを置くディレクトリを返します。
+pub fn themes_dir() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(THEMES_DIR_NAME))
+}
+
+/// 使えるテーマの名前を、組み込みのテーマ、ユーザー定義のテーマ（名前順）の順に返します。
+pub fn theme_names() -> Vec<String> {
+    let mut user_themes: Vec<String> = themes_dir()
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "toml" {
+                return None;
+            }
+            Some(path.file_stem()?.to_string_lossy().into_owned())
+        })
+        .filter(|name| !BUILTIN_THEMES.contains(&name.as_str()))
+        .collect();
+    user_themes.sort();
+    BUILTIN_THEMES
+        .iter()
+        .map(|name| name.to_string())
+        .chain(user_themes)
+        .collect()
+}
+
+impl App {
+    /// 名前で指定されたテーマを読み込んで切り替えます。
+    /// 組み込みのテーマと同じ名前のファイルがあっても、組み込みのテーマを使います。
+    /// 読み込めなかった場合は、今のテーマのままにして`false`を返します。
+    pub fn set_theme(&mut self, name: &str) -> bool {
+        if let Some(theme) = Theme::builtin(name) {
+            self.apply_theme(theme);
+            return true;
+        }
+        let Some(path) = themes_dir().map(|dir| dir.join(format!("{}.toml", name))) else {
+            emsg!(
+                self,
+                "設定ディレクトリが分かりません。HOME または XDG_CONFIG_HOME を設定してください。"
+            );
+            return false;
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                emsg!(
+                    self,
+                    "テーマ \"{}\" が見つかりません（{}、または {:?}）",
+                    name,
+                    BUILTIN_THEMES.join(" / "),
+                    path
+                );
+                return false;
+            }
+            Err(e) => {
+                emsg!(
+                    self,
+                    "テーマ {:?} の読み込み中にエラーが発生しました: {}",
+                    path,
+                    e
+                );
+                return false;
+            }
+        };
+        match Theme::parse(name, &text) {
+            Ok((theme, errors)) => {
+                for error in errors {
+                    emsg!(self, "テーマ {:?} の誤り: {}", path, error);
+                }
+                self.apply_theme(theme);
+                true
+            }
+            Err(e) => {
+                emsg!(self, "テーマ {:?} を解釈できません: {}", path, e);
+                false
+            }
+        }
+    }
+
+    /// テーマを切り替えます。色を使えない端末では、色の代わりに太字・反転・下線を使うようにします。
+    fn apply_theme(&mut self, mut theme: Theme) {
+        theme.monochrome = self.color_depth == ColorDepth::Monochrome;
+        self.theme = theme;
+    }
+
+    /// 使えるテーマのうち、今のテーマの次のテーマに切り替えます。
+    pub fn next_theme(&mut self) {
+        let names = theme_names();
+        let next = names
+            .iter()
+            .position(|name| *name == self.theme.name)
+            .map_or(0, |index| (index + 1) % names.len());
+        if self.set_theme(&names[next]) {
+            msg!(self, "テーマを {} に切り替えました。", self.theme.name);
+        }
+    }
+
+    /// 今のテーマを読み直します。（テーマファイルを編集した後に使う）
+    pub fn reload_theme(&mut self) {
+        let name = self.theme.name.clone();
+        if self.set_theme(&name) {
+            msg!(self, "テーマ {} を読み直しました。", name);
+        }
+    }
+}
@@ -0,0 +1,518 @@
+// src/app/window.rs
+
+use super::App;
+use super::cursor::Cursor;
+use super::editor::Editor;
+use crate::msg;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+
+/// ペインを分割する向きです。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitDirection {
+    Horizontal, // 上下に並べる
+    Vertical,   // 左右に並べる
+}
+
+/// フォーカスを移動する方向です。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// ペインごとに持つ表示位置（カーソルとスクロールオフセット）です。
+/// 同じファイルを複数のペインで開いても、それぞれ別の位置を表示できます。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct View {
+    pub cursor: Cursor,
+    pub scroll_offset_x: usize,
+    pub scroll_offset_y: usize,
+    revision: u64,                   // 表示位置を取り出した時点のバッファのリビジョン
+    cursor_offset: usize,            // その時点のカーソル位置（バイトオフセット）
+    selection_offset: Option<usize>, // その時点の選択範囲の開始位置（バイトオフセット）
+    top_offset: usize,               // その時点の先頭に表示していた行の行頭（バイトオフセット）
+}
+
+impl View {
+    /// エディタの現在の表示位置を取り出します。
+    pub fn capture(editor: &Editor) -> Self {
+        let buffer = &editor.buffer;
+        let top_line = editor
+            .scroll_offset_y
+            .min(buffer.len_lines().saturating_sub(1));
+        Self {
+            cursor: editor.cursor,
+            scroll_offset_x: editor.scroll_offset_x,
+            scroll_offset_y: editor.scroll_offset_y,
+            revision: editor.revision,
+            cursor_offset: buffer.pos_to_byte(editor.cursor.y, editor.cursor.x),
+            selection_offset: editor
+                .cursor
+                .selection_start
+                .map(|(x, y)| buffer.pos_to_byte(y, x)),
+            top_offset: buffer.line_to_byte(top_line),
+        }
+    }
+
+    /// 表示位置をエディタに設定します。
+    /// 他のペインで同じファイルを編集していた場合は、その変更に合わせてカーソルとスクロール位置をずらします。
+    /// 変更の記録が残っていない場合は、カーソルをバッファの範囲内に収めるだけにします。
+    pub fn restore(&self, editor: &mut Editor) {
+        let view = if self.revision == editor.revision {
+            *self
+        } else {
+            self.shifted(editor).unwrap_or(*self)
+        };
+        let clamp = |(x, y): (usize, usize)| -> (usize, usize) {
+            let y = y.min(editor.buffer.len_lines().saturating_sub(1));
+            (x.min(editor.buffer.line_len_chars(y)), y)
+        };
+        let (x, y) = clamp((view.cursor.x, view.cursor.y));
+        editor.cursor = Cursor {
+            x,
+            y,
+            selection_start: view.cursor.selection_start.map(clamp),
+        };
+        editor.scroll_offset_x = view.scroll_offset_x;
+        editor.scroll_offset_y = view
+            .scroll_offset_y
+            .min(editor.buffer.len_lines().saturating_sub(1));
+    }
+
+    /// 表示位置を取り出した後のバッファの変更に合わせて、ずらした表示位置を返します。
+    fn shifted(&self, editor: &Editor) -> Option<Self> {
+        let buffer = &editor.buffer;
+        let map = |offset| editor.map_offset_since(self.revision, offset);
+        let (cursor_y, cursor_x) = buffer.byte_to_pos(map(self.cursor_offset)?);
+        let selection_start = match self.selection_offset {
+            Some(offset) => {
+                let (y, x) = buffer.byte_to_pos(map(offset)?);
+                Some((x, y))
+            }
+            None => None,
+        };
+        Some(Self {
+            cursor: Cursor {
+                x: cursor_x,
+                y: cursor_y,
+                selection_start,
+            },
+            scroll_offset_x: self.scroll_offset_x,
+            scroll_offset_y: buffer.byte_to_line(map(self.top_offset)?),
+            ..*self
+        })
+    }
+}
+
+/// エディタ領域を分割した1つの区画です。
+#[derive(Debug, Clone)]
+pub struct Pane {
+    pub id: usize,       // ペインの識別子（分割の木から参照する）
+    pub document: usize, // 表示しているファイルのインデックス
+    pub view: View,      // 表示位置（フォーカスのあるペインでは、エディタの状態の方が新しい）
+    pub area: Rect,      // 最後に描画した領域（フォーカスの移動に使用）
+}
+
+/// 1つのペインの中の各部分の領域です。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaneRegions {
+    pub header: Option<Rect>, // ファイル名の見出し（分割している場合のみ）
+    pub gutter: Rect,         // 行番号と差分（Left Block）
+    pub text: Rect,           // エディタ本体（Editor Block）
+    pub scrollbar: Rect,      // スクロールバーと差分マーカー（Right Block）
+}
+
+impl PaneRegions {
+    /// ペインの領域を各部分に分けます。分割している場合（`split`）は、上端の1行を見出しにします。
+    /// 左の行番号の列は`gutter_width`桁、右のスクロールバーは3桁で、残りをエディタ本体にします。
+    pub fn new(area: Rect, split: bool, gutter_width: u16) -> Self {
+        let (header, body) = if split && area.height > 1 {
+            (
+                Some(Rect { height: 1, ..area }),
+                Rect {
+                    y: area.y + 1,
+                    height: area.height - 1,
+                    ..area
+                },
+            )
+        } else {
+            (None, area)
+        };
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(gutter_width), // Left Block (行番号と差分シンボル、例: " 999 + ")
+                Constraint::Min(0),               // Editor Block (エディタ本体)
+                Constraint::Length(3),            // Right Block (スクロールバーと差分マーカー)
+            ])
+            .split(body);
+        Self {
+            header,
+            gutter: chunks[0],
+            text: chunks[1],
+            scrollbar: chunks[2],
+        }
+    }
+}
+
+/// ペインの配置を表す分割の木です。
+#[derive(Debug, Clone)]
+pub enum WindowNode {
+    Pane(usize), // ペインのid
+    Split {
+        direction: SplitDirection,
+        ratio: u16, // 1つ目の子に割り当てる割合（%）
+        first: Box<WindowNode>,
+        second: Box<WindowNode>,
+    },
+}
+
+/// エディタ領域のペインの一覧と、その配置を管理します。
+#[derive(Debug, Clone)]
+pub struct Windows {
+    pub panes: Vec<Pane>,
+    pub root: WindowNode,
+    pub focused: usize, // フォーカスのあるペインのid
+    next_id: usize,
+}
+
+impl Default for Windows {
+    /// 最初のファイルを表示するペインが1つだけの状態を作成します。
+    fn default() -> Self {
+        Self {
+            panes: vec![Pane {
+                id: 0,
+                document: 0,
+                view: View::default(),
+                area: Rect::default(),
+            }],
+            root: WindowNode::Pane(0),
+            focused: 0,
+            next_id: 1,
+        }
+    }
+}
+
+impl Windows {
+    /// 指定されたidのペインを返します。
+    pub fn pane(&self, id: usize) -> &Pane {
+        self.panes
+            .iter()
+            .find(|pane| pane.id == id)
+            .expect("分割の木にあるペインは必ず存在する")
+    }
+
+    /// 指定されたidのペインを返します。
+    pub fn pane_mut(&mut self, id: usize) -> &mut Pane {
+        self.panes
+            .iter_mut()
+            .find(|pane| pane.id == id)
+            .expect("分割の木にあるペインは必ず存在する")
+    }
+
+    /// フォーカスのあるペインを返します。
+    pub fn focused_pane_mut(&mut self) -> &mut Pane {
+        self.pane_mut(self.focused)
+    }
+
+    /// 領域を分割の木に従って割り当て、各ペインの`area`を更新します。
+    pub fn layout(&mut self, area: Rect) {
+        let mut areas = Vec::new();
+        layout_node(&self.root, area, &mut areas);
+        for (id, area) in areas {
+            self.pane_mut(id).area = area;
+        }
+    }
+
+    /// フォーカスのあるペインを分割し、同じ内容を表示する新しいペインを作ってフォーカスを移します。
+    fn split(&mut self, direction: SplitDirection, view: View) {
+        let id = self.next_id;
+        self.next_id += 1;
+        let focused = self.focused;
+        let document = self.pane(focused).document;
+        self.panes.push(Pane {
+            id,
+            document,
+            view,
+            area: Rect::default(),
+        });
+        if let Some(node) = find_node_mut(&mut self.root, focused) {
+            *node = WindowNode::Split {
+                direction,
+                ratio: 50,
+                first: Box::new(WindowNode::Pane(focused)),
+                second: Box::new(WindowNode::Pane(id)),
+            };
+        }
+        self.focused = id;
+    }
+
+    /// フォーカスのあるペインを閉じ、分割の相手側にフォーカスを移します。
+    /// ペインが1つしかない場合は何もせず`false`を返します。
+    fn close_focused(&mut self) -> bool {
+        let focused = self.focused;
+        let Some(sibling) = remove_pane(&mut self.root, focused) else {
+            return false;
+        };
+        self.panes.retain(|pane| pane.id != focused);
+        self.focused = first_pane(&sibling);
+        true
+    }
+
+    /// フォーカスのあるペインを含む最も内側の分割について、そのペイン側の割合を増減します。
+    fn resize_focused(&mut self, delta: i16) -> bool {
+        resize_node(&mut self.root, self.focused, delta) == Some(true)
+    }
+
+    /// フォーカスのあるペインから指定した方向にある、最も近いペインのidを返します。
+    fn neighbor(&self, direction: FocusDirection) -> Option<usize> {
+        let from = self.pane(self.focused).area;
+        let center_x = from.x as i32 + from.width as i32 / 2;
+        let center_y = from.y as i32 + from.height as i32 / 2;
+        self.panes
+            .iter()
+            .filter(|pane| pane.id != self.focused)
+            .filter(|pane| {
+                let to = pane.area;
+                // 移動方向にあり、かつ移動方向と直交する向きで範囲が重なっているペインが候補
+                match direction {
+                    FocusDirection::Left => to.right() <= from.x && overlaps_y(from, to),
+                    FocusDirection::Right => to.x >= from.right() && overlaps_y(from, to),
+                    FocusDirection::Up => to.bottom() <= from.y && overlaps_x(from, to),
+                    FocusDirection::Down => to.y >= from.bottom() && overlaps_x(from, to),
+                }
+            })
+            .min_by_key(|pane| {
+                let to = pane.area;
+                let distance_x = (to.x as i32 + to.width as i32 / 2 - center_x).abs();
+                let distance_y = (to.y as i32 + to.height as i32 / 2 - center_y).abs();
+                distance_x + distance_y
+            })
+            .map(|pane| pane.id)
+    }
+
+    /// ファイルが閉じられた場合に、各ペインが表示しているファイルのインデックスを付け直します。
+    /// 閉じたファイルを表示していたペインは、`replacement`のファイルを表示します。
+    pub fn remove_document(&mut self, index: usize, replacement: usize) {
+        for pane in &mut self.panes {
+            if pane.document == index {
+                pane.document = replacement;
+                pane.view = View::default();
+            } else if pane.document > index {
+                pane.document -= 1;
+            }
+        }
+    }
+}
+
+impl App {
+    /// フォーカスのあるペインを指定した向きに分割します。新しいペインは同じファイルの同じ位置を表示します。
+    pub fn split_pane(&mut self, direction: SplitDirection) {
+        self.save_focused_view();
+        let view = self.windows.pane(self.windows.focused).view;
+        self.windows.split(direction, view);
+    }
+
+    /// フォーカスのあるペインを閉じます。
+    pub fn close_pane(&mut self) {
+        self.save_focused_view();
+        if !self.windows.close_focused() {
+            msg!(self, "最後のペインは閉じられません。");
+            return;
+        }
+        self.load_focused_view();
+    }
+
+    /// 指定した方向にあるペインにフォーカスを移します。
+    pub fn focus_pane(&mut self, direction: FocusDirection) {
+        if let Some(id) = self.windows.neighbor(direction) {
+            self.focus_pane_id(id);
+        }
+    }
+
+    /// 指定されたidのペインにフォーカスを移します。
+    pub fn focus_pane_id(&mut self, id: usize) {
+        if id == self.windows.focused {
+            return;
+        }
+        self.save_focused_view();
+        self.windows.focused = id;
+        self.load_focused_view();
+    }
+
+    /// フォーカスのあるペインの大きさを、割合（%）で増減します。
+    pub fn resize_pane(&mut self, delta: i16) {
+        if !self.windows.resize_focused(delta) {
+            msg!(self, "分割されていないため、大きさを変更できません。");
+        }
+    }
+
+    /// フォーカスのあるペインの表示位置を、エディタの状態から保存します。
+    fn save_focused_view(&mut self) {
+        let view = View::capture(self.editor());
+        self.windows.focused_pane_mut().view = view;
+    }
+
+    /// フォーカスのあるペインのファイルを編集中のファイルにし、表示位置をエディタに設定します。
+    fn load_focused_view(&mut self) {
+        let pane = self.windows.pane(self.windows.focused).clone();
+        if pane.document != self.active_document && self.search.is_some() {
+            self.close_search();
+        }
+        self.diff_view = None;
+        self.hunk_preview_visible = false;
+        self.active_document = pane.document;
+        pane.view.restore(self.editor_mut());
+    }
+}
+
+/// 分割の木に従って領域を割り当て、(ペインのid, 領域) を`areas`に追加します。
+fn layout_node(node: &WindowNode, area: Rect, areas: &mut Vec<(usize, Rect)>) {
+    match node {
+        WindowNode::Pane(id) => areas.push((*id, area)),
+        WindowNode::Split {
+            direction,
+            ratio,
+            first,
+            second,
+        } => {
+            let (first_area, second_area) = match direction {
+                SplitDirection::Horizontal => {
+                    let height = (area.height as u32 * *ratio as u32 / 100) as u16;
+                    (
+                        Rect { height, ..area },
+                        Rect {
+                            y: area.y + height,
+                            height: area.height - height,
+                            ..area
+                        },
+                    )
+                }
+                SplitDirection::Vertical => {
+                    let width = (area.width as u32 * *ratio as u32 / 100) as u16;
+                    (
+                        Rect { width, ..area },
+                        Rect {
+                            x: area.x + width,
+                            width: area.width - width,
+                            ..area
+                        },
+                    )
+                }
+            };
+            layout_node(first, first_area, areas);
+            layout_node(second, second_area, areas);
+        }
+    }
+}
+
+/// 指定されたペインを表す節を探します。
+fn find_node_mut(node: &mut WindowNode, id: usize) -> Option<&mut WindowNode> {
+    match node {
+        WindowNode::Pane(pane_id) if *pane_id == id => Some(node),
+        WindowNode::Pane(_) => None,
+        WindowNode::Split { first, second, .. } => {
+            find_node_mut(first, id).or_else(|| find_node_mut(second, id))
+        }
+    }
+}
+
+/// 分割の木からペインを取り除き、その分割を相手側の子で置き換えます。置き換えた子を返します。
+fn remove_pane(node: &mut WindowNode, id: usize) -> Option<WindowNode> {
+    let WindowNode::Split { first, second, .. } = node else {
+        return None; // ルートが単一のペインの場合は取り除けない
+    };
+    let sibling = match (first.as_ref(), second.as_ref()) {
+        (WindowNode::Pane(pane_id), _) if *pane_id == id => Some((**second).clone()),
+        (_, WindowNode::Pane(pane_id)) if *pane_id == id => Some((**first).clone()),
+        _ => None,
+    };
+    match sibling {
+        Some(sibling) => {
+            *node = sibling.clone();
+            Some(sibling)
+        }
+        None => remove_pane(first, id).or_else(|| remove_pane(second, id)),
+    }
+}
+
+/// 節に含まれる最初のペインのidを返します。
+fn first_pane(node: &WindowNode) -> usize {
+    match node {
+        WindowNode::Pane(id) => *id,
+        WindowNode::Split { first, .. } => first_pane(first),
+    }
+}
+
+/// ペインを含む最も内側の分割について、そのペイン側の割合を`delta`だけ増減します。
+/// ペインが含まれない場合はNone、ペインそのものの節ではSome(false)、変更した場合はSome(true)を返します。
+fn resize_node(node: &mut WindowNode, id: usize, delta: i16) -> Option<bool> {
+    match node {
+        WindowNode::Pane(pane_id) => (*pane_id == id).then_some(false),
+        WindowNode::Split {
+            ratio,
+            first,
+            second,
+            ..
+        } => {
+            // 1つ目の子にペインがあれば割合を増やし、2つ目の子にあれば減らす
+            let delta = match (
+                resize_node(first, id, delta),
+                resize_node(second, id, delta),
+            ) {
+                (Some(true), _) | (_, Some(true)) => return Some(true),
+                (Some(false), _) => delta,
+                (_, Some(false)) => -delta,
+                (None, None) => return None,
+            };
+            *ratio = ratio.saturating_add_signed(delta).clamp(10, 90);
+            Some(true)
+        }
+    }
+}
+
+/// 2つの領域の縦方向の範囲が重なっているかどうかを返します。
+fn overlaps_y(a: Rect, b: Rect) -> bool {
+    a.y < b.bottom() && b.y < a.bottom()
+}
+
+/// 2つの領域の横方向の範囲が重なっているかどうかを返します。
+fn overlaps_x(a: Rect, b: Rect) -> bool {
+    a.x < b.right() && b.x < a.right()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 同じファイルを表示している他のペインで編集すると、表示位置が変更に合わせてずれることを確かめます。
+    #[test]
+    fn shifts_other_pane_view_after_edits() {
+        let mut app = App::default();
+        app.editor_mut().paste_text("a\nb\nc\nd\n");
+        app.editor_mut().set_cursor_position(1, 3, false);
+        app.editor_mut().scroll_offset_y = 2;
+        let first = app.windows.focused;
+        app.split_pane(SplitDirection::Horizontal);
+
+        // 上に2行挿入すると、カーソルとスクロール位置は2行下にずれる
+        app.editor_mut().set_cursor_position(0, 0, false);
+        app.editor_mut().paste_text("x\ny\n");
+        let second = app.windows.focused;
+        app.focus_pane_id(first);
+        assert_eq!((app.editor().cursor.x, app.editor().cursor.y), (1, 5));
+        assert_eq!(app.editor().scroll_offset_y, 4);
+
+        // カーソルのある行ごと削除すると、カーソルは削除した位置に移る
+        app.editor_mut().set_cursor_position(0, 4, false);
+        app.focus_pane_id(second);
+        app.editor_mut().set_cursor_position(0, 2, false);
+        app.editor_mut().set_cursor_position(0, 6, true);
+        app.editor_mut().paste_text("");
+        app.focus_pane_id(first);
+        assert_eq!(app.editor().buffer.to_string(), "x\ny\n");
+        assert_eq!((app.editor().cursor.x, app.editor().cursor.y), (0, 2));
+    }
+}
@@ -1,4 +1,12 @@
 pub mod bottom_bar;
+pub mod command_palette;
+pub mod diff_view;
+pub mod file_explorer;
+pub mod fuzzy_finder;
+pub mod hunk_preview;
+pub mod keybindings_view;
 pub mod message_display;
 pub mod middle_block;
+pub mod pane_header;
+pub mod search_bar;
 pub mod top_bar;
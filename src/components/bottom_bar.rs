@@ -1,26 +1,45 @@
 // src/components/bottom_bar.rs
 
 use crate::app::App;
+use crate::app::keymap::KeySequence;
 use ratatui::{
     Frame,
     layout::Rect,
-    style::{Style, Stylize},
     text::Line,
     widgets::{Block, Borders, Paragraph},
 }; // App構造体を使用するためにインポート
 
 /// Bottom Barを描画します。右寄せで現在のカーソル位置を表示します。
+/// 検索バーが開いている場合は、検索結果の件数（例: "3 of 17"）も表示します。
+/// 続けて押すキーの並びの途中であれば、入力済みのキー（例: "Ctrl+K …"）も表示します。
 pub fn render_bottom_bar(f: &mut Frame, area: Rect, app: &App) {
     // カーソル位置の文字列をフォーマット (0-indexed なので +1 して表示)
-    let cursor_info = format!(
+    let mut cursor_info = format!(
         "Ln {}, Col {}",
-        app.editor.cursor.y + 1,
-        app.editor.cursor.x + 1
+        app.editor().cursor.y + 1,
+        app.editor().cursor.x + 1
     );
 
+    // 検索中であれば、現在の検索結果の位置と総数をカーソル位置の前に表示
+    if let Some(prompt) = &app.search
+        && !prompt.input.text.is_empty()
+    {
+        let search_info = match app.editor().current_search_idx {
+            Some(idx) => format!("{} of {}", idx + 1, app.editor().search_matches.len()),
+            None => "No results".to_string(),
+        };
+        cursor_info = format!("{} | {}", search_info, cursor_info);
+    }
+
+    // 続けて押すキーの並びの途中であれば、入力済みのキーを表示する
+    if !app.pending_keys.is_empty() {
+        let keys = KeySequence(app.pending_keys.clone());
+        cursor_info = format!("{} … | {}", keys, cursor_info);
+    }
+
     let paragraph = Paragraph::new(Line::from(cursor_info).right_aligned())
         .block(Block::default().borders(Borders::NONE)) // 上線で区切る
-        .style(Style::default().black().on_white()); // 黒文字、白背景
+        .style(app.theme.status_bar());
 
     f.render_widget(paragraph, area);
 }
@@ -0,0 +1,127 @@
+// src/components/command_palette.rs
+
+use crate::app::App;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+/// コマンドパレットのポップアップを配置する領域を返します。（画面の上寄り中央、幅70%・高さ60%まで）
+pub fn palette_area(screen: Rect) -> Rect {
+    let width = (screen.width as u32 * 70 / 100) as u16;
+    let height = (screen.height as u32 * 60 / 100).max(4) as u16;
+    Rect {
+        x: screen.x + (screen.width - width) / 2,
+        y: screen.y + 1, // Top Bar の下
+        width,
+        height: height.min(screen.height.saturating_sub(1)),
+    }
+}
+
+/// コマンドパレットの候補の一覧に使える行数を返します。（枠線と入力欄の行を除く）
+pub fn palette_list_height(screen: Rect) -> u16 {
+    palette_area(screen).height.saturating_sub(3)
+}
+
+/// コマンドパレットを描画します。各行に操作の説明・名前・割り当てられたキーを表示し、
+/// クエリにマッチした文字を強調します。
+pub fn render_command_palette(f: &mut Frame, screen: Rect, app: &App) {
+    let Some(palette) = &app.palette else {
+        return;
+    };
+    let theme = &app.theme;
+    let area = palette_area(screen);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.accent))
+        .style(theme.base_style())
+        .title(format!(" コマンド  {}件 ", palette.entries.len()));
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+    if inner.height == 0 {
+        return;
+    }
+
+    // 検索クエリの入力欄
+    let prompt = "> ";
+    let input_area = Rect { height: 1, ..inner };
+    f.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled(prompt, Style::default().fg(theme.accent)),
+            Span::raw(palette.input.text.as_str()),
+        ])),
+        input_area,
+    );
+    let before_cursor: String = palette
+        .input
+        .text
+        .chars()
+        .take(palette.input.cursor)
+        .collect();
+    let cursor_x = input_area.x + (prompt.len() + Span::raw(before_cursor).width()) as u16;
+    if cursor_x < input_area.right() {
+        f.set_cursor_position((cursor_x, input_area.y));
+    }
+
+    // 候補の一覧
+    let list_area = Rect {
+        y: inner.y + 1,
+        height: inner.height - 1,
+        ..inner
+    };
+    let match_style = Style::default()
+        .fg(theme.fuzzy_match)
+        .add_modifier(Modifier::BOLD);
+    let lines: Vec<Line> = palette
+        .entries
+        .iter()
+        .enumerate()
+        .skip(palette.scroll_offset)
+        .take(list_area.height as usize)
+        .map(|(index, entry)| {
+            let base = if index == palette.selected {
+                theme.highlight()
+            } else {
+                Style::default()
+            };
+            let highlight = |text: &str, enabled: bool, style: Style| -> Vec<Span<'static>> {
+                text.chars()
+                    .enumerate()
+                    .map(|(position, c)| {
+                        if enabled && entry.matched.positions.contains(&position) {
+                            Span::styled(c.to_string(), style.patch(match_style))
+                        } else {
+                            Span::styled(c.to_string(), style)
+                        }
+                    })
+                    .collect()
+            };
+            let mut spans = vec![Span::styled(" ", base)];
+            spans.extend(highlight(
+                entry.action.description,
+                !entry.matched_name,
+                base,
+            ));
+            spans.push(Span::styled("  ", base));
+            spans.extend(highlight(
+                entry.action.name,
+                entry.matched_name,
+                base.fg(theme.muted),
+            ));
+
+            // キーは右寄せで表示する
+            let keys = format!("{} ", app.keymap.describe_keys(entry.action));
+            let width: usize = spans.iter().map(Span::width).sum();
+            let padding =
+                (list_area.width as usize).saturating_sub(width + Span::raw(&keys).width());
+            spans.push(Span::styled(" ".repeat(padding), base));
+            spans.push(Span::styled(keys, base.fg(theme.accent)));
+            Line::from(spans)
+        })
+        .collect();
+    f.render_widget(Paragraph::new(lines), list_area);
+}
@@ -0,0 +1,142 @@
+// src/components/diff_view.rs
+
+use crate::app::App;
+use crate::app::diff::DiffRow;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+};
+
+/// 行番号と差分記号の欄の幅（例: " 999 + "）
+const GUTTER_WIDTH: u16 = 7;
+
+/// 保存済みの内容（左）と現在のバッファ（右）を並べた差分表示を描画します。
+/// 左右の行は揃えてあり、同じスクロール位置で描画します。
+pub fn render_diff_view(f: &mut Frame, area: Rect, app: &App) {
+    let Some(view) = &app.diff_view else {
+        return;
+    };
+
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    let (deleted, added) = view.hunks.iter().fold((0, 0), |(deleted, added), hunk| {
+        (deleted + hunk.old_len, added + hunk.new_len)
+    });
+    let old_title = match &app.document().target_path {
+        Some(path) => format!(
+            " 保存済み: {} ",
+            path.file_name().unwrap_or_default().to_string_lossy()
+        ),
+        None => " 保存済み: (新規) ".to_string(),
+    };
+    let new_title = format!(
+        " 編集中 (-{} +{})  Esc:閉じる Alt+E:書き出し ",
+        deleted, added
+    );
+
+    let theme = &app.theme;
+    let old_lines: Vec<&str> = app.document().snapshot_buffer.split('\n').collect();
+    render_pane(
+        f,
+        panes[0],
+        app,
+        &old_title,
+        Borders::TOP | Borders::RIGHT,
+        |row| row.old.map(|y| (y, old_lines[y].to_string())),
+        (theme.deleted, theme.deleted_background, "-"),
+    );
+    render_pane(
+        f,
+        panes[1],
+        app,
+        &new_title,
+        Borders::TOP,
+        |row| {
+            row.new
+                .map(|y| (y, app.editor().buffer.line(y).to_string()))
+        },
+        (theme.added, theme.added_background, "+"),
+    );
+}
+
+/// 差分表示の片側（行番号の欄とテキスト）を描画します。
+/// `line_of`は表示行に対応するこちら側の行番号と内容を返し、`changed_style`は変更行の
+/// (記号の色, 背景色, 記号) です。
+fn render_pane(
+    f: &mut Frame,
+    area: Rect,
+    app: &App,
+    title: &str,
+    borders: Borders,
+    line_of: impl Fn(&DiffRow) -> Option<(usize, String)>,
+    changed_style: (Color, Color, &str),
+) {
+    let Some(view) = &app.diff_view else {
+        return;
+    };
+    let theme = &app.theme;
+    let block = Block::default()
+        .borders(borders)
+        .title(Span::styled(
+            title.to_string(),
+            Style::default().add_modifier(Modifier::BOLD),
+        ))
+        .style(Style::default().bg(theme.panel_background));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(GUTTER_WIDTH), Constraint::Min(0)])
+        .split(inner);
+    let (symbol_color, changed_bg, symbol) = changed_style;
+    let filler_bg = theme.inactive_background; // 反対側にだけ行がある場合の空き行
+
+    let mut gutter_lines: Vec<Line> = Vec::new();
+    let mut text_lines: Vec<Line> = Vec::new();
+    for row in view
+        .rows
+        .iter()
+        .skip(view.scroll_y)
+        .take(inner.height as usize)
+    {
+        match line_of(row) {
+            Some((y, text)) => {
+                let (symbol, symbol_style, text_style) = if row.changed {
+                    (
+                        symbol,
+                        theme.diff_marker(symbol_color).add_modifier(Modifier::BOLD),
+                        theme.changed_line(changed_bg),
+                    )
+                } else {
+                    (" ", Style::default(), Style::default())
+                };
+                gutter_lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("{:>5}", y + 1),
+                        Style::default().fg(theme.line_number),
+                    ),
+                    Span::styled(format!(" {}", symbol), symbol_style),
+                ]));
+                text_lines.push(Line::styled(text, text_style));
+            }
+            None => {
+                gutter_lines.push(Line::raw(""));
+                text_lines.push(Line::styled("", Style::default().bg(filler_bg)));
+            }
+        }
+    }
+
+    f.render_widget(Paragraph::new(gutter_lines), chunks[0]);
+    // 横方向のスクロールは左右で共通
+    f.render_widget(
+        Paragraph::new(text_lines).scroll((0, view.scroll_x)),
+        chunks[1],
+    );
+}
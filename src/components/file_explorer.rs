@@ -0,0 +1,146 @@
+// src/components/file_explorer.rs
+
+use crate::app::App;
+use crate::app::explorer::ExplorerPrompt;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+};
+
+/// サイドバーのファイルツリーを描画します。
+/// ディレクトリには展開状態を示す記号を付け、編集中のファイルは色を変えて表示します。
+/// 作成・名前の変更・削除の操作中は、最下行に入力欄（確認）を表示します。
+pub fn render_file_explorer(f: &mut Frame, area: Rect, app: &App) {
+    let explorer = &app.explorer;
+    let theme = &app.theme;
+    let title_style = if explorer.focused {
+        Style::default()
+            .fg(theme.emphasis)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(theme.muted)
+    };
+    let root_name = explorer.root.file_name().map_or_else(
+        || explorer.root.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    let block = Block::default()
+        .borders(Borders::RIGHT)
+        .border_style(Style::default().fg(theme.muted))
+        .title(Span::styled(format!(" {} ", root_name), title_style));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let list_height = explorer_list_height(app, inner.height) as usize;
+    let active_path = app
+        .document()
+        .target_path
+        .as_deref()
+        .and_then(|path| std::fs::canonicalize(path).ok());
+
+    let mut lines: Vec<Line> = Vec::new();
+    for (index, entry) in explorer
+        .entries
+        .iter()
+        .enumerate()
+        .skip(explorer.scroll_offset)
+        .take(list_height)
+    {
+        let marker = if !entry.is_dir {
+            "  "
+        } else if explorer.is_expanded(&entry.path) {
+            "▾ "
+        } else {
+            "▸ "
+        };
+        let mut style = if entry.is_dir {
+            Style::default().fg(theme.directory)
+        } else if active_path.as_deref() == Some(entry.path.as_path()) {
+            Style::default().fg(theme.active_file)
+        } else {
+            Style::default()
+        };
+        if index == explorer.selected {
+            // 選択行はフォーカスがある場合だけ目立たせる
+            style = if explorer.focused {
+                style.patch(theme.highlight()).add_modifier(Modifier::BOLD)
+            } else {
+                style.bg(theme.inactive_background)
+            };
+        }
+        let text = format!("{}{}{}", "  ".repeat(entry.depth), marker, entry.name);
+        let padding = (inner.width as usize).saturating_sub(Span::raw(&text).width());
+        lines.push(Line::from(Span::styled(
+            format!("{}{}", text, " ".repeat(padding)),
+            style,
+        )));
+    }
+    if explorer.entries.is_empty() {
+        lines.push(Line::from(Span::styled(
+            " (空のディレクトリ)",
+            Style::default().fg(theme.muted),
+        )));
+    }
+    f.render_widget(
+        Paragraph::new(lines),
+        Rect {
+            height: list_height as u16,
+            ..inner
+        },
+    );
+
+    // 操作中の入力欄（確認）を最下行に描画
+    let Some(prompt) = &explorer.prompt else {
+        return;
+    };
+    let prompt_area = Rect {
+        y: inner.bottom().saturating_sub(1),
+        height: 1,
+        ..inner
+    };
+    let label_style = Style::default()
+        .fg(theme.accent_foreground)
+        .bg(theme.accent);
+    let (label, input) = match prompt {
+        ExplorerPrompt::Create { input, .. } => ("新規:", Some(input)),
+        ExplorerPrompt::Rename { input, .. } => ("名前:", Some(input)),
+        ExplorerPrompt::Delete { .. } => ("削除 (y/n):", None),
+    };
+    let text = match prompt {
+        ExplorerPrompt::Create { input, .. } | ExplorerPrompt::Rename { input, .. } => {
+            input.text.clone()
+        }
+        ExplorerPrompt::Delete { path } => path
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned()),
+    };
+    let spans = vec![
+        Span::styled(label, label_style),
+        Span::raw(" "),
+        Span::raw(text),
+    ];
+    f.render_widget(Paragraph::new(Line::from(spans)), prompt_area);
+
+    if let Some(input) = input {
+        let before_cursor: String = input.text.chars().take(input.cursor).collect();
+        let x = prompt_area.x
+            + Span::raw(label).width() as u16
+            + 1
+            + Span::raw(before_cursor).width() as u16;
+        if x < prompt_area.right() {
+            f.set_cursor_position((x, prompt_area.y));
+        }
+    }
+}
+
+/// ファイルツリーの一覧に使える行数を返します。操作中は入力欄の1行を除きます。
+pub fn explorer_list_height(app: &App, inner_height: u16) -> u16 {
+    if app.explorer.prompt.is_some() {
+        inner_height.saturating_sub(1)
+    } else {
+        inner_height
+    }
+}
@@ -0,0 +1,141 @@
+// src/components/fuzzy_finder.rs
+
+use crate::app::App;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+/// ファインダーのポップアップを配置する領域を返します。（画面の中央、幅80%・高さ70%）
+pub fn finder_area(screen: Rect) -> Rect {
+    let width = (screen.width as u32 * 80 / 100) as u16;
+    let height = (screen.height as u32 * 70 / 100).max(5) as u16;
+    Rect {
+        x: screen.x + (screen.width - width) / 2,
+        y: screen.y + (screen.height.saturating_sub(height)) / 2,
+        width,
+        height: height.min(screen.height),
+    }
+}
+
+/// ファインダーの候補の一覧に使える行数を返します。（枠線と入力欄の行を除く）
+pub fn finder_list_height(screen: Rect) -> u16 {
+    finder_area(screen).height.saturating_sub(3)
+}
+
+/// ファインダー（ファイルのあいまい検索）のポップアップを描画します。
+/// 左に候補の一覧（マッチした文字を強調）を、右に選択している候補の先頭部分を表示します。
+pub fn render_fuzzy_finder(f: &mut Frame, screen: Rect, app: &App) {
+    let Some(finder) = &app.finder else {
+        return;
+    };
+    let theme = &app.theme;
+    let area = finder_area(screen);
+    let indexing = if finder.index.is_complete() {
+        ""
+    } else {
+        "  (一覧を作成中…)"
+    };
+    let title = format!(
+        " ファイルを開く  {}/{}件{} ",
+        finder.matches.len(),
+        finder.index.files.len(),
+        indexing
+    );
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.accent))
+        .style(theme.base_style())
+        .title(title);
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(inner);
+
+    // 検索クエリの入力欄
+    let prompt = "> ";
+    f.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled(prompt, Style::default().fg(theme.accent)),
+            Span::raw(finder.input.text.as_str()),
+        ])),
+        rows[0],
+    );
+    let before_cursor: String = finder
+        .input
+        .text
+        .chars()
+        .take(finder.input.cursor)
+        .collect();
+    let cursor_x = rows[0].x + (prompt.len() + Span::raw(before_cursor).width()) as u16;
+    if cursor_x < rows[0].right() {
+        f.set_cursor_position((cursor_x, rows[0].y));
+    }
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(rows[1]);
+
+    // 候補の一覧（マッチした文字を強調表示）
+    let match_style = Style::default()
+        .fg(theme.fuzzy_match)
+        .add_modifier(Modifier::BOLD);
+    let lines: Vec<Line> = finder
+        .matches
+        .iter()
+        .enumerate()
+        .skip(finder.scroll_offset)
+        .take(columns[0].height as usize)
+        .map(|(index, m)| {
+            let base = if index == finder.selected {
+                theme.highlight()
+            } else {
+                Style::default()
+            };
+            let mut spans = vec![Span::styled(" ", base)];
+            for (position, c) in finder.index.files[m.file].chars().enumerate() {
+                let style = if m.positions.contains(&position) {
+                    base.patch(match_style)
+                } else {
+                    base
+                };
+                spans.push(Span::styled(c.to_string(), style));
+            }
+            let width: usize = spans.iter().map(Span::width).sum();
+            let padding = (columns[0].width as usize).saturating_sub(width);
+            spans.push(Span::styled(" ".repeat(padding), base));
+            Line::from(spans)
+        })
+        .collect();
+    f.render_widget(Paragraph::new(lines), columns[0]);
+
+    // 選択している候補のプレビュー
+    let preview_block = Block::default()
+        .borders(Borders::LEFT)
+        .border_style(Style::default().fg(theme.muted));
+    let preview_lines: Vec<Line> = match &finder.preview {
+        Some(preview) if preview.lines.is_empty() => vec![Line::styled(
+            "（空のファイル）",
+            Style::default().fg(theme.muted),
+        )],
+        Some(preview) => preview
+            .lines
+            .iter()
+            .take(columns[1].height as usize)
+            .map(|line| Line::raw(line.as_str()))
+            .collect(),
+        None => Vec::new(),
+    };
+    f.render_widget(
+        Paragraph::new(preview_lines).block(preview_block),
+        columns[1],
+    );
+}
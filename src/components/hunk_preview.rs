@@ -0,0 +1,87 @@
+// src/components/hunk_preview.rs
+
+use crate::app::App;
+use crate::app::editor::ScreenRow;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+/// プレビューに表示する比較元の行数の上限
+const MAX_PREVIEW_LINES: usize = 10;
+
+/// カーソル行の変更箇所（ハンク）について、比較元の内容をポップアップで表示します。
+/// カーソル行の直下に表示し、入りきらない場合はカーソル行の上に表示します。
+/// カーソル行の画面上の位置は、エディタ本体に表示している画面上の行`rows`から求めます。（折り返し表示では1行が複数行になる）
+pub fn render_hunk_preview(f: &mut Frame, area: Rect, app: &App, rows: &[ScreenRow]) {
+    if !app.hunk_preview_visible {
+        return;
+    }
+    let Some(hunk) = app.hunk_at_cursor() else {
+        return; // カーソルが変更箇所から離れたら表示しない
+    };
+
+    let original_lines: Vec<&str> = app
+        .document()
+        .original_buffer
+        .split('\n')
+        .skip(hunk.old_start)
+        .take(hunk.old_len)
+        .collect();
+    let mut lines: Vec<Line> = original_lines
+        .iter()
+        .take(MAX_PREVIEW_LINES)
+        .map(|line| {
+            Line::from(vec![
+                Span::styled("- ", app.theme.diff_marker(app.theme.deleted)),
+                Span::raw(line.to_string()),
+            ])
+        })
+        .collect();
+    if original_lines.len() > MAX_PREVIEW_LINES {
+        lines.push(Line::styled(
+            format!("... 他 {} 行", original_lines.len() - MAX_PREVIEW_LINES),
+            Style::default().fg(app.theme.muted),
+        ));
+    }
+    if lines.is_empty() {
+        lines.push(Line::styled(
+            "（追加された行です）",
+            Style::default().fg(app.theme.muted),
+        ));
+    }
+
+    // 枠線の分を加えた高さで、カーソル行の下（入りきらなければ上）に配置する
+    // 折り返したカーソル行は、その全ての画面上の行を避ける
+    let cursor_line = app.editor().cursor.y;
+    let (Some(first_row), Some(last_row)) = (
+        rows.iter().position(|row| row.line == cursor_line),
+        rows.iter().rposition(|row| row.line == cursor_line),
+    ) else {
+        return; // カーソル行が表示範囲外
+    };
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let below = area.y + last_row as u16 + 1;
+    let y = if below + height <= area.bottom() {
+        below
+    } else {
+        (area.y + first_row as u16)
+            .saturating_sub(height)
+            .max(area.y)
+    };
+    let popup_area = Rect::new(area.x, y, area.width, height);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(Span::styled(
+            format!(" 変更前 ({}) ", app.document().diff_base.label()),
+            Style::default().add_modifier(Modifier::BOLD),
+        ))
+        .style(app.theme.base_style().bg(app.theme.preview_background));
+
+    f.render_widget(Clear, popup_area);
+    f.render_widget(Paragraph::new(lines).block(block), popup_area);
+}
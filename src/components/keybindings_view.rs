@@ -0,0 +1,74 @@
+// src/components/keybindings_view.rs
+
+use crate::app::App;
+use crate::app::actions::ACTIONS;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+/// キーの列の幅
+const KEYS_WIDTH: usize = 24;
+
+/// キー割り当ての一覧のポップアップを配置する領域を返します。（画面の中央、幅80%・高さ80%）
+pub fn keybindings_area(screen: Rect) -> Rect {
+    let width = (screen.width as u32 * 80 / 100) as u16;
+    let height = (screen.height as u32 * 80 / 100).max(3) as u16;
+    let height = height.min(screen.height);
+    Rect {
+        x: screen.x + (screen.width - width) / 2,
+        y: screen.y + (screen.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// キー割り当ての一覧に使える行数を返します。（枠線を除く）
+pub fn keybindings_list_height(screen: Rect) -> u16 {
+    keybindings_area(screen).height.saturating_sub(2)
+}
+
+/// キー割り当ての一覧を描画します。全ての操作について、割り当てられたキー・説明・名前を1行ずつ表示します。
+pub fn render_keybindings_view(f: &mut Frame, screen: Rect, app: &App) {
+    let Some(view) = &app.keybindings_view else {
+        return;
+    };
+    let theme = &app.theme;
+    let area = keybindings_area(screen);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.accent))
+        .style(theme.base_style())
+        .title(" キー割り当て ")
+        .title_bottom(" ↑↓ スクロール  Esc 閉じる ");
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let lines: Vec<Line> = ACTIONS
+        .iter()
+        .skip(view.scroll_offset)
+        .take(inner.height as usize)
+        .map(|action| {
+            let keys = app.keymap.describe_keys(action);
+            let (keys, keys_style) = if keys.is_empty() {
+                ("（なし）".to_string(), Style::default().fg(theme.muted))
+            } else {
+                (keys, Style::default().fg(theme.accent))
+            };
+            let padding = KEYS_WIDTH.saturating_sub(Span::raw(keys.as_str()).width());
+            Line::from(vec![
+                Span::styled(format!(" {}{} ", keys, " ".repeat(padding)), keys_style),
+                Span::raw(action.description),
+                Span::styled(
+                    format!("  {}", action.name),
+                    Style::default().fg(theme.muted),
+                ),
+            ])
+        })
+        .collect();
+    f.render_widget(Paragraph::new(lines), inner);
+}
@@ -4,7 +4,7 @@ use crate::app::{App, MessageType}; // AppとMessageType構造体を使用する
 use ratatui::{
     Frame,
     layout::Rect,
-    style::{Color, Style},
+    style::Style,
     text::Line,
     widgets::{Block, Borders, Paragraph},
 };
@@ -12,20 +12,18 @@ use std::time::Duration; // Durationをインポート
 
 /// アプリケーションのメッセージキューを表示します。（通知スタイル）
 pub fn render_message_display(f: &mut Frame, area: Rect, app: &App) {
-    const MESSAGE_LIFETIME_SECS: u64 = 3; // メッセージの表示期間（秒）
+    let lifetime = Duration::from_secs(app.settings.message_lifetime_secs); // メッセージの表示期間
 
     // 現在時刻から、表示期間内のメッセージのみをフィルタリング
     let now = std::time::Instant::now();
     let mut visible_messages: Vec<Line> = app
         .messages
         .iter()
-        .filter(|(_, _, timestamp)| {
-            now.duration_since(*timestamp) < Duration::from_secs(MESSAGE_LIFETIME_SECS)
-        })
+        .filter(|(_, _, timestamp)| now.duration_since(*timestamp) < lifetime)
         .map(|(msg_type, msg_content, _)| {
             let style = match msg_type {
-                MessageType::Info => Style::default().fg(Color::Yellow), // 情報メッセージは黄色
-                MessageType::Error => Style::default().fg(Color::Red),   // エラーメッセージは赤色
+                MessageType::Info => Style::default().fg(app.theme.message_info),
+                MessageType::Error => Style::default().fg(app.theme.message_error),
             };
             Line::from(msg_content.clone()).style(style)
         })
@@ -44,7 +42,7 @@ pub fn render_message_display(f: &mut Frame, area: Rect, app: &App) {
 
     let block = Block::default()
         .borders(Borders::ALL) // 境界線
-        .style(Style::default().bg(Color::DarkGray)); // 暗い背景色で通知感を出す
+        .style(Style::default().bg(app.theme.message_background)); // 背景色を変えて通知感を出す
 
     let paragraph = Paragraph::new(visible_messages)
         .block(block)
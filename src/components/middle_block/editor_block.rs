@@ -1,50 +1,125 @@
 use crate::app::App;
+use crate::app::editor::{Editor, ScreenRow, char_display_width};
 use ratatui::{
     Frame,
     layout::Rect,
-    style::{Color, Style},
+    style::Style,
     text::{Line, Span, Text}, // Textをインポート
-    widgets::{Block, Borders, Paragraph, Wrap},
+    widgets::{Block, Borders, Paragraph},
 };
 
 /// エディタ本体 (テキストとネイティブカーソル) を描画します。
-pub fn render_editor_block(f: &mut Frame, area: Rect, app: &App) {
-    let editor_content = &app.editor.buffer;
-    let cursor_x = app.editor.cursor.x;
-    let cursor_y = app.editor.cursor.y;
-    let selection_range = app.editor.get_selection_range(); // バイトオフセットでの選択範囲
+/// `rows`は表示する画面上の行で、折り返し表示ではバッファの1行が複数の行になります。
+/// ネイティブカーソルは、フォーカスのあるペイン (`focused`) でのみ表示します。
+pub fn render_editor_block(
+    f: &mut Frame,
+    area: Rect,
+    app: &App,
+    rows: &[ScreenRow],
+    focused: bool,
+) {
+    let editor_content = &app.editor().buffer;
+    let cursor = app.editor().cursor;
+    let selection_range = app.editor().get_selection_range(); // バイトオフセットでの選択範囲
+    let search_matches = &app.editor().search_matches; // 検索結果の位置（出現順にソート済み）
+    let search_scope = app.editor().active_search_scope(); // 選択範囲内検索の対象範囲
+    let tab_width = app.editor().tab_width;
+    let theme = &app.theme;
+    // 折り返し表示では横方向にスクロールしない
+    let scroll_x = if app.word_wrap_enabled {
+        0
+    } else {
+        app.editor().scroll_offset_x
+    };
 
     let mut lines_for_paragraph: Vec<Line> = Vec::new();
 
-    // 全ての行をイテレートし、選択状態を考慮したスタイルを適用します。
-    // Paragraphウィジェットが内部でスクロールを処理するため、
-    // ここで表示範囲による行のフィルタリングは行いません。
-    for (line_idx, line_str) in editor_content.lines().enumerate() {
+    // 画面上の行に表示されるバッファの行だけをイテレートし、選択状態を考慮したスタイルを適用します。
+    // 縦方向のスクロールと折り返しは画面上の行（`rows`）で処理し、
+    // 横方向のスクロールはParagraphウィジェットに任せます。
+    let start_line_idx = rows.first().map_or(0, |row| row.line);
+    let end_line_idx = rows.last().map_or(0, |row| row.line + 1);
+
+    // 表示範囲の構文ハイライトをまとめて取得する（開始位置の順に重なりなし）
+    let highlights = match &app.editor().syntax {
+        Some(syntax) => syntax.highlights(
+            editor_content,
+            editor_content.line_to_byte(start_line_idx)..editor_content.line_to_byte(end_line_idx),
+        ),
+        None => Vec::new(),
+    };
+    let mut highlight_idx = 0;
+
+    for line_idx in start_line_idx..end_line_idx {
+        // この行の画面上の行（折り返した位置で新しい行を始める）
+        let line_rows: Vec<&ScreenRow> = rows.iter().filter(|row| row.line == line_idx).collect();
+        let (Some(first_row), Some(last_row)) = (line_rows.first(), line_rows.last()) else {
+            continue;
+        };
         let mut spans: Vec<Span> = Vec::new();
-        let mut current_byte_offset_in_line = 0;
+        // この行の先頭のグローバルバイトオフセット（TextBufferがO(log n)で返す）
+        let mut char_global_start_offset = editor_content.line_to_byte(line_idx);
+
+        // この行にかかる検索結果の範囲を二分探索で求める
+        let line_end_byte = editor_content.line_to_byte(line_idx + 1);
+        let first_match_idx =
+            search_matches.partition_point(|found| found.end <= char_global_start_offset);
+        let line_matches = search_matches[first_match_idx..]
+            .iter()
+            .take_while(|found| found.start < line_end_byte);
 
-        for c in line_str.chars() {
+        let mut column = 0; // 表示上の桁位置（タブを展開するために数える）
+        for (char_idx, c) in editor_content.line(line_idx).chars().enumerate() {
             let char_len_bytes = c.len_utf8();
+            let char_global_end_offset = char_global_start_offset + char_len_bytes;
+
+            // 表示する画面上の行の範囲外の文字（描画領域の下端で切れた行の続き）は描画しない
+            if char_idx >= last_row.end && char_idx > first_row.start {
+                break;
+            }
+            // 折り返す位置で画面上の行を改める
+            if char_idx > first_row.start && line_rows.iter().any(|row| row.start == char_idx) {
+                lines_for_paragraph.push(Line::from(std::mem::take(&mut spans)));
+            }
 
             let mut style = Style::default();
 
+            // 構文ハイライト（文字色）
+            while highlights
+                .get(highlight_idx)
+                .is_some_and(|span| span.end <= char_global_start_offset)
+            {
+                highlight_idx += 1;
+            }
+            if let (Some(span), Some(syntax)) =
+                (highlights.get(highlight_idx), &app.editor().syntax)
+                && span.start <= char_global_start_offset
+                && let Some(color) = theme.syntax_color(syntax.capture_name(span.capture))
+            {
+                style = style.fg(color);
+            }
+
+            // 選択範囲内検索の対象範囲を薄くハイライト
+            if let Some((scope_start, scope_end)) = search_scope
+                && (scope_start..scope_end).contains(&char_global_start_offset)
+            {
+                style = style.bg(theme.search_scope_background);
+            }
+
+            // 検索結果のハイライト（現在の検索結果は別の色で強調）
+            if let Some(match_idx) = line_matches
+                .clone()
+                .position(|found| (found.start..found.end).contains(&char_global_start_offset))
+            {
+                if app.editor().current_search_idx == Some(first_match_idx + match_idx) {
+                    style = style.patch(theme.current_match()); // 現在の検索結果
+                } else {
+                    style = style.patch(theme.search_match()); // その他の検索結果
+                }
+            }
+
             // 選択範囲のハイライト
             if let Some((sel_start, sel_end)) = selection_range {
-                // この行の先頭からのグローバルバイトオフセットを計算
-                // buffer.lines().take(line_idx) は、現在の行より前の全ての行のイテレータを返します。
-                // その後、mapで各行のバイト長+改行文字のバイト長を計算し、sumで合計します。
-                let global_line_start_byte_offset = app
-                    .editor
-                    .buffer
-                    .lines()
-                    .take(line_idx)
-                    .map(|l| l.len() + 1) // +1 for newline (LF is 1 byte)
-                    .sum::<usize>();
-
-                let char_global_start_offset =
-                    global_line_start_byte_offset + current_byte_offset_in_line;
-                let char_global_end_offset = char_global_start_offset + char_len_bytes;
-
                 // 現在の文字が選択範囲内にあるかチェック
                 // このチェックは、文字の開始オフセットが選択範囲内にあるか、
                 // 文字の終了オフセットが選択範囲内にあるか、
@@ -53,45 +128,74 @@ pub fn render_editor_block(f: &mut Frame, area: Rect, app: &App) {
                     || (char_global_end_offset > sel_start && char_global_end_offset <= sel_end)
                     || (sel_start >= char_global_start_offset && sel_start < char_global_end_offset)
                 {
-                    style = style.bg(Color::Rgb(50, 50, 100)); // 選択色
+                    style = style.patch(theme.selection()); // 選択色
                 }
             }
 
-            spans.push(Span::styled(c.to_string(), style));
-            current_byte_offset_in_line += char_len_bytes;
+            // タブは次のタブ位置までの空白として表示する
+            let width = char_display_width(c, column, tab_width);
+            if c == '\t' {
+                spans.push(Span::styled(" ".repeat(width), style));
+            } else {
+                spans.push(Span::styled(c.to_string(), style));
+            }
+            column += width;
+            char_global_start_offset = char_global_end_offset;
         }
         lines_for_paragraph.push(Line::from(spans));
+        // 空行などで最後の画面上の行に文字がない場合も、行の数を揃える
+        while lines_for_paragraph.len() < rows.iter().take_while(|row| row.line <= line_idx).count()
+        {
+            lines_for_paragraph.push(Line::raw(""));
+        }
     }
 
     // バッファが空の場合、少なくとも1行を表示してカーソルが描画されるようにする
     // Paragraphは空のVec<Line>が渡されると何も描画しないため、カーソルが表示されない可能性があります。
     // このチェックは必要です。
-    if editor_content.is_empty() {
+    if lines_for_paragraph.is_empty() {
         lines_for_paragraph.push(Line::from(vec![Span::raw("")]));
     }
 
-    let mut paragraph = Paragraph::new(Text::from(lines_for_paragraph)) // Text::fromでVec<Line>をラップ
+    let paragraph = Paragraph::new(Text::from(lines_for_paragraph)) // Text::fromでVec<Line>をラップ
         .block(Block::default().borders(Borders::NONE)) // 枠線なし
-        // Paragraphウィジェットにスクロールオフセットを適用させることで、
-        // 適切な行と列の範囲が自動的に描画されます。
-        .scroll((app.editor.scroll_offset_y, app.editor.scroll_offset_x));
-
-    // 折り返し表示モードの設定
-    if app.word_wrap_enabled {
-        paragraph = paragraph.wrap(Wrap { trim: false });
-    }
+        // 表示する行は既に切り出し済みなので、横方向のスクロールオフセットのみを適用します。
+        .scroll((0, u16::try_from(scroll_x).unwrap_or(u16::MAX)));
 
     f.render_widget(paragraph, area);
 
+    // EditorConfig の max_line_length の桁にルーラーを表示する
+    if let Some(max_line_length) = app.document().editorconfig.max_line_length
+        && let Some(offset) = max_line_length.checked_sub(scroll_x)
+        && offset < area.width as usize
+    {
+        let x = area.x + offset as u16;
+        for y in area.top()..area.bottom() {
+            f.buffer_mut()[(x, y)].set_bg(theme.ruler_background);
+        }
+    }
+
     // ネイティブカーソルを描画する
-    // カーソルの論理的な位置 (cursor_x, cursor_y) から、
-    // スクロールオフセットを考慮した画面上の物理的な位置を計算します。
-    let actual_cursor_x_on_screen = area.x + cursor_x.saturating_sub(app.editor.scroll_offset_x);
-    let actual_cursor_y_on_screen = area.y + cursor_y.saturating_sub(app.editor.scroll_offset_y);
+    // カーソルのある画面上の行（折り返した位置では次の行の先頭）を探し、
+    // その行の先頭と横方向のスクロールオフセットを考慮した画面上の物理的な位置を計算します。
+    // 横方向はタブや全角文字の幅を考慮した表示上の桁で扱います。
+    let Some(row_idx) = Editor::screen_row_of(rows, cursor.x, cursor.y) else {
+        return; // カーソルがビューポート外にある場合は表示しない
+    };
+    let row = rows[row_idx];
+    let cursor_column = app.editor().display_column(row.line, cursor.x)
+        - app.editor().display_column(row.line, row.start);
+    let Some(cursor_column_on_screen) = cursor_column.checked_sub(scroll_x) else {
+        return; // カーソルが左にスクロールされて見えない
+    };
+    let actual_cursor_x_on_screen = area.x as usize + cursor_column_on_screen;
+    let actual_cursor_y_on_screen = area.y + row_idx as u16;
 
     // 計算されたカーソル位置が描画領域内にある場合のみ設定します。
-    // これにより、カーソルがビューポート外にある場合に表示されないことを保証します。
-    if actual_cursor_x_on_screen < area.right() && actual_cursor_y_on_screen < area.bottom() {
-        f.set_cursor_position((actual_cursor_x_on_screen, actual_cursor_y_on_screen));
+    if focused
+        && actual_cursor_x_on_screen < area.right() as usize
+        && actual_cursor_y_on_screen < area.bottom()
+    {
+        f.set_cursor_position((actual_cursor_x_on_screen as u16, actual_cursor_y_on_screen));
     }
 }
@@ -1,49 +1,57 @@
+use crate::app::editor::ScreenRow;
 use crate::app::{App, LineStatus};
 use ratatui::{
     Frame,
     layout::{Alignment, Rect},
-    style::{Color, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph},
 }; // AppとLineStatus構造体を使用するためにインポート
 
 /// Left Block を描画します。行番号と差分を表示します。
-pub fn render_left_block(f: &mut Frame, area: Rect, app: &App) {
+pub fn render_left_block(f: &mut Frame, area: Rect, app: &App, rows: &[ScreenRow]) {
+    let theme = &app.theme;
     let mut lines_to_display: Vec<Line> = Vec::new();
-    let editor_lines: Vec<&str> = app.editor.buffer.lines().collect();
 
-    let start_line_idx = app.editor.scroll_offset_y as usize;
-    // 描画する行の最大インデックス (バッファの終わり、またはビューポートの終わり)
-    let end_line_idx_in_buffer = (editor_lines.len() as u16).min(app.editor.scroll_offset_y + area.height) as usize;
+    // 行番号の桁数（右の境界線と、差分シンボルとスペースの分を除いた幅）
+    let number_width = (area.width as usize).saturating_sub(3);
 
-    // 表示する行の範囲で行番号と差分ステータスを生成
-    for i in start_line_idx..end_line_idx_in_buffer {
+    // 表示する画面上の行ごとに行番号と差分ステータスを生成
+    for row in rows {
+        // 折り返した行の続きには行番号を表示しない
+        if row.start > 0 {
+            lines_to_display.push(Line::from(vec![Span::raw(" ".repeat(number_width + 2))]));
+            continue;
+        }
+        let i = row.line;
         let line_number = (i + 1).to_string(); // 1-indexed
         let line_status = app
+            .document()
             .line_statuses
             .get(i)
             .copied()
             .unwrap_or(LineStatus::Unchanged); // 範囲外はUnchangedとする
 
         let diff_symbol_style = match line_status {
-            LineStatus::Modified => Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(ratatui::style::Modifier::BOLD),
-            LineStatus::Added => Style::default()
-                .fg(Color::Green)
-                .add_modifier(ratatui::style::Modifier::BOLD),
-            LineStatus::Unchanged => Style::default().fg(Color::DarkGray),
+            LineStatus::Modified => theme
+                .diff_marker(theme.modified)
+                .add_modifier(Modifier::BOLD),
+            LineStatus::Added => theme.diff_marker(theme.added).add_modifier(Modifier::BOLD),
+            LineStatus::DeletedAbove | LineStatus::DeletedBelow => theme.diff_marker(theme.deleted),
+            LineStatus::Unchanged => Style::default().fg(theme.muted),
         };
         let diff_symbol = match line_status {
             LineStatus::Modified => "~",
             LineStatus::Added => "+",
+            LineStatus::DeletedAbove => "▔", // 行の上端に線を引き、直前で削除されたことを示す
+            LineStatus::DeletedBelow => "▁", // 行の下端に線を引き、直後で削除されたことを示す
             LineStatus::Unchanged => " ",
         };
 
         // 行番号を右寄せ、差分シンボルをその左に配置
         let line_num_span = Span::styled(
-            format!("{:>4}", line_number), // 行番号を4桁に右寄せ
-            Style::default().fg(Color::Blue),
+            format!("{:>width$}", line_number, width = number_width), // 行番号を右寄せ
+            Style::default().fg(theme.line_number),
         );
         let diff_span = Span::styled(
             format!("{} ", diff_symbol), // シンボルとスペース
@@ -56,13 +64,12 @@ pub fn render_left_block(f: &mut Frame, area: Rect, app: &App) {
     // バッファの実際の行数よりもビューポートの高さが大きい場合、残りの領域を空行で埋める
     // これにより、ブロック全体が正しく埋められ、余分な行番号は表示されない
     while lines_to_display.len() < area.height as usize {
-        lines_to_display.push(Line::from(vec![Span::raw("      ")])); // 行番号の幅に合わせた空スペース
+        lines_to_display.push(Line::from(vec![Span::raw(" ".repeat(number_width + 2))])); // 行番号の幅に合わせた空スペース
     }
 
-
     let block = Block::default()
         .borders(Borders::RIGHT) // 右側に境界線
-        .style(Style::default().bg(Color::Rgb(30, 30, 30))); // 暗い背景色
+        .style(Style::default().bg(theme.panel_background));
 
     let paragraph = Paragraph::new(lines_to_display)
         .block(block)
@@ -2,14 +2,15 @@ use crate::app::{App, LineStatus};
 use ratatui::{
     Frame,
     layout::{Alignment, Rect},
-    style::{Color, Style},
+    style::Style,
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph},
 }; // AppとLineStatus構造体を使用するためにインポート
 
 /// Right Block を描画します。スクロールバーと差分マーカーを表示します。
 pub fn render_right_block(f: &mut Frame, area: Rect, app: &App) {
-    let editor_lines_count = app.editor.buffer.lines().count() as u16;
+    let theme = &app.theme;
+    let editor_lines_count = app.editor().buffer.len_lines() as u16;
     let viewport_height = area.height;
 
     let mut scrollbar_content: Vec<Line> = Vec::new();
@@ -25,7 +26,7 @@ pub fn render_right_block(f: &mut Frame, area: Rect, app: &App) {
 
     // つまみの上端位置
     let thumb_start_y = if editor_lines_count > 0 {
-        ((app.editor.scroll_offset_y as f32 / editor_lines_count as f32) * viewport_height as f32)
+        ((app.editor().scroll_offset_y as f32 / editor_lines_count as f32) * viewport_height as f32)
             as u16
     } else {
         0
@@ -45,25 +46,31 @@ pub fn render_right_block(f: &mut Frame, area: Rect, app: &App) {
         spans.push(Span::raw(line_content)); // スクロールバー部分
 
         // 差分マーカーの描画 (該当する行がある場合のみ)
-        let corresponding_editor_line_idx = (app.editor.scroll_offset_y + y_on_screen) as usize;
-        let total_editor_lines = app.editor.buffer.lines().count(); // バッファの実際の総行数
+        let corresponding_editor_line_idx = app.editor().scroll_offset_y + y_on_screen as usize;
+        let total_editor_lines = app.editor().buffer.len_lines(); // バッファの実際の総行数
 
         if corresponding_editor_line_idx < total_editor_lines {
             // 該当する行が存在する場合のみ差分マーカーを表示
             let status = app
+                .document()
                 .line_statuses
                 .get(corresponding_editor_line_idx)
                 .copied()
                 .unwrap_or(LineStatus::Unchanged); // 安全のため
 
             let diff_style = match status {
-                LineStatus::Modified => Style::default().fg(Color::Yellow),
-                LineStatus::Added => Style::default().fg(Color::Green),
-                LineStatus::Unchanged => Style::default().fg(Color::DarkGray),
+                LineStatus::Modified => theme.diff_marker(theme.modified),
+                LineStatus::Added => theme.diff_marker(theme.added),
+                LineStatus::DeletedAbove | LineStatus::DeletedBelow => {
+                    theme.diff_marker(theme.deleted)
+                }
+                LineStatus::Unchanged => Style::default().fg(theme.muted),
             };
             let marker_char = match status {
                 LineStatus::Modified => '~',
                 LineStatus::Added => '+',
+                LineStatus::DeletedAbove => '▔',
+                LineStatus::DeletedBelow => '▁',
                 LineStatus::Unchanged => ' ',
             };
             spans.push(Span::styled(marker_char.to_string(), diff_style));
@@ -76,7 +83,7 @@ pub fn render_right_block(f: &mut Frame, area: Rect, app: &App) {
 
     let block = Block::default()
         .borders(Borders::LEFT) // 左側に境界線
-        .style(Style::default().bg(Color::Rgb(30, 30, 30))); // 暗い背景色
+        .style(Style::default().bg(theme.panel_background));
 
     let paragraph = Paragraph::new(scrollbar_content)
         .block(block)
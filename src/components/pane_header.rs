@@ -0,0 +1,27 @@
+// src/components/pane_header.rs
+
+use crate::app::App;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+
+/// エディタ領域を分割している場合に、各ペインの上端に表示しているファイル名を描画します。
+/// フォーカスのあるペインの見出しは強調表示します。
+pub fn render_pane_header(f: &mut Frame, area: Rect, app: &App, focused: bool) {
+    let document = app.document();
+    let modified = if document.is_modified() { " ●" } else { "" };
+    let label = format!(" {}{} ", document.name(), modified);
+    let style = if focused {
+        app.theme.highlight().add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+            .bg(app.theme.inactive_background)
+            .fg(app.theme.inactive)
+    };
+    let paragraph = Paragraph::new(Line::from(Span::raw(label))).style(style);
+    f.render_widget(paragraph, area);
+}
@@ -0,0 +1,163 @@
+// src/components/search_bar.rs
+
+use crate::app::App;
+use crate::app::input::InputLine;
+use crate::app::search::SearchField;
+use crate::app::tabs::CloseConfirm;
+use crate::app::theme::Theme;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+}; // App構造体を使用するためにインポート
+
+/// 入力欄の見出しの幅（"Replace: " に合わせて揃える）
+const LABEL_WIDTH: usize = 9;
+
+/// 検索バーの高さ（行数）を返します。閉じている場合は0、置換モードでは2行です。
+/// 「ファイルを開く」の入力欄と、閉じる前の確認も同じ場所に1行で表示します。
+pub fn search_bar_height(app: &App) -> u16 {
+    match &app.search {
+        Some(prompt) if prompt.replacement.is_some() => 2,
+        Some(_) => 1,
+        None if app.open_file_prompt.is_some() || app.close_confirm.is_some() => 1,
+        None => 0,
+    }
+}
+
+/// 「ファイルを開く」のパスの入力欄を描画し、ネイティブカーソルを置きます。
+pub fn render_open_file_bar(f: &mut Frame, area: Rect, app: &App) {
+    let Some(input) = &app.open_file_prompt else {
+        return;
+    };
+    let hint = Span::styled(
+        "  Enter:Open Esc:Cancel",
+        Style::default().fg(app.theme.muted),
+    );
+    render_field(f, area, &app.theme, "Open:", input, vec![hint]);
+    set_input_cursor(f, area, input);
+}
+
+/// 保存していない変更を破棄して閉じる（終了する）かの確認を描画します。
+/// 検索バーを開いている場合は、その1行目に重ねて表示します。
+pub fn render_close_confirm_bar(f: &mut Frame, area: Rect, app: &App) {
+    let question = match app.close_confirm {
+        Some(CloseConfirm::Tab) => format!(" {} は保存されていません。", app.document().name()),
+        Some(CloseConfirm::Quit) => {
+            let modified = app.documents.iter().filter(|d| d.is_modified()).count();
+            format!(" 保存されていないファイルが{}個あります。", modified)
+        }
+        None => return,
+    };
+    let line = Line::from(vec![
+        Span::styled(question, Style::default().add_modifier(Modifier::BOLD)),
+        Span::styled(
+            "  y:Discard s:Save Esc:Cancel",
+            Style::default().fg(app.theme.muted),
+        ),
+    ]);
+    let paragraph = Paragraph::new(line).style(Style::default().bg(app.theme.panel_background));
+    f.render_widget(
+        paragraph,
+        Rect {
+            height: area.height.min(1),
+            ..area
+        },
+    );
+}
+
+/// 検索バーを描画します。検索クエリ（置換モードでは置換テキストも）の入力欄と、
+/// 検索オプションの状態、正規表現のエラーを表示し、入力中の欄にネイティブカーソルを置きます。
+pub fn render_search_bar(f: &mut Frame, area: Rect, app: &App) {
+    let Some(prompt) = &app.search else {
+        return; // 検索バーが閉じている場合は何も描画しない
+    };
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1); 2].as_ref())
+        .split(area);
+
+    // 1行目: 検索クエリ + オプション + エラー
+    let theme = &app.theme;
+    let options = &app.editor().search_options;
+    let mut query_spans = vec![
+        Span::raw(" "),
+        option_span(theme, "Aa", options.match_case),
+        option_span(theme, "ab", options.whole_word),
+        option_span(theme, ".*", options.regex),
+        option_span(theme, "Sel", options.in_selection),
+    ];
+    if let Some(error) = &prompt.error {
+        // 正規表現の構文エラーは複数行になるので、要点が書かれた最後の行だけを表示する
+        let first_line = error.lines().last().unwrap_or_default();
+        query_spans.push(Span::styled(
+            format!(" {}", first_line),
+            Style::default().fg(theme.message_error),
+        ));
+    }
+    render_field(f, rows[0], theme, "Find:", &prompt.input, query_spans);
+
+    // 2行目: 置換テキスト + 操作のヒント（置換モードの場合のみ）
+    if let Some(replacement) = &prompt.replacement {
+        let hint = Span::styled(
+            "  Enter:Replace Alt+S:Skip Alt+A:All",
+            Style::default().fg(theme.muted),
+        );
+        render_field(f, rows[1], theme, "Replace:", replacement, vec![hint]);
+    }
+
+    // 入力中の欄のカーソル位置にネイティブカーソルを描画する
+    // （エディタ本体のカーソルより後に設定することで、こちらが優先される）
+    let (row, input) = match (prompt.focus, &prompt.replacement) {
+        (SearchField::Replacement, Some(replacement)) => (rows[1], replacement),
+        _ => (rows[0], &prompt.input),
+    };
+    set_input_cursor(f, row, input);
+}
+
+/// 入力欄のカーソル位置にネイティブカーソルを置きます。
+fn set_input_cursor(f: &mut Frame, row: Rect, input: &InputLine) {
+    let text_before_cursor: String = input.text.chars().take(input.cursor).collect();
+    let cursor_x = row.x + LABEL_WIDTH as u16 + Span::raw(text_before_cursor).width() as u16;
+    if cursor_x < row.right() {
+        f.set_cursor_position((cursor_x, row.y));
+    }
+}
+
+/// 見出し付きの入力欄を1行描画します。入力テキストの後ろに`trailing`を続けて表示します。
+fn render_field(
+    f: &mut Frame,
+    area: Rect,
+    theme: &Theme,
+    label: &str,
+    input: &InputLine,
+    trailing: Vec<Span>,
+) {
+    let mut spans = vec![
+        Span::styled(
+            format!("{:<width$}", label, width = LABEL_WIDTH),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(input.text.as_str()),
+    ];
+    spans.extend(trailing);
+
+    let paragraph = Paragraph::new(Line::from(spans))
+        .block(Block::default().borders(Borders::NONE))
+        .style(Style::default().bg(theme.panel_background));
+
+    f.render_widget(paragraph, area);
+}
+
+/// 検索オプションの切り替え状態を表す表示を作成します。有効なオプションは強調表示されます。
+fn option_span(theme: &Theme, label: &str, enabled: bool) -> Span<'static> {
+    let style = if enabled {
+        theme.current_match().add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(theme.muted)
+    };
+    Span::styled(format!("[{}]", label), style)
+}
@@ -5,22 +5,55 @@ use ratatui::{
     Frame,
     layout::Rect,
     style::{Modifier, Style},
-    text::Line,
+    text::{Line, Span},
     widgets::{Block, Borders, Paragraph},
 }; // App構造体を使用するためにインポート
 
-/// Top Barを描画します。中央寄せでタイトルを表示します。
+/// Top Barを描画します。開いているファイルをタブとして並べ、編集中のファイルのパスを続けて表示します。
+/// 保存していない変更のあるファイルには「●」を付けます。
 pub fn render_top_bar(f: &mut Frame, area: Rect, app: &App) {
-    // タイトルを決定
-    let title = if let Some(path) = &app.target_path {
-        format!("{} - InEdit", path.display()) // ファイルパスがあればそれを表示
+    let tabs: Vec<Span> = app
+        .documents
+        .iter()
+        .enumerate()
+        .map(|(index, document)| {
+            let modified = if document.is_modified() { " ●" } else { "" };
+            let label = format!(" {}{} ", document.name(), modified);
+            if index == app.active_document {
+                Span::styled(label, app.theme.highlight().add_modifier(Modifier::BOLD))
+            } else {
+                Span::styled(label, Style::default().fg(app.theme.inactive))
+            }
+        })
+        .collect();
+
+    // タブが画面に収まらない場合は、編集中のタブが見えるまで左側のタブを省く
+    let active_end: usize = tabs[..=app.active_document].iter().map(Span::width).sum();
+    let mut first_tab = 0;
+    let mut hidden_width = 0;
+    while active_end - hidden_width > area.width as usize && first_tab < app.active_document {
+        hidden_width += tabs[first_tab].width();
+        first_tab += 1;
+    }
+
+    let mut spans: Vec<Span> = Vec::new();
+    if first_tab > 0 {
+        spans.push(Span::styled("…", Style::default().fg(app.theme.muted)));
+    }
+    for tab in tabs.into_iter().skip(first_tab) {
+        spans.push(tab);
+        spans.push(Span::raw("│"));
+    }
+    // タイトル（ファイルパスがあればそれを、なければ「Untitled」）をタブの後ろに表示
+    let title = if let Some(path) = &app.document().target_path {
+        format!(" {} - InEdit", path.display())
     } else {
-        "Untitled - InEdit".to_string() // なければ「Untitled」
+        " Untitled - InEdit".to_string()
     };
+    spans.push(Span::styled(title, Style::default().fg(app.theme.muted)));
 
-    let paragraph = Paragraph::new(Line::from(title).centered())
-        .block(Block::default().borders(Borders::NONE)) // 下線で区切る
-        .style(Style::default().add_modifier(Modifier::BOLD)); // 太字にする
+    let paragraph =
+        Paragraph::new(Line::from(spans)).block(Block::default().borders(Borders::NONE));
 
     f.render_widget(paragraph, area);
 }
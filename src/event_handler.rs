@@ -1,135 +1,123 @@
 // src/event_handler.rs
 
 use crate::app::App;
+use crate::app::explorer::ExplorerPrompt;
+use crate::app::input::InputLine;
+use crate::app::keys::KeyChord;
+use crate::app::search::SearchField;
+use crate::app::tabs::CloseConfirm;
 use crate::{emsg, msg};
-use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 
 /// イベントを処理し、アプリケーションの状態を更新します。
 /// 終了が要求された場合はtrueを返します。
 pub fn handle_event(app: &mut App) -> std::io::Result<bool> {
     // 100ミリ秒間イベントをポーリング
     if event::poll(std::time::Duration::from_millis(100))? {
-        // キーイベントのみを処理
-        if let Event::Key(key) = event::read()? {
+        let event = event::read()?;
+        // マウスのイベント（クリック・ドラッグ・ホイール）
+        if let Event::Mouse(mouse) = event {
+            app.handle_mouse(mouse);
+            return Ok(false);
+        }
+        // 端末から貼り付けられたテキスト（ブラケットペースト）
+        if let Event::Paste(text) = event {
+            handle_paste(app, &text);
+            return Ok(false);
+        }
+        // それ以外はキーイベントのみを処理
+        if let Event::Key(key) = event {
             // キーの押下イベントのみを処理（繰り返しやリリースは無視）
             if key.kind == KeyEventKind::Press {
-                let extend_selection = key.modifiers.contains(KeyModifiers::SHIFT);
+                // キー割り当ての一覧を表示している間は、キー入力を一覧のスクロールとして処理する
+                if app.keybindings_view.is_some() {
+                    handle_keybindings_view_key(app, key);
+                    return Ok(false);
+                }
+                // 保存していない変更を破棄するかの確認中は、キー入力を確認への応答として処理する
+                if app.close_confirm.is_some() {
+                    handle_close_confirm_key(app, key);
+                    return Ok(app.quit_requested);
+                }
+                // コマンドパレットが開いている間は、キー入力をコマンドパレットで処理する
+                if app.palette.is_some() {
+                    handle_palette_key(app, key);
+                    return Ok(app.quit_requested);
+                }
+                // ファインダーが開いている間は、キー入力をファインダーで処理する
+                if app.finder.is_some() {
+                    return Ok(handle_finder_key(app, key));
+                }
+                // 検索バーが開いている間は、キー入力を検索バーで処理する
+                if app.search.is_some() {
+                    return Ok(handle_search_key(app, key));
+                }
+                // 「ファイルを開く」の入力欄が開いている間は、キー入力を入力欄で処理する
+                if app.open_file_prompt.is_some() {
+                    handle_open_file_key(app, key);
+                    return Ok(false);
+                }
+                // ファイルツリーにフォーカスがある間は、キー入力をファイルツリーの操作として処理する
+                if app.explorer.focused {
+                    return Ok(handle_explorer_key(app, key));
+                }
+                // 差分表示モードの間は、キー入力を差分表示の操作として処理する
+                if app.diff_view.is_some() {
+                    return Ok(handle_diff_view_key(app, key));
+                }
 
-                match key.code {
-                    // アプリケーション終了コマンド
-                    KeyCode::Char('q') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        msg!(app, "アプリケーションを終了します。");
-                        return Ok(true); // Ctrl+Qで終了
-                    }
-                    KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        msg!(app, "アプリケーションを終了します。");
-                        return Ok(true); // Ctrl+Wで終了
-                    }
-                    KeyCode::Esc => {
-                        msg!(app, "アプリケーションを終了します。");
-                        return Ok(true); // Escキーで終了
-                    }
+                // 操作に割り当てられたキー（または続けて押すキーの並びの途中）であれば、キー割り当てで処理する
+                if app.handle_key_chord(KeyChord::from(key)) {
+                    return Ok(app.quit_requested);
+                }
 
-                    // 編集コマンド
-                    KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        // Ctrl+S で保存
-                        match app.save_current_file() {
-                            Ok(_) => msg!(app, "ファイルが正常に保存されました。"),
-                            Err(e) => emsg!(app, "ファイルの保存に失敗しました: {}", e),
-                        }
-                    }
-                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        // Ctrl+C でコピー
-                        if app.editor.copy_selection().is_some() {
-                            msg!(app, "選択範囲をクリップボードにコピーしました。");
-                        } else {
-                            msg!(app, "コピーする選択範囲がありません。");
-                        }
-                    }
-                    KeyCode::Char('x') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        // Ctrl+X で切り取り
-                        if let Some(cut_text) = app.editor.cut_selection() {
-                            app.clipboard = Some(cut_text);
-                            msg!(app, "選択範囲をクリップボードに切り取りました。");
-                        } else {
-                            msg!(app, "切り取る選択範囲がありません。");
-                        }
-                    }
-                    KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        // Ctrl+V でペースト
-                        if let Some(text_to_paste) = &app.clipboard {
-                            app.editor.paste_text(text_to_paste);
-                            // ペースト後、バッファ内容が変わるので差分を再計算
-                            app.calculate_diff_status();
-                            msg!(app, "クリップボードの内容をペーストしました。");
-                        } else {
-                            msg!(app, "クリップボードが空です。");
-                        }
-                    }
-                    KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        // Ctrl+A で全て選択
-                        app.editor.select_all();
-                        msg!(app, "全選択しました。");
-                    }
-                    KeyCode::Char('z') if key.modifiers.contains(KeyModifiers::ALT) => {
-                        // Alt+Z で折り返し表示モードをトグル
-                        app.word_wrap_enabled = !app.word_wrap_enabled;
-                        app.calculate_diff_status(); // 折り返しモード変更でもDiff再計算（状態が変化したため）
-                        if app.word_wrap_enabled {
-                            msg!(app, "折り返し表示モード: ON");
-                        } else {
-                            msg!(app, "折り返し表示モード: OFF");
-                        }
-                    }
+                let extend_selection = key.modifiers.contains(KeyModifiers::SHIFT);
 
+                match key.code {
                     // テキスト挿入
-                    KeyCode::Char(c) => {
+                    KeyCode::Char(c)
                         // Ctrlキーが押されていない通常の文字入力
                         if !key.modifiers.contains(KeyModifiers::CONTROL)
-                            && !key.modifiers.contains(KeyModifiers::ALT)
-                        {
-                            app.editor.insert_char(c);
-                            app.calculate_diff_status(); // 文字入力後、バッファ内容が変わるので差分を再計算
-                        }
+                            && !key.modifiers.contains(KeyModifiers::ALT) =>
+                    {
+                        app.editor_mut().insert_char(c);
                     }
                     KeyCode::Backspace => {
                         // Backspaceキー
-                        app.editor.delete_previous_char();
-                        app.calculate_diff_status(); // 削除後、バッファ内容が変わるので差分を再計算
+                        app.editor_mut().delete_previous_char();
                     }
                     KeyCode::Delete => {
                         // Deleteキー
-                        app.editor.delete_current_char();
-                        app.calculate_diff_status(); // 削除後、バッファ内容が変わるので差分を再計算
+                        app.editor_mut().delete_current_char();
                     }
                     KeyCode::Enter => {
                         // Enterキー (改行)
-                        app.editor.insert_char('\n');
-                        app.calculate_diff_status(); // 改行後、バッファ内容が変わるので差分を再計算
+                        app.editor_mut().insert_char('\n');
                     }
                     KeyCode::Tab => {
-                        // Tabキー (簡易的にスペース4つを挿入)
-                        app.editor.paste_text("    "); // paste_textは内部でcalculate_diff_statusを呼び出す
+                        // Tabキー (EditorConfig の指定に従ってタブ文字か、設定の数だけスペースを挿入)
+                        let indent = app.indent_text();
+                        app.editor_mut().paste_text(&indent);
                     }
 
                     // カーソル移動
                     KeyCode::Left => {
-                        app.editor.previous_char(extend_selection);
+                        app.editor_mut().previous_char(extend_selection);
                     }
                     KeyCode::Right => {
-                        app.editor.next_char(extend_selection);
+                        app.editor_mut().next_char(extend_selection);
                     }
                     KeyCode::Up => {
-                        app.editor.previous_line(extend_selection);
+                        app.editor_mut().previous_line(extend_selection);
                     }
                     KeyCode::Down => {
-                        app.editor.next_line(extend_selection);
+                        app.editor_mut().next_line(extend_selection);
                     }
                     KeyCode::Home => {
-                        app.editor.move_cursor_to_line_start(extend_selection);
+                        app.editor_mut().move_cursor_to_line_start(extend_selection);
                     }
                     KeyCode::End => {
-                        app.editor.move_cursor_to_line_end(extend_selection);
+                        app.editor_mut().move_cursor_to_line_end(extend_selection);
                     }
 
                     // その他のキーは現時点では無視
@@ -138,5 +126,431 @@ pub fn handle_event(app: &mut App) -> std::io::Result<bool> {
             }
         }
     }
-    Ok(false) // 終了が要求されていない場合はfalseを返す
+    Ok(app.quit_requested) // 終了が要求されていない場合はfalseを返す
+}
+
+/// 端末から貼り付けられたテキストを処理します。
+/// 入力欄が開いている場合はその欄に、そうでなければエディタに1回の編集として挿入します。
+/// エディタへはキー入力とは違って字下げの変換などをせず、改行コードだけを揃えてそのまま挿入します。
+fn handle_paste(app: &mut App, text: &str) {
+    // 続けて押すキーの並びの途中であれば取り消す
+    app.pending_keys.clear();
+
+    if app.keybindings_view.is_some() || app.diff_view.is_some() || app.close_confirm.is_some() {
+        return;
+    }
+    if let Some(palette) = &mut app.palette {
+        palette.input.insert_str(text);
+        palette.update_entries();
+        return;
+    }
+    if let Some(finder) = &mut app.finder {
+        finder.input.insert_str(text);
+        app.update_finder();
+        return;
+    }
+    if let Some(prompt) = &mut app.search {
+        let focus = prompt.focus;
+        prompt.focused_input_mut().insert_str(text);
+        if focus == SearchField::Query {
+            app.update_search();
+        }
+        return;
+    }
+    if let Some(input) = &mut app.open_file_prompt {
+        input.insert_str(text);
+        return;
+    }
+    if app.explorer.focused {
+        if let Some(ExplorerPrompt::Create { input, .. } | ExplorerPrompt::Rename { input, .. }) =
+            &mut app.explorer.prompt
+        {
+            input.insert_str(text);
+        }
+        return;
+    }
+
+    // 端末によっては改行を CR で送るため、バッファの改行（LF）に揃える
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    app.editor_mut().paste_text(&text);
+}
+
+/// 検索バーが開いている間のキー入力を処理します。
+/// 終了が要求された場合はtrueを返します。
+fn handle_search_key(app: &mut App, key: KeyEvent) -> bool {
+    if app.handle_quit_key(KeyChord::from(key)) {
+        return app.quit_requested;
+    }
+    let focus = app.search.as_ref().map_or(SearchField::Query, |p| p.focus);
+    let replace_mode = app.search.as_ref().is_some_and(|p| p.replacement.is_some());
+
+    match key.code {
+        KeyCode::Esc => {
+            // Esc で検索バーを閉じる（カーソルは現在のマッチ位置に残る）
+            app.close_search();
+        }
+        KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            // Ctrl+F で検索クエリの欄へ
+            app.open_search();
+        }
+        KeyCode::Char('h') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            // Ctrl+H で置換テキストの欄へ（なければ追加）
+            app.open_replace();
+        }
+        KeyCode::Tab | KeyCode::BackTab if replace_mode => {
+            // Tab で検索クエリと置換テキストの欄を切り替える
+            if let Some(prompt) = &mut app.search {
+                prompt.focus = match prompt.focus {
+                    SearchField::Query => SearchField::Replacement,
+                    SearchField::Replacement => SearchField::Query,
+                };
+            }
+        }
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::ALT) => {
+            // Alt+C で大文字と小文字の区別を切り替える
+            app.toggle_search_match_case();
+        }
+        KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::ALT) => {
+            // Alt+W で単語単位の検索を切り替える
+            app.toggle_search_whole_word();
+        }
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::ALT) => {
+            // Alt+R で正規表現モードを切り替える
+            app.toggle_search_regex();
+        }
+        KeyCode::Char('l') if key.modifiers.contains(KeyModifiers::ALT) => {
+            // Alt+L で選択範囲内検索を切り替える
+            app.toggle_search_in_selection();
+        }
+        KeyCode::Char('a') if replace_mode && key.modifiers.contains(KeyModifiers::ALT) => {
+            // Alt+A で全て置換
+            app.replace_all();
+        }
+        KeyCode::Char('s') if replace_mode && key.modifiers.contains(KeyModifiers::ALT) => {
+            // Alt+S で現在の検索結果を置換せずに次へ
+            app.search_next();
+        }
+        KeyCode::Enter if focus == SearchField::Replacement => {
+            // 置換テキストの欄で Enter を押すと、現在の検索結果を置換して次へ
+            app.replace_current();
+        }
+        KeyCode::Enter | KeyCode::F(3) if key.modifiers.contains(KeyModifiers::SHIFT) => {
+            // Shift+Enter / Shift+F3 で前の検索結果へ
+            app.search_previous();
+        }
+        KeyCode::Enter | KeyCode::F(3) => {
+            // Enter / F3 で次の検索結果へ
+            app.search_next();
+        }
+        _ => {
+            // それ以外は入力中の欄の編集として扱い、検索クエリが変わったら検索し直す
+            if let Some(prompt) = &mut app.search
+                && edit_input_line(prompt.focused_input_mut(), key)
+                && focus == SearchField::Query
+            {
+                app.update_search();
+            }
+        }
+    }
+    false
+}
+
+/// 「ファイルを開く」の入力欄が開いている間のキー入力を処理します。
+fn handle_open_file_key(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc => {
+            // Esc で入力欄を閉じる
+            app.open_file_prompt = None;
+        }
+        KeyCode::Enter => {
+            // Enter で入力されたパスのファイルを開く
+            app.confirm_open_file_prompt();
+        }
+        _ => {
+            if let Some(input) = &mut app.open_file_prompt {
+                edit_input_line(input, key);
+            }
+        }
+    }
+}
+
+/// コマンドパレットが開いている間のキー入力を処理します。
+fn handle_palette_key(app: &mut App, key: KeyEvent) {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Esc => {
+            // Esc でコマンドパレットを閉じる
+            app.palette = None;
+        }
+        KeyCode::Enter => {
+            // Enter で選択している操作を実行する
+            app.confirm_palette();
+        }
+        KeyCode::Up => app.palette_move_selection(-1),
+        KeyCode::Down => app.palette_move_selection(1),
+        KeyCode::Char('p') if ctrl => app.palette_move_selection(-1),
+        KeyCode::Char('n') if ctrl => app.palette_move_selection(1),
+        KeyCode::PageUp => app.palette_move_selection(-10),
+        KeyCode::PageDown => app.palette_move_selection(10),
+        _ => {
+            if let Some(palette) = &mut app.palette
+                && edit_input_line(&mut palette.input, key)
+            {
+                palette.update_entries();
+            }
+        }
+    }
+}
+
+/// ファインダーが開いている間のキー入力を処理します。
+/// 終了が要求された場合はtrueを返します。
+fn handle_finder_key(app: &mut App, key: KeyEvent) -> bool {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    if app.handle_quit_key(KeyChord::from(key)) {
+        return app.quit_requested;
+    }
+    match key.code {
+        KeyCode::Esc => {
+            // Esc でファインダーを閉じる
+            app.finder = None;
+        }
+        KeyCode::Enter => {
+            // Enter で選択しているファイルを開く
+            app.confirm_finder();
+        }
+        KeyCode::Up => app.finder_move_selection(-1),
+        KeyCode::Down => app.finder_move_selection(1),
+        KeyCode::Char('p') if ctrl => app.finder_move_selection(-1),
+        KeyCode::Char('n') if ctrl => app.finder_move_selection(1),
+        KeyCode::PageUp => app.finder_move_selection(-10),
+        KeyCode::PageDown => app.finder_move_selection(10),
+        _ => {
+            if let Some(finder) = &mut app.finder
+                && edit_input_line(&mut finder.input, key)
+            {
+                app.update_finder();
+            }
+        }
+    }
+    false
+}
+
+/// ファイルツリーにフォーカスがある間のキー入力を処理します。
+/// 終了が要求された場合はtrueを返します。
+fn handle_explorer_key(app: &mut App, key: KeyEvent) -> bool {
+    if app.explorer.prompt.is_some() {
+        handle_explorer_prompt_key(app, key);
+        return false;
+    }
+    if app.handle_quit_key(KeyChord::from(key)) {
+        return app.quit_requested;
+    }
+    match key.code {
+        KeyCode::Char('b') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            // Ctrl+B でサイドバーを閉じる
+            app.toggle_explorer();
+        }
+        KeyCode::Esc | KeyCode::Tab => {
+            // Esc / Tab でエディタにフォーカスを戻す（サイドバーは表示したまま）
+            app.explorer.focused = false;
+        }
+        KeyCode::Up => app.explorer.move_selection(-1),
+        KeyCode::Down => app.explorer.move_selection(1),
+        KeyCode::PageUp => app.explorer.move_selection(-10),
+        KeyCode::PageDown => app.explorer.move_selection(10),
+        KeyCode::Home => app.explorer.selected = 0,
+        KeyCode::End => app.explorer.move_selection(isize::MAX),
+        KeyCode::Enter => {
+            // Enter でファイルを開く（ディレクトリは展開・折りたたみ）
+            app.explorer_open_selected();
+        }
+        KeyCode::Right => app.explorer_expand(),
+        KeyCode::Left => app.explorer_collapse(),
+        KeyCode::Char('a') | KeyCode::Char('n') => {
+            // a / n で新しいファイルを作成（名前の末尾を / にするとディレクトリ）
+            app.explorer_start_create();
+        }
+        KeyCode::Char('r') | KeyCode::F(2) => {
+            // r / F2 で名前を変更
+            app.explorer_start_rename();
+        }
+        KeyCode::Char('d') | KeyCode::Delete => {
+            // d / Delete で削除（確認あり）
+            app.explorer_start_delete();
+        }
+        KeyCode::F(5) => {
+            // F5 でファイルツリーを読み直す
+            app.explorer_refresh();
+        }
+        _ => {}
+    }
+    false
+}
+
+/// ファイルツリーで作成・名前の変更・削除の操作中のキー入力を処理します。
+fn handle_explorer_prompt_key(app: &mut App, key: KeyEvent) {
+    if let Some(ExplorerPrompt::Delete { .. }) = app.explorer.prompt {
+        // 削除の確認は y で確定し、それ以外のキーで取り消す
+        if key.code == KeyCode::Char('y') {
+            app.confirm_explorer_prompt();
+        } else {
+            app.explorer.prompt = None;
+        }
+        return;
+    }
+    match key.code {
+        KeyCode::Esc => app.explorer.prompt = None,
+        KeyCode::Enter => app.confirm_explorer_prompt(),
+        _ => {
+            if let Some(
+                ExplorerPrompt::Create { input, .. } | ExplorerPrompt::Rename { input, .. },
+            ) = &mut app.explorer.prompt
+            {
+                edit_input_line(input, key);
+            }
+        }
+    }
+}
+
+/// 保存していない変更を破棄するかの確認中のキー入力を処理します。
+fn handle_close_confirm_key(app: &mut App, key: KeyEvent) {
+    match key.code {
+        // y で保存せずに、s で保存してから閉じる。それ以外のキーで取り消す
+        KeyCode::Char('y') => app.confirm_close(false),
+        KeyCode::Char('s') => app.confirm_close(true),
+        _ => match app.close_confirm.take() {
+            Some(CloseConfirm::Quit) => msg!(app, "終了を取り消しました。"),
+            _ => msg!(app, "閉じるのを取り消しました。"),
+        },
+    }
+}
+
+/// 差分表示モードの間のキー入力を処理します。
+/// 終了が要求された場合はtrueを返します。
+fn handle_diff_view_key(app: &mut App, key: KeyEvent) -> bool {
+    if app.handle_quit_key(KeyChord::from(key)) {
+        return app.quit_requested;
+    }
+    match key.code {
+        KeyCode::Esc => {
+            // Esc で差分表示を閉じる
+            app.toggle_diff_view();
+        }
+        KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::ALT) => {
+            // Alt+V で差分表示を閉じる
+            app.toggle_diff_view();
+        }
+        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            // Ctrl+S で保存（差分表示は保存した内容との差分に更新される）
+            match app.save_current_file() {
+                Ok(_) => msg!(app, "ファイルが正常に保存されました。"),
+                Err(e) => emsg!(app, "ファイルの保存に失敗しました: {}", e),
+            }
+        }
+        KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::ALT) => {
+            // Alt+E で差分を unified diff 形式で書き出す
+            app.export_unified_diff();
+        }
+        KeyCode::Char(']') if key.modifiers.contains(KeyModifiers::ALT) => {
+            // Alt+] で次の変更箇所へ
+            app.diff_view_jump_hunk(true);
+        }
+        KeyCode::Char('[') if key.modifiers.contains(KeyModifiers::ALT) => {
+            // Alt+[ で前の変更箇所へ
+            app.diff_view_jump_hunk(false);
+        }
+        KeyCode::Up => app.scroll_diff_view(-1),
+        KeyCode::Down => app.scroll_diff_view(1),
+        KeyCode::PageUp => app.scroll_diff_view_page(false),
+        KeyCode::PageDown => app.scroll_diff_view_page(true),
+        KeyCode::Home => app.scroll_diff_view_to_edge(false),
+        KeyCode::End => app.scroll_diff_view_to_edge(true),
+        KeyCode::Left => app.scroll_diff_view_horizontal(-4),
+        KeyCode::Right => app.scroll_diff_view_horizontal(4),
+        _ => {}
+    }
+    false
+}
+
+/// キー割り当ての一覧を表示している間のキー入力を処理します。
+fn handle_keybindings_view_key(app: &mut App, key: KeyEvent) {
+    let Some(view) = &mut app.keybindings_view else {
+        return;
+    };
+    match key.code {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
+            // Esc / Enter / q で一覧を閉じる
+            app.keybindings_view = None;
+        }
+        KeyCode::Up => view.scroll(-1),
+        KeyCode::Down => view.scroll(1),
+        KeyCode::PageUp => view.scroll(-(view.height.max(1) as isize)),
+        KeyCode::PageDown => view.scroll(view.height.max(1) as isize),
+        KeyCode::Home => view.scroll_offset = 0,
+        KeyCode::End => view.scroll(isize::MAX),
+        _ => {}
+    }
+}
+
+/// 1行の入力欄に対する共通の編集キーを処理します。
+/// テキストが変更された場合は`true`を返します。
+fn edit_input_line(input: &mut InputLine, key: KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Char(c)
+            if !key.modifiers.contains(KeyModifiers::CONTROL)
+                && !key.modifiers.contains(KeyModifiers::ALT) =>
+        {
+            input.insert_char(c);
+            true
+        }
+        KeyCode::Backspace => input.delete_previous_char(),
+        KeyCode::Delete => input.delete_current_char(),
+        KeyCode::Left => {
+            input.move_left();
+            false
+        }
+        KeyCode::Right => {
+            input.move_right();
+            false
+        }
+        KeyCode::Home => {
+            input.move_to_start();
+            false
+        }
+        KeyCode::End => {
+            input.move_to_end();
+            false
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tabs::CloseConfirm;
+
+    /// 貼り付けたテキストは改行コードだけを揃え、字下げを足さずに1回の編集として挿入することを確かめます。
+    #[test]
+    fn pastes_text_literally_as_one_edit() {
+        let mut app = App::default();
+        for c in "    x".chars() {
+            app.editor_mut().insert_char(c);
+        }
+        app.pending_keys.push("Ctrl+K".parse().unwrap());
+
+        handle_paste(&mut app, "a\r\n  b\rc\n");
+        assert_eq!(app.editor().buffer.to_string(), "    xa\n  b\nc\n");
+        assert_eq!((app.editor().cursor.x, app.editor().cursor.y), (0, 3));
+        assert!(app.pending_keys.is_empty());
+
+        // 貼り付けは1回で元に戻り、その前の入力は残る
+        assert!(app.editor_mut().undo());
+        assert_eq!(app.editor().buffer.to_string(), "    x");
+
+        // 閉じる前の確認中は貼り付けを無視する
+        app.close_confirm = Some(CloseConfirm::Quit);
+        handle_paste(&mut app, "y");
+        assert_eq!(app.editor().buffer.to_string(), "    x");
+    }
 }
@@ -1,31 +1,52 @@
 // src/ui.rs
 use crate::{
-    app::App,
+    app::{
+        App,
+        window::{PaneRegions, View},
+    },
     components::{
         bottom_bar::render_bottom_bar,
+        command_palette::{palette_list_height, render_command_palette},
+        diff_view::render_diff_view,
+        file_explorer::{explorer_list_height, render_file_explorer},
+        fuzzy_finder::{finder_list_height, render_fuzzy_finder},
+        hunk_preview::render_hunk_preview,
+        keybindings_view::{keybindings_list_height, render_keybindings_view},
         message_display::render_message_display,
         middle_block::editor_block::render_editor_block,
         middle_block::left_block::render_left_block,
         middle_block::right_block::render_right_block,
+        pane_header::render_pane_header,
+        search_bar::{
+            render_close_confirm_bar, render_open_file_bar, render_search_bar, search_bar_height,
+        },
         top_bar::render_top_bar,
     },
 };
 use ratatui::{
     Frame,
     layout::{Constraint, Direction, Layout, Rect},
+    widgets::Block,
 };
 
 /// アプリケーションのUIを描画します。
 pub fn draw_ui(f: &mut Frame, app: &mut App) {
     let size = f.area();
 
+    // テーマの背景色と文字色で画面全体を塗り、各コンポーネントはその上に描画する
+    f.render_widget(Block::default().style(app.theme.base_style()), size);
+
+    // 検索バーは開いている場合のみ高さを確保する（置換モードでは2行）
+    let search_bar_height = search_bar_height(app);
+
     // メインのレイアウトチャンクを定義
     let main_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
             [
-                Constraint::Length(1), // Top Bar (タイトル)
-                Constraint::Min(0),    // メインエディタ領域（Left + Editor + Right）
+                Constraint::Length(1),                 // Top Bar (タイトル)
+                Constraint::Min(0), // メインエディタ領域（Left + Editor + Right）
+                Constraint::Length(search_bar_height), // Search Bar (検索クエリ入力)
                 Constraint::Length(1), // Bottom Bar (カーソル位置)
             ]
             .as_ref(),
@@ -35,37 +56,57 @@ pub fn draw_ui(f: &mut Frame, app: &mut App) {
     // Top Bar の描画
     render_top_bar(f, main_chunks[0], app);
 
-    // メインエディタ領域をさらに分割 (Left Block + Editor Block + Right Block)
-    // 左ブロック (行番号と差分): 7文字固定 (例: "    1 + ")
-    // 右ブロック (スクロールバーと差分): 3文字固定 (例: " |~")
-    // エディタ本体: 残りのスペース
-    let editor_area_chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(
-            [
-                Constraint::Length(7), // Left Block (行番号と差分シンボル、例: " 999 + ")
-                Constraint::Min(0),    // Editor Block (エディタ本体)
-                Constraint::Length(3), // Right Block (スクロールバーと差分マーカー)
-            ]
-            .as_ref(),
-        )
-        .split(main_chunks[1]); // main_chunks[1] がエディタの親領域
+    // ファイルツリーを表示している場合は、メインエディタ領域の左側をサイドバーにする
+    let editor_area = if app.explorer.visible {
+        const EXPLORER_WIDTH: u16 = 30; // サイドバーの幅
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(EXPLORER_WIDTH.min(main_chunks[1].width / 2)),
+                Constraint::Min(0),
+            ])
+            .split(main_chunks[1]);
+        let list_height = explorer_list_height(app, chunks[0].height.saturating_sub(1)); // 見出しの行を除く
+        app.explorer.adjust_scroll(list_height as usize);
+        render_file_explorer(f, chunks[0], app);
+        chunks[1]
+    } else {
+        main_chunks[1]
+    };
 
-    // Middle Block の描画前にスクロールオフセットを調整
-    // エディタ本体の描画領域を adjust_viewport_offset に渡す
-    app.editor.adjust_viewport_offset(editor_area_chunks[1]);
+    // 差分表示モードでは、メインエディタ領域に保存済みの内容との差分を左右に並べて表示する
+    if app.diff_view.is_some() {
+        app.adjust_diff_view_scroll(editor_area.height.saturating_sub(1)); // 見出しの行を除く
+        render_diff_view(f, editor_area, app);
+    } else {
+        render_panes(f, editor_area, app, !app.explorer.focused);
+    }
 
-    // Left Block の描画
-    render_left_block(f, editor_area_chunks[0], app);
+    // Search Bar と「ファイルを開く」の入力欄、閉じる前の確認の描画（エディタ本体より後に描画し、ネイティブカーソルを入力欄に置く）
+    render_search_bar(f, main_chunks[2], app);
+    render_open_file_bar(f, main_chunks[2], app);
+    render_close_confirm_bar(f, main_chunks[2], app);
 
-    // Editor Block の描画
-    render_editor_block(f, editor_area_chunks[1], app);
+    // Bottom Bar の描画
+    render_bottom_bar(f, main_chunks[3], app);
 
-    // Right Block の描画
-    render_right_block(f, editor_area_chunks[2], app);
+    // ファインダーは画面の中央に重ねて描画する
+    if let Some(finder) = &mut app.finder {
+        finder.adjust_scroll(finder_list_height(size) as usize);
+    }
+    render_fuzzy_finder(f, size, app);
 
-    // Bottom Bar の描画
-    render_bottom_bar(f, main_chunks[2], app);
+    // コマンドパレットも画面の上寄りに重ねて描画する
+    if let Some(palette) = &mut app.palette {
+        palette.adjust_scroll(palette_list_height(size) as usize);
+    }
+    render_command_palette(f, size, app);
+
+    // キー割り当ての一覧は画面の中央に重ねて描画する
+    if let Some(view) = &mut app.keybindings_view {
+        view.set_height(keybindings_list_height(size) as usize);
+    }
+    render_keybindings_view(f, size, app);
 
     // メッセージ通知エリアを計算 (画面全体の右下)
     const MAX_MESSAGE_HEIGHT: u16 = 5; // メッセージ表示の最大行数
@@ -96,4 +137,88 @@ pub fn draw_ui(f: &mut Frame, app: &mut App) {
 
     // メッセージ通知の描画
     render_message_display(f, msg_area, app);
+
+    // 端末で表示できない色を、表示できる最も近い色に置き換える
+    app.color_depth.degrade(f.buffer_mut());
+}
+
+/// メインエディタ領域をペインに分割して描画します。
+/// フォーカスのないペインは、そのペインのファイルと表示位置を一時的にエディタに設定して描画し、
+/// 描画後に元に戻します。フォーカスのあるペインは、ネイティブカーソルを置くため最後に描画します。
+/// ファイルツリーにフォーカスがある場合 (`editor_focused`が`false`) は、ネイティブカーソルを置きません。
+fn render_panes(f: &mut Frame, area: Rect, app: &mut App, editor_focused: bool) {
+    app.windows.layout(area);
+    let split = app.windows.panes.len() > 1;
+    let focused = app.windows.focused;
+    let others: Vec<usize> = app
+        .windows
+        .panes
+        .iter()
+        .map(|pane| pane.id)
+        .filter(|id| *id != focused)
+        .collect();
+
+    for id in others {
+        let pane = app.windows.pane(id).clone();
+        let active_document = app.active_document;
+        app.active_document = pane.document;
+        let focused_view = View::capture(app.editor());
+        pane.view.restore(app.editor_mut());
+
+        render_pane(f, pane.area, app, split, false);
+
+        // 描画時に調整されたスクロール位置をペインに残し、エディタの状態を元に戻す
+        app.windows.pane_mut(id).view = View::capture(app.editor());
+        focused_view.restore(app.editor_mut());
+        app.active_document = active_document;
+    }
+
+    let area = app.windows.pane(focused).area;
+    render_pane(f, area, app, split, editor_focused);
+}
+
+/// 1つのペインを描画します。分割している場合は、上端にファイル名の見出しを付けます。
+fn render_pane(f: &mut Frame, area: Rect, app: &mut App, split: bool, focused: bool) {
+    let regions = PaneRegions::new(area, split, app.settings.gutter_width);
+    if let Some(header) = regions.header {
+        render_pane_header(f, header, app, focused);
+    }
+    render_editor_area(f, regions, app, focused);
+}
+
+/// メインエディタ領域（行番号・エディタ本体・スクロールバー）を描画します。
+fn render_editor_area(f: &mut Frame, regions: PaneRegions, app: &mut App, focused: bool) {
+    // Middle Block の描画前にスクロールオフセットを調整
+    // エディタ本体の描画領域を adjust_viewport_offset に渡す
+    // カーソルと端の間に空ける幅は、このペインのファイルの言語の設定に従う
+    let settings = app.language_settings();
+    app.editor_mut().adjust_viewport_offset(
+        regions.text,
+        settings.scroll_padding_x,
+        settings.scroll_padding_y,
+    );
+
+    // 編集によって古くなった構文木を、描画前に再解析する
+    app.editor_mut().update_syntax();
+
+    // 表示する画面上の行（折り返し表示ではバッファの1行が複数行になる）を、行番号とエディタ本体で共有する
+    let rows = app.editor().screen_rows(
+        regions.text.width,
+        regions.text.height,
+        app.word_wrap_enabled,
+    );
+
+    // Left Block の描画
+    render_left_block(f, regions.gutter, app, &rows);
+
+    // Editor Block の描画
+    render_editor_block(f, regions.text, app, &rows, focused);
+
+    // Right Block の描画
+    render_right_block(f, regions.scrollbar, app);
+
+    // 変更箇所のプレビューをエディタ本体の上に重ねて描画（フォーカスのあるペインのみ）
+    if focused {
+        render_hunk_preview(f, regions.text, app, &rows);
+    }
 }